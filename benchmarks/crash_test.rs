@@ -0,0 +1,354 @@
+use tokio::net::TcpStream;
+use tokio::io::{AsyncWriteExt, AsyncReadExt};
+use tokio::process::{Child, Command};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, Duration};
+use vortex_rpc::{RequestHeader, SearchRequest, SearchHit, UpsertRequest, GetRequest, VBP_VERSION_1, OP_UPSERT, OP_GET, OP_SEARCH, STATUS_OK, STATUS_NOT_FOUND};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use clap::Parser;
+
+/// Crash-consistency harness: SIGKILLs `vortex-server` in the middle of an upsert
+/// stream, restarts it and audits "Persistence Precedes Response":
+/// every acknowledged write must come back whole and searchable, and a write
+/// that was never acknowledged must be either absent or complete.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path to the vortex-server binary
+    #[arg(long, default_value = "target/release/vortex-server")]
+    server: String,
+
+    /// Data directory (tmpfs or loop mount). Wiped before the first round.
+    #[arg(short, long)]
+    dir: String,
+
+    #[arg(short, long, default_value_t = 9100)]
+    port: u16,
+
+    /// Kill/restart cycles
+    #[arg(short, long, default_value_t = 20)]
+    rounds: usize,
+
+    /// Upper bound of upserts sent per round
+    #[arg(long, default_value_t = 20000)]
+    per_round: u64,
+
+    #[arg(long, default_value_t = 32)]
+    dimension: usize,
+
+    /// SIGKILL lands at a random point in [min_kill_ms, max_kill_ms] after the load starts
+    #[arg(long, default_value_t = 20)]
+    min_kill_ms: u64,
+
+    #[arg(long, default_value_t = 400)]
+    max_kill_ms: u64,
+
+    /// RNG seed, to replay a failing schedule
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+/// Acknowledged IDs that are sampled with a k-NN search on every audit.
+const SEARCH_SAMPLE: usize = 200;
+
+/// Time allowed for boot plus WAL replay before a restart counts as failed.
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Everything one audit found wrong.
+#[derive(Default)]
+struct Audit {
+    /// Acknowledged but missing after recovery
+    lost: Vec<u64>,
+    /// Present with a different vector (half-applied or corrupted)
+    torn: Vec<u64>,
+    /// Never sent, yet present
+    phantom: Vec<u64>,
+    /// Present but not returned by a search for their own vector
+    unsearchable: Vec<u64>,
+}
+
+impl Audit {
+    fn is_clean(&self) -> bool {
+        self.lost.is_empty() && self.torn.is_empty() && self.phantom.is_empty() && self.unsearchable.is_empty()
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let addr = format!("127.0.0.1:{}", args.port);
+
+    println!("--- VORTEX CRASH-CONSISTENCY HARNESS ---");
+    println!("Server:       {}", args.server);
+    println!("Data Dir:     {}", args.dir);
+    println!("Rounds:       {}", args.rounds);
+    println!("Kill Window:  {}-{} ms", args.min_kill_ms, args.max_kill_ms);
+    println!("Seed:         {}", seed);
+    println!("----------------------------------------\n");
+
+    let _ = std::fs::remove_dir_all(&args.dir);
+    std::fs::create_dir_all(&args.dir)?;
+
+    // IDs whose ACK the client saw (or which recovery proved durable)
+    let mut durable: Vec<u64> = Vec::new();
+    // IDs sent in the last round without an ACK
+    let mut in_doubt: Vec<u64> = Vec::new();
+    let mut next_id = 0u64;
+    let mut failed_rounds = 0;
+    let global_start = Instant::now();
+
+    for round in 0..=args.rounds {
+        let mut server = spawn_server(&args)?;
+        wait_ready(&mut server, &addr).await?;
+
+        // 1. Audit what survived the previous kill
+        let (audit, promoted) = audit(&addr, args.dimension, &durable, &in_doubt, next_id, &mut rng).await?;
+        durable.extend(&promoted);
+        report(round, &audit, durable.len(), promoted.len(), in_doubt.len() - promoted.len());
+        if !audit.is_clean() {
+            failed_rounds += 1;
+        }
+        if round == args.rounds {
+            server.kill().await?;
+            break;
+        }
+
+        // 2. Drive upserts and pull the plug at a random point
+        let kill_after = Duration::from_millis(rng.gen_range(args.min_kill_ms..=args.max_kill_ms));
+        let (sent, acked) = load_then_kill(&mut server, &addr, args.dimension, next_id, args.per_round, kill_after).await?;
+        let acked_set: std::collections::HashSet<u64> = acked.iter().copied().collect();
+        in_doubt = (next_id..next_id + sent).filter(|id| !acked_set.contains(id)).collect();
+        durable.extend(acked);
+        next_id += sent;
+    }
+
+    let total_time = global_start.elapsed();
+    println!("\n==================================================");
+    println!("        VORTEX CRASH-CONSISTENCY RECEIPT          ");
+    println!("==================================================");
+    let status = if failed_rounds == 0 { "PASS" } else { "FAIL" };
+    println!(" Status:       {}", status);
+    println!(" Kills:        {}", args.rounds);
+    println!(" Upserts Sent: {}", next_id);
+    println!(" Durable IDs:  {}", durable.len());
+    println!(" Bad Audits:   {}", failed_rounds);
+    println!(" Wall Clock:   {:.2?}", total_time);
+    println!(" Seed:         {}", seed);
+    println!("==================================================\n");
+
+    if failed_rounds > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn spawn_server(args: &Args) -> std::io::Result<Child> {
+    // One shard, so every connection sees every ID. L2 makes a vector its own nearest neighbour.
+    let capacity = (args.rounds as u64 + 1) * args.per_round;
+    Command::new(&args.server)
+        .arg("--dir").arg(&args.dir)
+        .arg("--port").arg(args.port.to_string())
+        .arg("--shards").arg("1")
+        .arg("--capacity").arg(capacity.to_string())
+        .arg("--dimension").arg(args.dimension.to_string())
+        .arg("--metric").arg("l2")
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+}
+
+/// Waits until the server answers requests, i.e. WAL replay has finished.
+///
+/// A bare connect is not enough: the killed process's listener can outlive it
+/// for a moment (its io_uring teardown is asynchronous) and accept, then reset.
+async fn wait_ready(server: &mut Child, addr: &str) -> std::io::Result<()> {
+    let deadline = Instant::now() + READY_TIMEOUT;
+    while Instant::now() < deadline {
+        if let Some(status) = server.try_wait()? {
+            return Err(std::io::Error::other(format!("vortex-server exited during recovery: {}", status)));
+        }
+        let probe = frame(OP_GET, u64::MAX, &GetRequest { id: u64::MAX }.encode());
+        if exchange(addr, probe, 1).await.is_ok() {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "vortex-server did not come back online"))
+}
+
+/// Streams upserts starting at `first_id` and SIGKILLs the server after `kill_after`.
+/// Returns how many upserts were sent and the IDs whose ACK arrived before the kill.
+async fn load_then_kill(server: &mut Child, addr: &str, dimension: usize, first_id: u64, limit: u64, kill_after: Duration) -> std::io::Result<(u64, Vec<u64>)> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    let (mut reader, mut writer) = stream.into_split();
+
+    let sent = Arc::new(Mutex::new(0u64));
+    let sent_ref = sent.clone();
+    let writer_handle = tokio::spawn(async move {
+        let mut chunk = Vec::new();
+        for id in first_id..first_id + limit {
+            chunk.extend_from_slice(&frame(OP_UPSERT, id, &UpsertRequest { id }.encode(&vector_for(id, dimension))));
+            // Count an upsert as sent before it reaches the socket: a partial write is still in doubt
+            if let Ok(mut sent) = sent_ref.lock() {
+                *sent += 1;
+            }
+            if chunk.len() >= 16 * 1024 {
+                if writer.write_all(&chunk).await.is_err() { return; }
+                chunk.clear();
+            }
+        }
+        let _ = writer.write_all(&chunk).await;
+    });
+
+    let reader_handle = tokio::spawn(async move {
+        let mut acked = Vec::new();
+        let mut header = [0u8; 16];
+        let mut payload = Vec::new();
+        while reader.read_exact(&mut header).await.is_ok() {
+            let (status, request_id, len) = parse_response(&header);
+            payload.resize(len, 0);
+            if reader.read_exact(&mut payload).await.is_err() { break; }
+            if status == STATUS_OK {
+                acked.push(request_id);
+            }
+        }
+        acked
+    });
+
+    tokio::time::sleep(kill_after).await;
+    // tokio sends SIGKILL: no drain, no final flush
+    server.kill().await?;
+
+    let _ = writer_handle.await;
+    let acked = reader_handle.await.unwrap_or_default();
+    let sent = *sent.lock().map_err(|_| std::io::Error::other("writer panicked"))?;
+    Ok((sent, acked))
+}
+
+/// Checks the recovered server against the client's view. Returns the findings and
+/// the in-doubt IDs that turned out to be durable.
+async fn audit(addr: &str, dimension: usize, durable: &[u64], in_doubt: &[u64], next_id: u64, rng: &mut StdRng) -> std::io::Result<(Audit, Vec<u64>)> {
+    let mut audit = Audit::default();
+    let mut promoted = Vec::new();
+
+    // Every acknowledged ID must be back with exactly the vector that was sent
+    let never_sent: Vec<u64> = (next_id..next_id + 16).collect();
+    let ids: Vec<u64> = durable.iter().chain(in_doubt).chain(&never_sent).copied().collect();
+    let mut requests = Vec::new();
+    for &id in &ids {
+        requests.extend_from_slice(&frame(OP_GET, id, &GetRequest { id }.encode()));
+    }
+    let responses = exchange(addr, requests, ids.len()).await?;
+
+    let mut stored = vec![0.0f32; dimension];
+    for (i, &id) in ids.iter().enumerate() {
+        let present = match responses.get(&id) {
+            Some((STATUS_NOT_FOUND, _)) => false,
+            Some((STATUS_OK, payload)) if vortex_rpc::decode_f32s(payload, &mut stored) == dimension
+                && stored == vector_for(id, dimension) => true,
+            _ => {
+                audit.torn.push(id);
+                continue;
+            }
+        };
+        if i < durable.len() {
+            if !present {
+                audit.lost.push(id);
+            }
+        } else if i < durable.len() + in_doubt.len() {
+            if present {
+                promoted.push(id);
+            }
+        } else if present {
+            audit.phantom.push(id);
+        }
+    }
+
+    // A sample of durable IDs must also be reachable through the graph
+    let sample: Vec<u64> = (0..SEARCH_SAMPLE.min(durable.len())).map(|_| durable[rng.gen_range(0..durable.len())]).collect();
+    let mut requests = Vec::new();
+    for (i, &id) in sample.iter().enumerate() {
+        let payload = SearchRequest { top_k: 10, ef_search: 256 }.encode(&vector_for(id, dimension));
+        requests.extend_from_slice(&frame(OP_SEARCH, i as u64, &payload));
+    }
+    let responses = exchange(addr, requests, sample.len()).await?;
+    for (i, &id) in sample.iter().enumerate() {
+        let found = responses.get(&(i as u64))
+            .and_then(|(_, payload)| SearchHit::decode_all(payload).ok())
+            .is_some_and(|hits| hits.iter().any(|hit| hit.id == id));
+        if !found {
+            audit.unsearchable.push(id);
+        }
+    }
+
+    Ok((audit, promoted))
+}
+
+/// Pipelines `requests` on a fresh connection and collects `expected` responses by request ID.
+async fn exchange(addr: &str, requests: Vec<u8>, expected: usize) -> std::io::Result<HashMap<u64, (u8, Vec<u8>)>> {
+    let stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+    let (mut reader, mut writer) = stream.into_split();
+    let writer_handle = tokio::spawn(async move { writer.write_all(&requests).await });
+
+    let mut responses = HashMap::with_capacity(expected);
+    let mut header = [0u8; 16];
+    for _ in 0..expected {
+        tokio::time::timeout(Duration::from_secs(10), reader.read_exact(&mut header)).await
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "audit response timed out"))??;
+        let (status, request_id, len) = parse_response(&header);
+        let mut payload = vec![0u8; len];
+        reader.read_exact(&mut payload).await?;
+        responses.insert(request_id, (status, payload));
+    }
+    writer_handle.await.map_err(std::io::Error::other)??;
+    Ok(responses)
+}
+
+fn report(round: usize, audit: &Audit, durable: usize, promoted: usize, dropped: usize) {
+    let verdict = if audit.is_clean() { "OK" } else { "FAIL" };
+    println!("[ROUND {:>3}] {:<4} durable={} in-doubt kept={} in-doubt absent={} lost={} torn={} phantom={} unsearchable={}",
+        round, verdict, durable, promoted, dropped, audit.lost.len(), audit.torn.len(), audit.phantom.len(), audit.unsearchable.len());
+    for (label, ids) in [("lost", &audit.lost), ("torn", &audit.torn), ("phantom", &audit.phantom), ("unsearchable", &audit.unsearchable)] {
+        if !ids.is_empty() {
+            println!("            {} (first 10): {:?}", label, &ids[..ids.len().min(10)]);
+        }
+    }
+}
+
+/// Deterministic vector of `id`, so an audit can recompute what was sent.
+fn vector_for(id: u64, dimension: usize) -> Vec<f32> {
+    // SplitMix64 stream seeded by the ID
+    let mut state = id.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    (0..dimension).map(|_| {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    }).collect()
+}
+
+fn frame(opcode: u8, request_id: u64, payload: &[u8]) -> Vec<u8> {
+    let header = RequestHeader::new(VBP_VERSION_1, opcode, payload.len() as u32, request_id);
+    let mut packet = Vec::with_capacity(RequestHeader::SIZE + payload.len());
+    packet.extend_from_slice(&header.to_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+/// Splits a response header into (status, request_id, payload_len).
+fn parse_response(header: &[u8; 16]) -> (u8, u64, usize) {
+    let status = header[2];
+    let payload_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let request_id = u64::from_le_bytes(header[8..16].try_into().unwrap_or([0; 8]));
+    (status, request_id, payload_len)
+}
@@ -4,7 +4,7 @@ use std::time::{Instant, Duration};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::sync::Barrier;
-use vortex_rpc::{RequestHeader, VBP_MAGIC, OP_UPSERT};
+use vortex_rpc::{RequestHeader, SearchRequest, VBP_VERSION_1, OP_UPSERT};
 // use rand::Rng;
 use clap::Parser;
 
@@ -102,19 +102,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 for i in 0..reqs_per_task {
                     let id = (task_id * reqs_per_task + i) as u64;
                     let opcode = if is_search { OP_SEARCH } else { OP_UPSERT };
-                    let payload_len = if is_search { DIMENSION * 4 } else { 8 + (DIMENSION * 4) };
+                    let payload_len = if is_search { SearchRequest::SIZE + DIMENSION * 4 } else { 8 + (DIMENSION * 4) };
                     
                     let mut packet = vec![0u8; 16 + payload_len];
-                    let header = RequestHeader {
-                        magic: VBP_MAGIC, version: 1, opcode,
-                        payload_len: payload_len as u32, request_id: id,
-                    };
-
-                    unsafe {
-                        std::ptr::copy_nonoverlapping(&header as *const _ as *const u8, packet.as_mut_ptr(), 16);
-                    }
+                    let header = RequestHeader::new(VBP_VERSION_1, opcode, payload_len as u32, id);
+                    packet[..16].copy_from_slice(&header.to_bytes());
                     
-                    if !is_search {
+                    if is_search {
+                        let search = SearchRequest { top_k: 10, ef_search: 0 }.encode(&[0.0; DIMENSION]);
+                        packet[16..].copy_from_slice(&search);
+                    } else {
                         packet[16..24].copy_from_slice(&id.to_le_bytes());
                     }
                     
@@ -125,16 +122,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             let mut acks_received = 0;
             let mut buffer = [0u8; 16]; 
+            let mut result_buffer = Vec::new();
             while acks_received < reqs_per_task {
                 let start = Instant::now();
                 match tokio::time::timeout(Duration::from_secs(10), reader.read_exact(&mut buffer)).await {
                     Ok(Ok(_)) => {
+                        // Drain the search hits (if any) so the next header stays framed
+                        let result_len = u32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]) as usize;
+                        if result_len > 0 {
+                            result_buffer.resize(result_len, 0);
+                            if reader.read_exact(&mut result_buffer).await.is_err() { break; }
+                        }
                         let lat = start.elapsed();
                         latencies.push(lat);
                         stats_task.record(lat);
                         acks_received += 1;
                         let total = acks_ref.fetch_add(1, Ordering::Relaxed) + 1;
-                        if total % 10000 == 0 {
+                        if total.is_multiple_of(10000) {
                             println!("[PROGRESS] {:>6} / {} ACKs received...", total, total_requests);
                         }
                     },
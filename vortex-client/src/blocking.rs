@@ -0,0 +1,281 @@
+//! Blocking VBP client over `std::net`.
+
+use crate::codec::{Request, Response, HELLO_REQUEST_ID};
+use crate::{ClientConfig, CollectionInfo, Error, Metadata, Result, SearchHit, SearchOptions, StoredVector};
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use vortex_rpc::{HelloResponse, ResponseHeader};
+
+/// Blocking handle to a VORTEX server.
+///
+/// Cheap to clone and safe to share between threads: every call checks a connection
+/// out of a shared pool and returns it once the exchange is complete.
+#[derive(Clone)]
+pub struct Client {
+    pool: Arc<Pool>,
+    collection: Option<u32>,
+}
+
+impl Client {
+    /// Connects to `addr` with the default `ClientConfig`.
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self> {
+        Self::connect_with(addr, ClientConfig::default())
+    }
+
+    /// Connects to `addr`. One connection is opened up front so a bad address, or a
+    /// server without a protocol version in common, fails here.
+    pub fn connect_with(addr: impl ToSocketAddrs, config: ClientConfig) -> Result<Self> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        let (first, server) = Connection::open(&addrs, &config)?;
+        let pool = Pool { addrs, config, server, idle: Mutex::new(vec![first]) };
+        Ok(Self { pool: Arc::new(pool), collection: None })
+    }
+
+    /// What the server reported in the handshake of the first connection.
+    pub fn server_info(&self) -> &HelloResponse {
+        &self.pool.server
+    }
+
+    /// A handle addressing `collection_id` (protocol v2), sharing this client's connections.
+    pub fn collection(&self, collection_id: u32) -> Self {
+        Self { pool: self.pool.clone(), collection: Some(collection_id) }
+    }
+
+    /// Inserts or replaces `id`. Returns once the record is durable in the WAL.
+    pub fn upsert(&self, id: u64, vector: &[f32]) -> Result<()> {
+        self.call(Request::upsert(self.collection, id, vector, None))?.into_ok().map(drop)
+    }
+
+    /// Inserts or replaces `id` with `metadata` attached.
+    pub fn upsert_with_metadata(&self, id: u64, vector: &[f32], metadata: &Metadata) -> Result<()> {
+        self.call(Request::upsert(self.collection, id, vector, Some(metadata)))?.into_ok().map(drop)
+    }
+
+    /// Pipelines every upsert on one connection, keeping up to `pipeline_depth` in flight.
+    ///
+    /// # Errors
+    /// Every record is sent even if some are rejected; the first rejection is returned.
+    pub fn upsert_batch<'a>(&self, records: impl IntoIterator<Item = (u64, &'a [f32])>) -> Result<()> {
+        self.upsert_batch_with_metadata(records.into_iter().map(|(id, vector)| (id, vector, None)))
+    }
+
+    /// `upsert_batch` for records that may carry metadata.
+    pub fn upsert_batch_with_metadata<'a>(&self, records: impl IntoIterator<Item = (u64, &'a [f32], Option<&'a Metadata>)>) -> Result<()> {
+        let collection = self.collection;
+        let requests = records.into_iter().map(|(id, vector, metadata)| Request::upsert(collection, id, vector, metadata));
+        let mut first_error = None;
+        self.pool.with_connection(|conn| conn.pipeline(requests, |response| {
+            if let Err(e) = response.into_ok() {
+                first_error.get_or_insert(e);
+            }
+        }))?;
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Returns the `options.top_k` nearest neighbors of `query`, nearest first.
+    pub fn search(&self, query: &[f32], options: &SearchOptions) -> Result<Vec<SearchHit>> {
+        self.call(Request::search(self.collection, query, options))?.into_hits()
+    }
+
+    /// Fetches the stored vector of `id` and its metadata, or `None` if it is not live.
+    pub fn get(&self, id: u64) -> Result<Option<StoredVector>> {
+        let request = Request::get(self.collection, id, self.pool.server.version);
+        let version = request.version();
+        self.call(request)?.into_vector(version)
+    }
+
+    /// Tombstones `id`. Deleting an absent ID succeeds.
+    pub fn delete(&self, id: u64) -> Result<()> {
+        self.call(Request::delete(self.collection, id))?.into_ok().map(drop)
+    }
+
+    /// Lists every collection in the server catalog.
+    pub fn list_collections(&self) -> Result<Vec<CollectionInfo>> {
+        self.call(Request::list_collections())?.into_collections()
+    }
+
+    fn call(&self, request: Request) -> Result<Response> {
+        let mut answer = None;
+        self.pool.with_connection(|conn| conn.pipeline(std::iter::once(request), |response| answer = Some(response)))?;
+        answer.ok_or(Error::Closed)
+    }
+}
+
+/// Idle connections waiting for the next call.
+struct Pool {
+    addrs: Vec<SocketAddr>,
+    config: ClientConfig,
+    server: HelloResponse,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl Pool {
+    /// Runs `exchange` on an idle connection (or a new one) and keeps the connection
+    /// only if the stream is still framed afterwards.
+    fn with_connection<T>(&self, exchange: impl FnOnce(&mut Connection) -> Result<T>) -> Result<T> {
+        let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        let mut conn = match idle {
+            Some(conn) => conn,
+            None => Connection::open(&self.addrs, &self.config)?.0,
+        };
+        let result = exchange(&mut conn);
+        if result.is_ok() {
+            let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+            if idle.len() < self.config.connections {
+                idle.push(conn);
+            }
+        }
+        result
+    }
+}
+
+/// One TCP stream and the request IDs issued on it.
+struct Connection {
+    stream: TcpStream,
+    next_request_id: u64,
+    pipeline_depth: usize,
+    frames: Vec<u8>,
+}
+
+impl Connection {
+    /// Connects and runs the handshake, returning what the server reported.
+    fn open(addrs: &[SocketAddr], config: &ClientConfig) -> Result<(Self, HelloResponse)> {
+        let mut last_error = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(addr, config.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_nodelay(true)?;
+                    stream.set_read_timeout(config.request_timeout)?;
+                    stream.set_write_timeout(config.request_timeout)?;
+                    let mut conn = Self { stream, next_request_id: HELLO_REQUEST_ID + 1, pipeline_depth: config.pipeline_depth, frames: Vec::new() };
+                    let server = conn.handshake()?;
+                    return Ok((conn, server));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| std::io::Error::new(ErrorKind::InvalidInput, "Address resolved to nothing")).into())
+    }
+
+    /// Sends `requests` with at most `pipeline_depth` in flight and hands every response
+    /// to `on_response`. Responses may arrive in any order; each is matched by request ID.
+    fn pipeline(&mut self, requests: impl Iterator<Item = Request>, mut on_response: impl FnMut(Response)) -> Result<()> {
+        let mut requests = requests.peekable();
+        let mut in_flight: HashMap<u64, u8> = HashMap::new();
+        loop {
+            // Top the window up in a single write
+            self.frames.clear();
+            while in_flight.len() < self.pipeline_depth {
+                let Some(request) = requests.next() else { break };
+                let request_id = self.next_request_id;
+                self.next_request_id += 1;
+                request.encode(request_id, &mut self.frames);
+                in_flight.insert(request_id, request.opcode());
+            }
+            if !self.frames.is_empty() {
+                self.stream.write_all(&self.frames).map_err(io_error)?;
+            }
+            if in_flight.is_empty() {
+                return Ok(());
+            }
+
+            // Drain to half the window before refilling, so writes stay batched
+            let low_water = if requests.peek().is_some() { self.pipeline_depth / 2 } else { 0 };
+            while in_flight.len() > low_water {
+                let response = self.read_response()?;
+                let opcode = in_flight.remove(&response.request_id)
+                    .ok_or(Error::Protocol("Response carries an unknown request ID"))?;
+                on_response(response.expect_opcode(opcode)?);
+            }
+        }
+    }
+
+    /// Negotiates the protocol version before any other request is sent.
+    fn handshake(&mut self) -> Result<HelloResponse> {
+        self.frames.clear();
+        Request::hello().encode(HELLO_REQUEST_ID, &mut self.frames);
+        self.stream.write_all(&self.frames).map_err(io_error)?;
+        self.read_response()?.into_hello()
+    }
+
+    fn read_response(&mut self) -> Result<Response> {
+        let mut raw = [0u8; ResponseHeader::SIZE];
+        self.stream.read_exact(&mut raw).map_err(io_error)?;
+        let (header, len) = Response::parse_header(&raw)?;
+        let mut payload = vec![0u8; len];
+        self.stream.read_exact(&mut payload).map_err(io_error)?;
+        Ok(Response::new(&header, payload))
+    }
+}
+
+/// Maps socket timeouts and EOF onto their typed errors.
+fn io_error(e: std::io::Error) -> Error {
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => Error::Timeout,
+        ErrorKind::UnexpectedEof => Error::Closed,
+        _ => Error::Io(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{basis_vector, TestServer};
+    use vortex_rpc::{OP_UPSERT, STATUS_DIMENSION_MISMATCH, STATUS_UNKNOWN_COLLECTION};
+
+    #[test]
+    fn test_round_trip_of_every_operation() {
+        let server = TestServer::start("client_blocking_ops", 8);
+        let client = Client::connect(("127.0.0.1", server.port)).unwrap();
+        assert_eq!((client.server_info().version, client.server_info().dimension), (vortex_rpc::VBP_VERSION_MAX, 8));
+
+        client.upsert(7, &basis_vector(8, 1)).unwrap();
+        client.upsert_with_metadata(9, &basis_vector(8, 2), &Metadata::new().keyword("region", "eu")).unwrap();
+        assert_eq!(client.get(7).unwrap(), Some(StoredVector { vector: basis_vector(8, 1), metadata: None }));
+        assert_eq!(client.get(8).unwrap(), None);
+
+        let hits = client.search(&basis_vector(8, 2), &SearchOptions::new(1)).unwrap();
+        assert_eq!(hits[0].id, 9);
+        let filtered = client.search(&basis_vector(8, 2), &SearchOptions::new(2).with_filter(crate::Filter::eq("region", "us"))).unwrap();
+        assert!(filtered.is_empty());
+
+        client.delete(7).unwrap();
+        assert_eq!(client.get(7).unwrap(), None);
+
+        // Rejections are typed and leave the connection usable
+        match client.upsert(1, &[1.0; 3]) {
+            Err(e @ Error::Status { opcode: OP_UPSERT, status: STATUS_DIMENSION_MISMATCH, .. }) => {
+                assert!(!e.is_retryable());
+                assert_eq!(e.to_string(), "Server rejected opcode 1: dimension mismatch (Vector dimension mismatch)");
+            }
+            other => panic!("expected a dimension mismatch, got {:?}", other),
+        }
+        match client.collection(42).get(9) {
+            Err(Error::Status { status: STATUS_UNKNOWN_COLLECTION, .. }) => {}
+            other => panic!("expected an unknown collection, got {:?}", other),
+        }
+        let stored = client.get(9).unwrap().unwrap();
+        assert_eq!((stored.vector, stored.metadata), (basis_vector(8, 2), Some(Metadata::new().keyword("region", "eu"))));
+    }
+
+    #[test]
+    fn test_upsert_batch_pipelines_past_the_window() {
+        let server = TestServer::start("client_blocking_batch", 8);
+        let config = ClientConfig::default().with_pipeline_depth(16);
+        let client = Client::connect_with(("127.0.0.1", server.port), config).unwrap();
+
+        let vectors: Vec<Vec<f32>> = (0..300).map(|i| basis_vector(8, i % 8)).collect();
+        client.upsert_batch(vectors.iter().enumerate().map(|(i, v)| (i as u64, v.as_slice()))).unwrap();
+        for (i, vector) in vectors.iter().enumerate() {
+            assert_eq!(client.get(i as u64).unwrap().map(|stored| stored.vector).as_ref(), Some(vector));
+        }
+
+        // A bad record in the middle fails the batch, but its neighbors still land
+        let short = [1.0f32; 3];
+        let records = [(500, vectors[0].as_slice()), (501, &short[..]), (502, vectors[1].as_slice())];
+        assert!(matches!(client.upsert_batch(records), Err(Error::Status { status: STATUS_DIMENSION_MISMATCH, .. })));
+        assert!(client.get(500).unwrap().is_some() && client.get(502).unwrap().is_some());
+    }
+}
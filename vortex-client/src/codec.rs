@@ -0,0 +1,153 @@
+use crate::{Error, Result, SearchOptions};
+use vortex_rpc::{
+    CollectionInfo, CollectionRef, DeleteRequest, StoredVector, DEFAULT_COLLECTION, GetRequest, HelloRequest, HelloResponse, Metadata, RequestHeader, ResponseHeader, SearchHit,
+    SearchRequest, UpsertRequest, OP_DELETE, OP_GET, OP_HELLO, OP_LIST_COLLECTIONS, OP_SEARCH, OP_UPSERT, STATUS_NOT_FOUND, STATUS_OK,
+    VBP_VERSION_1, VBP_VERSION_2,
+};
+
+/// Largest response payload accepted from the server: one 64KB response page.
+/// Anything longer means the stream lost its framing.
+pub const MAX_RESPONSE_PAYLOAD: usize = 65536;
+
+/// Request ID of the handshake, sent before any other request on a connection.
+pub(crate) const HELLO_REQUEST_ID: u64 = 0;
+
+/// A request frame that has not been given its request ID yet.
+pub(crate) struct Request {
+    version: u8,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+impl Request {
+    /// Addresses `body` to `collection`, or to the default collection (protocol v1) if `None`.
+    fn data(collection: Option<u32>, opcode: u8, body: Vec<u8>) -> Self {
+        match collection {
+            None => Self { version: VBP_VERSION_1, opcode, payload: body },
+            Some(collection_id) => Self {
+                version: VBP_VERSION_2,
+                opcode,
+                payload: CollectionRef { collection_id, reserved: 0 }.encode(&body),
+            },
+        }
+    }
+
+    pub fn upsert(collection: Option<u32>, id: u64, vector: &[f32], metadata: Option<&Metadata>) -> Self {
+        let request = UpsertRequest { id };
+        let body = match metadata {
+            Some(metadata) => request.encode_with_metadata(vector, metadata),
+            None => request.encode(vector),
+        };
+        Self::data(collection, OP_UPSERT, body)
+    }
+
+    pub fn search(collection: Option<u32>, query: &[f32], options: &SearchOptions) -> Self {
+        let request = SearchRequest { top_k: options.top_k, ef_search: options.ef_search };
+        let body = match &options.filter {
+            Some(filter) => request.encode_with_filter(query, filter),
+            None => request.encode(query),
+        };
+        Self::data(collection, OP_SEARCH, body)
+    }
+
+    /// Uses protocol v2 whenever `server_version` allows it, so the answer carries the metadata.
+    pub fn get(collection: Option<u32>, id: u64, server_version: u8) -> Self {
+        let collection = collection.or((server_version >= VBP_VERSION_2).then_some(DEFAULT_COLLECTION));
+        Self::data(collection, OP_GET, GetRequest { id }.encode())
+    }
+
+    pub fn delete(collection: Option<u32>, id: u64) -> Self {
+        Self::data(collection, OP_DELETE, DeleteRequest { id }.encode())
+    }
+
+    pub fn list_collections() -> Self {
+        Self { version: VBP_VERSION_1, opcode: OP_LIST_COLLECTIONS, payload: Vec::new() }
+    }
+
+    /// Offers every version this build speaks.
+    pub fn hello() -> Self {
+        Self { version: VBP_VERSION_1, opcode: OP_HELLO, payload: HelloRequest::current().encode() }
+    }
+
+    pub fn opcode(&self) -> u8 {
+        self.opcode
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Appends the full frame, tagged with `request_id`, to `out`.
+    pub fn encode(&self, request_id: u64, out: &mut Vec<u8>) {
+        let header = RequestHeader::new(self.version, self.opcode, self.payload.len() as u32, request_id);
+        out.extend_from_slice(&header.to_bytes());
+        out.extend_from_slice(&self.payload);
+    }
+}
+
+/// A response frame matched to its request.
+pub(crate) struct Response {
+    pub status: u8,
+    pub opcode: u8,
+    pub request_id: u64,
+    pub payload: Vec<u8>,
+}
+
+impl Response {
+    /// Validates a response header and returns it with the payload length still to read.
+    pub fn parse_header(bytes: &[u8; ResponseHeader::SIZE]) -> Result<(ResponseHeader, usize)> {
+        let header = ResponseHeader::parse(bytes).map_err(Error::Protocol)?;
+        let len = header.payload_len as usize;
+        if len > MAX_RESPONSE_PAYLOAD {
+            return Err(Error::Protocol("Response payload exceeds one response page"));
+        }
+        Ok((header, len))
+    }
+
+    pub fn new(header: &ResponseHeader, payload: Vec<u8>) -> Self {
+        Self { status: header.status, opcode: header.opcode, request_id: header.request_id, payload }
+    }
+
+    /// Checks the response answers a request with `opcode`. Connection-level refusals
+    /// (opcode 0, such as `STATUS_BUSY`) pass through to surface as a status error.
+    pub fn expect_opcode(self, opcode: u8) -> Result<Self> {
+        if self.opcode != opcode && !(self.opcode == 0 && self.status != STATUS_OK) {
+            return Err(Error::Protocol("Response opcode does not match its request"));
+        }
+        Ok(self)
+    }
+
+    /// Returns the payload of a `STATUS_OK` response.
+    pub fn into_ok(self) -> Result<Vec<u8>> {
+        if self.status != STATUS_OK {
+            let message = vortex_rpc::status_message(&self.payload).map_err(Error::Protocol)?;
+            return Err(Error::Status { opcode: self.opcode, status: self.status, message: message.map(str::to_owned) });
+        }
+        Ok(self.payload)
+    }
+
+    pub fn into_hits(self) -> Result<Vec<SearchHit>> {
+        SearchHit::decode_all(&self.into_ok()?).map_err(Error::Protocol)
+    }
+
+    pub fn into_collections(self) -> Result<Vec<CollectionInfo>> {
+        CollectionInfo::decode_all(&self.into_ok()?).map_err(Error::Protocol)
+    }
+
+    /// Decodes the answer to the handshake.
+    pub fn into_hello(self) -> Result<HelloResponse> {
+        if self.request_id != HELLO_REQUEST_ID {
+            return Err(Error::Protocol("Response arrived before the handshake completed"));
+        }
+        HelloResponse::parse(&self.expect_opcode(OP_HELLO)?.into_ok()?).map_err(Error::Protocol)
+    }
+
+    /// Decodes the answer to an `OP_GET` sent with protocol `version`.
+    /// `STATUS_NOT_FOUND` is `None`, not an error.
+    pub fn into_vector(self, version: u8) -> Result<Option<StoredVector>> {
+        if self.status == STATUS_NOT_FOUND {
+            return Ok(None);
+        }
+        StoredVector::parse(&self.into_ok()?, version).map(Some).map_err(Error::Protocol)
+    }
+}
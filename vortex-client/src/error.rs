@@ -0,0 +1,63 @@
+use thiserror::Error;
+use vortex_rpc::{
+    STATUS_BAD_MAGIC, STATUS_BUSY, STATUS_CAPACITY_FULL, STATUS_DIMENSION_MISMATCH, STATUS_ERR, STATUS_FRAME_TOO_LARGE, STATUS_NOT_FOUND,
+    STATUS_STORAGE, STATUS_UNKNOWN_COLLECTION, STATUS_UNKNOWN_OPCODE, STATUS_UNSUPPORTED_VERSION,
+};
+
+/// Everything a VBP call can fail with.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The connection could not be opened, or failed while a request was on it.
+    #[error("Connection I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    /// The server answered the request with a non-OK status, and possibly a reason.
+    #[error("Server rejected opcode {opcode}: {}{}", status_name(*.status), reason(.message))]
+    Status { opcode: u8, status: u8, message: Option<String> },
+    /// The server sent bytes that are not a valid VBP response.
+    #[error("Protocol violation: {0}")]
+    Protocol(&'static str),
+    /// No response arrived within the configured request timeout.
+    #[error("Request timed out")]
+    Timeout,
+    /// The connection closed before the response arrived.
+    #[error("Connection closed")]
+    Closed,
+}
+
+impl Error {
+    /// Whether sending the same request again may succeed. Transport failures are
+    /// retryable (every VBP write is idempotent); server statuses follow `vortex_rpc::is_retryable`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Status { status, .. } => vortex_rpc::is_retryable(*status),
+            Error::Io(_) | Error::Timeout | Error::Closed => true,
+            Error::Protocol(_) => false,
+        }
+    }
+}
+
+/// Result of a VBP call.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Human-readable name of a `STATUS_*` code.
+pub fn status_name(status: u8) -> &'static str {
+    match status {
+        STATUS_ERR => "error",
+        STATUS_NOT_FOUND => "not found",
+        STATUS_DIMENSION_MISMATCH => "dimension mismatch",
+        STATUS_UNKNOWN_COLLECTION => "unknown collection",
+        STATUS_CAPACITY_FULL => "capacity full",
+        STATUS_FRAME_TOO_LARGE => "frame too large",
+        STATUS_UNKNOWN_OPCODE => "unknown opcode",
+        STATUS_BAD_MAGIC => "bad magic",
+        STATUS_BUSY => "busy",
+        STATUS_UNSUPPORTED_VERSION => "unsupported version",
+        STATUS_STORAGE => "storage failure",
+        _ => "unknown status",
+    }
+}
+
+/// Formats the server's message, if any, as a suffix of the status name.
+fn reason(message: &Option<String>) -> String {
+    message.as_ref().map(|m| format!(" ({})", m)).unwrap_or_default()
+}
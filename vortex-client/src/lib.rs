@@ -0,0 +1,103 @@
+//! Client for the VORTEX Binary Protocol (VBP).
+//!
+//! Handles framing, request-ID correlation, pipelining and connection reuse, so
+//! callers never build `RequestHeader`s by hand.
+//! - `blocking::Client`: `std::net` connections, checked out of a pool per call.
+//! - `Client` (feature `tokio`): connections shared by all tasks, responses routed
+//!   back to their caller by request ID.
+//! - `testing::TestServer` (feature `testing`): an in-process server for tests.
+
+pub mod blocking;
+mod codec;
+mod error;
+#[cfg(feature = "tokio")]
+mod nonblocking;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use codec::MAX_RESPONSE_PAYLOAD;
+pub use error::{status_name, Error, Result};
+#[cfg(feature = "tokio")]
+pub use nonblocking::{Client, UpsertStream};
+pub use vortex_rpc::{CollectionInfo, CollectionSpec, Filter, HelloResponse, Metadata, MetadataValue, SearchHit, StoredVector};
+
+use std::time::Duration;
+
+/// Connection and pipelining settings shared by both clients.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Connections kept open to the server.
+    pub connections: usize,
+    /// Requests a batch keeps in flight on one connection before waiting for ACKs.
+    pub pipeline_depth: usize,
+    /// How long to wait for a response. `None` waits forever.
+    pub request_timeout: Option<Duration>,
+    /// How long to wait for the TCP handshake.
+    pub connect_timeout: Duration,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            connections: 4,
+            pipeline_depth: 256,
+            request_timeout: Some(Duration::from_secs(30)),
+            connect_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ClientConfig {
+    pub fn with_connections(mut self, connections: usize) -> Self {
+        self.connections = connections.max(1);
+        self
+    }
+
+    pub fn with_pipeline_depth(mut self, depth: usize) -> Self {
+        self.pipeline_depth = depth.max(1);
+        self
+    }
+
+    pub fn with_request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+}
+
+/// Parameters of a k-NN query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchOptions {
+    /// Number of nearest neighbors to return (at most `vortex_rpc::MAX_SEARCH_TOP_K`).
+    pub top_k: u32,
+    /// Beam width. 0 selects the collection default.
+    pub ef_search: u32,
+    /// Only vectors whose metadata matches are returned.
+    pub filter: Option<Filter>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+impl SearchOptions {
+    pub fn new(top_k: u32) -> Self {
+        Self { top_k, ef_search: 0, filter: None }
+    }
+
+    pub fn with_ef_search(mut self, ef_search: u32) -> Self {
+        self.ef_search = ef_search;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+}
@@ -0,0 +1,441 @@
+//! Tokio VBP client: pipelined, multiplexed connections.
+
+use crate::codec::{Request, Response, HELLO_REQUEST_ID};
+use crate::{ClientConfig, CollectionInfo, Error, Metadata, Result, SearchHit, SearchOptions, StoredVector};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+use vortex_rpc::{HelloResponse, ResponseHeader, OP_UPSERT};
+
+/// Async handle to a VORTEX server.
+///
+/// Cheap to clone. Requests from every clone are spread over `ClientConfig::connections`
+/// shared connections; a reader task per connection routes each response to its caller
+/// by request ID, so any number of requests can be in flight at once.
+#[derive(Clone)]
+pub struct Client {
+    inner: Arc<Inner>,
+    collection: Option<u32>,
+}
+
+impl Client {
+    /// Connects to `addr` with the default `ClientConfig`.
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self> {
+        Self::connect_with(addr, ClientConfig::default()).await
+    }
+
+    /// Connects to `addr`. One connection is opened up front so a bad address, or a
+    /// server without a protocol version in common, fails here; the rest are opened on first use.
+    pub async fn connect_with(addr: impl ToSocketAddrs, config: ClientConfig) -> Result<Self> {
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host(addr).await?.collect();
+        let (first, server) = Connection::open(&addrs, &config).await?;
+        let mut slots: Vec<Mutex<Option<Arc<Connection>>>> = (0..config.connections).map(|_| Mutex::new(None)).collect();
+        slots[0] = Mutex::new(Some(Arc::new(first)));
+        let inner = Inner {
+            addrs,
+            config,
+            server,
+            slots,
+            next_slot: AtomicUsize::new(0),
+            next_request_id: AtomicU64::new(HELLO_REQUEST_ID + 1),
+        };
+        Ok(Self { inner: Arc::new(inner), collection: None })
+    }
+
+    /// A handle addressing `collection_id` (protocol v2), sharing this client's connections.
+    pub fn collection(&self, collection_id: u32) -> Self {
+        Self { inner: self.inner.clone(), collection: Some(collection_id) }
+    }
+
+    /// What the server reported in the handshake of the first connection.
+    pub fn server_info(&self) -> &HelloResponse {
+        &self.inner.server
+    }
+
+    /// Inserts or replaces `id`. Returns once the record is durable in the WAL.
+    pub async fn upsert(&self, id: u64, vector: &[f32]) -> Result<()> {
+        self.call(Request::upsert(self.collection, id, vector, None)).await?.into_ok().map(drop)
+    }
+
+    /// Inserts or replaces `id` with `metadata` attached.
+    pub async fn upsert_with_metadata(&self, id: u64, vector: &[f32], metadata: &Metadata) -> Result<()> {
+        self.call(Request::upsert(self.collection, id, vector, Some(metadata))).await?.into_ok().map(drop)
+    }
+
+    /// Pipelines every upsert, in order, on one connection so they share group commits.
+    ///
+    /// # Errors
+    /// Every record is sent even if some are rejected; the first rejection is returned.
+    pub async fn upsert_batch<'a>(&self, records: impl IntoIterator<Item = (u64, &'a [f32])>) -> Result<()> {
+        self.upsert_batch_with_metadata(records.into_iter().map(|(id, vector)| (id, vector, None))).await
+    }
+
+    /// `upsert_batch` for records that may carry metadata.
+    pub async fn upsert_batch_with_metadata<'a>(&self, records: impl IntoIterator<Item = (u64, &'a [f32], Option<&'a Metadata>)>) -> Result<()> {
+        let mut stream = self.upsert_stream().await?;
+        for (id, vector, metadata) in records {
+            stream.send(id, vector, metadata).await?;
+        }
+        stream.finish().await.map(drop)
+    }
+
+    /// Opens an ordered stream of upserts on one connection.
+    /// Records reach the server (and the WAL) in the order they are sent.
+    pub async fn upsert_stream(&self) -> Result<UpsertStream> {
+        Ok(UpsertStream {
+            conn: self.inner.connection().await?,
+            inner: self.inner.clone(),
+            collection: self.collection,
+            in_flight: VecDeque::new(),
+            acked: 0,
+            first_error: None,
+        })
+    }
+
+    /// Returns the `options.top_k` nearest neighbors of `query`, nearest first.
+    pub async fn search(&self, query: &[f32], options: &SearchOptions) -> Result<Vec<SearchHit>> {
+        self.call(Request::search(self.collection, query, options)).await?.into_hits()
+    }
+
+    /// Fetches the stored vector of `id` and its metadata, or `None` if it is not live.
+    pub async fn get(&self, id: u64) -> Result<Option<StoredVector>> {
+        let request = Request::get(self.collection, id, self.inner.server.version);
+        let version = request.version();
+        self.call(request).await?.into_vector(version)
+    }
+
+    /// Tombstones `id`. Deleting an absent ID succeeds.
+    pub async fn delete(&self, id: u64) -> Result<()> {
+        self.call(Request::delete(self.collection, id)).await?.into_ok().map(drop)
+    }
+
+    /// Lists every collection in the server catalog.
+    pub async fn list_collections(&self) -> Result<Vec<CollectionInfo>> {
+        self.call(Request::list_collections()).await?.into_collections()
+    }
+
+    async fn call(&self, request: Request) -> Result<Response> {
+        let pending = self.inner.submit(&request).await?;
+        self.inner.wait(request.opcode(), pending).await
+    }
+}
+
+type Waiter = oneshot::Sender<Result<Response>>;
+
+struct Inner {
+    addrs: Vec<SocketAddr>,
+    config: ClientConfig,
+    server: HelloResponse,
+    /// Connections, opened lazily and replaced once their reader task has stopped.
+    slots: Vec<Mutex<Option<Arc<Connection>>>>,
+    next_slot: AtomicUsize,
+    /// Request IDs are unique across all connections of the client.
+    next_request_id: AtomicU64,
+}
+
+impl Inner {
+    /// Next connection (round robin), reopened if its reader task has stopped.
+    async fn connection(&self) -> Result<Arc<Connection>> {
+        let slot = self.next_slot.fetch_add(1, Ordering::Relaxed) % self.slots.len();
+        let mut slot = self.slots[slot].lock().await;
+        match slot.as_ref() {
+            Some(conn) if !conn.is_closed() => Ok(conn.clone()),
+            _ => {
+                let conn = Arc::new(Connection::open(&self.addrs, &self.config).await?.0);
+                *slot = Some(conn.clone());
+                Ok(conn)
+            }
+        }
+    }
+
+    /// Writes `request` on the next connection and returns where its response will land.
+    async fn submit(&self, request: &Request) -> Result<PendingResponse> {
+        let conn = self.connection().await?;
+        conn.send(request, self.next_request_id()).await
+    }
+
+    fn next_request_id(&self) -> u64 {
+        self.next_request_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Waits for the response of `pending`. On a timeout its waiter is dropped with it.
+    async fn wait(&self, opcode: u8, mut pending: PendingResponse) -> Result<Response> {
+        let response = match self.config.request_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, &mut pending.receiver).await.map_err(|_| Error::Timeout)?,
+            None => (&mut pending.receiver).await,
+        };
+        response.map_err(|_| Error::Closed)??.expect_opcode(opcode)
+    }
+}
+
+/// Upserts written in order on one connection, opened by `Client::upsert_stream`.
+///
+/// Up to `ClientConfig::pipeline_depth` records are in flight; `send` waits for the
+/// oldest ACK beyond that. Nothing is durable until `finish` returns.
+pub struct UpsertStream {
+    inner: Arc<Inner>,
+    conn: Arc<Connection>,
+    collection: Option<u32>,
+    in_flight: VecDeque<PendingResponse>,
+    acked: usize,
+    first_error: Option<Error>,
+}
+
+impl UpsertStream {
+    /// Writes one upsert.
+    ///
+    /// # Errors
+    /// Fails only if the frame could not be written; rejections surface in `finish`.
+    pub async fn send(&mut self, id: u64, vector: &[f32], metadata: Option<&Metadata>) -> Result<()> {
+        if self.in_flight.len() >= self.inner.config.pipeline_depth {
+            self.reap_oldest().await;
+        }
+        let request = Request::upsert(self.collection, id, vector, metadata);
+        let pending = self.conn.send(&request, self.inner.next_request_id()).await?;
+        self.in_flight.push_back(pending);
+        Ok(())
+    }
+
+    /// Waits for every outstanding ACK and returns how many records are durable.
+    ///
+    /// # Errors
+    /// Returns the first rejection (or transport failure) of any record in the stream.
+    pub async fn finish(mut self) -> Result<usize> {
+        while !self.in_flight.is_empty() {
+            self.reap_oldest().await;
+        }
+        self.first_error.map_or(Ok(self.acked), Err)
+    }
+
+    async fn reap_oldest(&mut self) {
+        let Some(pending) = self.in_flight.pop_front() else { return };
+        match self.inner.wait(OP_UPSERT, pending).await.and_then(Response::into_ok) {
+            Ok(_) => self.acked += 1,
+            Err(e) => {
+                self.first_error.get_or_insert(e);
+            }
+        }
+    }
+}
+
+/// One TCP stream: a locked write half plus a reader task resolving waiters by request ID.
+struct Connection {
+    writer: Mutex<OwnedWriteHalf>,
+    waiters: Arc<std::sync::Mutex<Waiters>>,
+    reader: JoinHandle<()>,
+}
+
+/// Callers waiting on one connection. `closed` is set once the reader task has stopped.
+#[derive(Default)]
+struct Waiters {
+    pending: HashMap<u64, Waiter>,
+    closed: bool,
+}
+
+/// The response slot of one sent request. Dropping it (a timeout, a cancelled caller)
+/// removes the waiter, so requests the server never answers do not pile up.
+struct PendingResponse {
+    request_id: u64,
+    receiver: oneshot::Receiver<Result<Response>>,
+    waiters: Arc<std::sync::Mutex<Waiters>>,
+}
+
+impl Drop for PendingResponse {
+    fn drop(&mut self) {
+        lock(&self.waiters).pending.remove(&self.request_id);
+    }
+}
+
+/// Retires the connection unless disarmed: a frame cut short by an error or by a
+/// cancelled `send` desyncs the stream.
+struct PartialWrite<'a> {
+    waiters: &'a std::sync::Mutex<Waiters>,
+    finished: bool,
+}
+
+impl Drop for PartialWrite<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            lock(self.waiters).closed = true;
+        }
+    }
+}
+
+impl Connection {
+    /// Connects and runs the handshake before the reader task takes over the stream.
+    async fn open(addrs: &[SocketAddr], config: &ClientConfig) -> Result<(Self, HelloResponse)> {
+        let mut stream = tokio::time::timeout(config.connect_timeout, TcpStream::connect(addrs)).await
+            .map_err(|_| Error::Timeout)??;
+        stream.set_nodelay(true)?;
+        let server = match config.request_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, handshake(&mut stream)).await.map_err(|_| Error::Timeout)??,
+            None => handshake(&mut stream).await?,
+        };
+        let (reader, writer) = stream.into_split();
+        let waiters = Arc::new(std::sync::Mutex::new(Waiters::default()));
+        let reader = tokio::spawn(read_responses(reader, waiters.clone()));
+        Ok((Self { writer: Mutex::new(writer), waiters, reader }, server))
+    }
+
+    fn is_closed(&self) -> bool {
+        lock(&self.waiters).closed
+    }
+
+    /// Writes one request frame. Cancel-safe: a future dropped mid-frame retires the connection.
+    async fn send(&self, request: &Request, request_id: u64) -> Result<PendingResponse> {
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut waiters = lock(&self.waiters);
+            if waiters.closed {
+                return Err(Error::Closed);
+            }
+            waiters.pending.insert(request_id, sender);
+        }
+        let pending = PendingResponse { request_id, receiver, waiters: self.waiters.clone() };
+
+        let mut frame = Vec::new();
+        request.encode(request_id, &mut frame);
+        let mut writer = self.writer.lock().await;
+        let mut write = PartialWrite { waiters: &self.waiters, finished: false };
+        writer.write_all(&frame).await?;
+        write.finished = true;
+        Ok(pending)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Routes every response on `reader` to its waiter until the stream ends or loses framing,
+/// then fails everything still pending with `Error::Closed`.
+async fn read_responses(mut reader: OwnedReadHalf, waiters: Arc<std::sync::Mutex<Waiters>>) {
+    while let Ok(response) = read_response(&mut reader).await {
+        // Waiters that timed out are gone; their late responses are dropped
+        if let Some(waiter) = lock(&waiters).pending.remove(&response.request_id) {
+            let _ = waiter.send(Ok(response));
+        }
+    }
+    let mut waiters = lock(&waiters);
+    waiters.closed = true;
+    for (_, waiter) in waiters.pending.drain() {
+        let _ = waiter.send(Err(Error::Closed));
+    }
+}
+
+/// Negotiates the protocol version before any other request is sent.
+async fn handshake(stream: &mut TcpStream) -> Result<HelloResponse> {
+    let mut frame = Vec::new();
+    Request::hello().encode(HELLO_REQUEST_ID, &mut frame);
+    stream.write_all(&frame).await?;
+    read_response(stream).await?.into_hello()
+}
+
+async fn read_response(reader: &mut (impl AsyncRead + Unpin)) -> Result<Response> {
+    let mut raw = [0u8; ResponseHeader::SIZE];
+    reader.read_exact(&mut raw).await?;
+    let (header, len) = Response::parse_header(&raw)?;
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(Response::new(&header, payload))
+}
+
+fn lock(waiters: &std::sync::Mutex<Waiters>) -> std::sync::MutexGuard<'_, Waiters> {
+    waiters.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{basis_vector, TestServer};
+    use std::io::{Read, Write};
+    use std::time::Duration;
+    use vortex_rpc::{STATUS_DIMENSION_MISMATCH, STATUS_OK, OP_HELLO};
+
+    /// A server that completes the handshake, then neither reads nor answers until `release` drops.
+    fn silent_server(release: std::sync::mpsc::Receiver<()>) -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut hello = [0u8; 20];
+            stream.read_exact(&mut hello).unwrap();
+            let response = HelloResponse { version: vortex_rpc::VBP_VERSION_MAX, max_frame_size: u32::MAX, dimension: 8, opcodes: vec![OP_HELLO] };
+            let mut payload = vec![0u8; response.encoded_len()];
+            response.write_to(&mut payload);
+            let header = ResponseHeader { magic: vortex_rpc::VBP_MAGIC, status: STATUS_OK, opcode: OP_HELLO, payload_len: payload.len() as u32, request_id: HELLO_REQUEST_ID };
+            stream.write_all(&header.to_bytes()).unwrap();
+            stream.write_all(&payload).unwrap();
+            let _ = release.recv();
+        });
+        port
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_tasks_share_connections() {
+        let server = TestServer::start("client_async_tasks", 8);
+        let config = ClientConfig::default().with_connections(2);
+        let client = Client::connect_with(("127.0.0.1", server.port), config).await.unwrap();
+        assert!(client.server_info().supports(vortex_rpc::OP_SEARCH));
+
+        let tasks: Vec<_> = (0..8u64).map(|task| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                for i in 0..25 {
+                    let id = task * 100 + i;
+                    client.upsert(id, &basis_vector(8, (id % 8) as usize)).await.unwrap();
+                    assert_eq!(client.get(id).await.unwrap().map(|stored| stored.vector), Some(basis_vector(8, (id % 8) as usize)));
+                }
+            })
+        }).collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let hits = client.search(&basis_vector(8, 3), &SearchOptions::new(5)).await.unwrap();
+        assert_eq!(hits.len(), 5);
+        client.delete(3).await.unwrap();
+        assert_eq!(client.get(3).await.unwrap(), None);
+        assert!(matches!(client.upsert(1, &[0.0; 2]).await, Err(Error::Status { status: STATUS_DIMENSION_MISMATCH, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_upsert_batch_awaits_every_ack() {
+        let server = TestServer::start("client_async_batch", 8);
+        let client = Client::connect(("127.0.0.1", server.port)).await.unwrap();
+
+        let vectors: Vec<Vec<f32>> = (0..500).map(|i| basis_vector(8, i % 8)).collect();
+        client.upsert_batch(vectors.iter().enumerate().map(|(i, v)| (i as u64, v.as_slice()))).await.unwrap();
+        for (i, vector) in vectors.iter().enumerate() {
+            assert_eq!(client.get(i as u64).await.unwrap().map(|stored| stored.vector).as_ref(), Some(vector));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_requests_release_their_waiters() {
+        let (_release, hold) = std::sync::mpsc::channel();
+        let port = silent_server(hold);
+        let config = ClientConfig { request_timeout: Some(Duration::from_millis(50)), ..ClientConfig::default().with_connections(1) };
+        let client = Client::connect_with(("127.0.0.1", port), config).await.unwrap();
+        let conn = client.inner.slots[0].lock().await.clone().unwrap();
+
+        // An unanswered request times out without leaving its waiter behind
+        assert!(matches!(client.get(1).await, Err(Error::Timeout)));
+        assert!(lock(&conn.waiters).pending.is_empty());
+        assert!(!conn.is_closed());
+
+        // A send cancelled mid-frame (the server stopped reading) retires the connection
+        let huge = vec![0.0f32; 8 << 20];
+        assert!(tokio::time::timeout(Duration::from_millis(200), client.upsert(2, &huge)).await.is_err());
+        assert!(lock(&conn.waiters).pending.is_empty());
+        assert!(conn.is_closed());
+    }
+}
@@ -0,0 +1,65 @@
+//! In-process server for the tests of the client and of the crates built on it.
+
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use vortex_core::reactor::ShardReactor;
+
+/// A single-shard `ShardReactor` driven on its own thread until dropped.
+pub struct TestServer {
+    /// Port the reactor listens on, on 127.0.0.1.
+    pub port: u16,
+    dir: String,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TestServer {
+    /// Starts a server on a fresh data directory named after `name`, whose default
+    /// collection holds up to 1000 `dimension`-sized vectors.
+    pub fn start(name: &str, dimension: usize) -> Self {
+        Self::with_capacity(name, dimension, 1000)
+    }
+
+    /// `start` with room for `max_elements` vectors in the default collection.
+    pub fn with_capacity(name: &str, dimension: usize, max_elements: usize) -> Self {
+        let dir = std::env::temp_dir().join(format!("vortex_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir = dir.to_string_lossy().into_owned();
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (stop_flag, reactor_dir) = (stop.clone(), dir.clone());
+        let handle = std::thread::spawn(move || {
+            let mut reactor = ShardReactor::new(0, 256, max_elements, dimension, &reactor_dir);
+            reactor.listen(port).unwrap();
+            ready_tx.send(()).unwrap();
+            while !stop_flag.load(Ordering::SeqCst) {
+                reactor.run_tick();
+            }
+        });
+        ready_rx.recv().unwrap();
+        Self { port, dir, stop, handle: Some(handle) }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // A fresh connection completes the pending accept, waking the final tick
+        let _ = TcpStream::connect(("127.0.0.1", self.port));
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Unit vector along `axis`.
+pub fn basis_vector(dimension: usize, axis: usize) -> Vec<f32> {
+    let mut v = vec![0.0f32; dimension];
+    v[axis] = 1.0;
+    v
+}
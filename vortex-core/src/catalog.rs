@@ -0,0 +1,365 @@
+use crate::index::distance::Metric;
+use crate::index::hnsw::HnswIndex;
+use crate::storage::manifest::{self, Manifest};
+use log::{error, info};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use vortex_rpc::{CollectionInfo, CollectionSpec, DEFAULT_COLLECTION, MAX_DIMENSION};
+
+/// Name of the collection catalog file inside the storage directory.
+pub const CATALOG_FILE: &str = "collections.catalog";
+
+/// Upper bound on collections per server, including the default collection.
+pub const MAX_COLLECTIONS: usize = 64;
+
+/// Name reported for the implicit collection configured on the command line.
+pub const DEFAULT_COLLECTION_NAME: &str = "default";
+
+/// Cluster-wide registry of named collections, shared by every shard reactor.
+///
+/// # Purpose
+/// Admin opcodes can land on any shard (`SO_REUSEPORT`), so definitions live here rather
+/// than in a reactor. Each reactor still owns its own indexes and WAL streams (Rule 6)
+/// and only compares `generation()` to notice creates and drops.
+///
+/// # Format
+/// One line per user collection plus the ID allocator, e.g.
+/// `collection id=1 name=text dimension=384 max_elements=100000 m=16 ef_construction=128 metric=0`.
+/// Dropped collections whose files are not deleted yet are listed as `dropped id=1`.
+/// The default collection is implicit and pinned by the storage manifest.
+pub struct Catalog {
+    base_path: String,
+    default_max_elements: usize,
+    state: RwLock<CatalogState>,
+    generation: AtomicU64,
+}
+
+struct CatalogState {
+    collections: Vec<CollectionInfo>,
+    next_id: u32,
+    /// Dropped collections whose directory is removed once no shard holds them open.
+    dropped: Vec<u32>,
+    /// Number of shards holding each collection open.
+    handles: HashMap<u32, usize>,
+}
+
+impl Catalog {
+    /// Loads the catalog of `base_path`, checking the default collection against the manifest.
+    ///
+    /// # Errors
+    /// Returns the manifest error on a dimension or metric change, or `ErrorKind::InvalidData`
+    /// if the catalog file cannot be parsed.
+    pub fn open(base_path: &str, dimension: usize, metric: Metric, default_max_elements: usize) -> std::io::Result<Self> {
+        Manifest::open(base_path, dimension, metric)?;
+
+        let default = CollectionInfo {
+            id: DEFAULT_COLLECTION,
+            spec: CollectionSpec {
+                dimension: dimension as u32,
+                max_elements: default_max_elements as u32,
+                m: HnswIndex::DEFAULT_M as u16,
+                ef_construction: HnswIndex::DEFAULT_EF_CONSTRUCTION as u16,
+                metric: metric.code(),
+                reserved: [0; 3],
+            },
+            name: DEFAULT_COLLECTION_NAME.to_string(),
+        };
+        let mut state = CatalogState { collections: vec![default], next_id: DEFAULT_COLLECTION + 1, dropped: Vec::new(), handles: HashMap::new() };
+
+        let path = Path::new(base_path).join(CATALOG_FILE);
+        if path.exists() {
+            Self::parse(&std::fs::read_to_string(&path)?, &mut state)?;
+            info!("Catalog loaded {} collection(s) from {:?}", state.collections.len() - 1, path);
+        }
+
+        let catalog = Self {
+            base_path: base_path.to_string(),
+            default_max_elements,
+            state: RwLock::new(state),
+            generation: AtomicU64::new(0),
+        };
+        // No shard is running yet: finish the deletes a crash or shutdown interrupted
+        {
+            let mut state = catalog.state.write().unwrap();
+            for id in std::mem::take(&mut state.dropped) {
+                catalog.remove_files(&mut state, id);
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// Bumped on every create and drop.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns every live collection, default first.
+    pub fn collections(&self) -> Vec<CollectionInfo> {
+        self.state.read().unwrap().collections.clone()
+    }
+
+    /// Directory holding the per-shard WAL streams of collection `id`.
+    /// The default collection keeps the original layout in the storage root.
+    pub fn collection_dir(&self, id: u32) -> String {
+        if id == DEFAULT_COLLECTION {
+            self.base_path.clone()
+        } else {
+            format!("{}/collection_{}", self.base_path, id)
+        }
+    }
+
+    /// Registers a new collection. Zero-valued spec fields take the server defaults.
+    ///
+    /// # Errors
+    /// Returns an error for a duplicate name, an invalid spec, too many collections,
+    /// or a failed catalog write.
+    pub fn create(&self, name: &str, spec: CollectionSpec) -> Result<CollectionInfo, &'static str> {
+        let spec = self.resolve_spec(spec)?;
+        let mut state = self.state.write().unwrap();
+        if state.collections.iter().any(|c| c.name == name) {
+            return Err("Collection name already exists");
+        }
+        if state.collections.len() >= MAX_COLLECTIONS {
+            return Err("Collection limit reached");
+        }
+
+        let info = CollectionInfo { id: state.next_id, spec, name: name.to_string() };
+        if let Err(e) = std::fs::create_dir_all(self.collection_dir(info.id)) {
+            error!("Catalog: Failed to create directory for collection {}: {}", name, e);
+            return Err("Failed to create collection directory");
+        }
+        state.collections.push(info.clone());
+        state.next_id += 1;
+        if let Err(e) = self.persist(&state) {
+            error!("Catalog: Failed to persist collection {}: {}", name, e);
+            state.collections.pop();
+            state.next_id -= 1;
+            return Err("Failed to persist catalog");
+        }
+
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        info!("Catalog: Created collection {} (id {}, Dim: {})", name, info.id, spec.dimension);
+        Ok(info)
+    }
+
+    /// Records that a shard opened collection `id`. Returns false if it is not live,
+    /// in which case the shard must not open it.
+    pub fn attach(&self, id: u32) -> bool {
+        let mut state = self.state.write().unwrap();
+        if !state.collections.iter().any(|c| c.id == id) {
+            return false;
+        }
+        *state.handles.entry(id).or_insert(0) += 1;
+        true
+    }
+
+    /// Records that a shard closed collection `id`. The last shard to close a dropped
+    /// collection deletes its files.
+    pub fn detach(&self, id: u32) {
+        let mut state = self.state.write().unwrap();
+        let open = state.handles.get(&id).copied().unwrap_or(0).saturating_sub(1);
+        if open > 0 {
+            state.handles.insert(id, open);
+            return;
+        }
+        state.handles.remove(&id);
+        if state.dropped.contains(&id) {
+            self.remove_files(&mut state, id);
+        }
+    }
+
+    /// Removes collection `id` from the catalog. Its WAL streams are deleted once every
+    /// shard has closed it (see `detach`). Returns false if it does not exist.
+    ///
+    /// # Errors
+    /// Returns an error for the default collection or a failed catalog write.
+    pub fn drop_collection(&self, id: u32) -> Result<bool, &'static str> {
+        if id == DEFAULT_COLLECTION {
+            return Err("The default collection cannot be dropped");
+        }
+        let mut state = self.state.write().unwrap();
+        let pos = match state.collections.iter().position(|c| c.id == id) {
+            Some(pos) => pos,
+            None => return Ok(false),
+        };
+        let removed = state.collections.remove(pos);
+        state.dropped.push(id);
+        if let Err(e) = self.persist(&state) {
+            error!("Catalog: Failed to persist drop of collection {}: {}", removed.name, e);
+            state.dropped.pop();
+            state.collections.insert(pos, removed);
+            return Err("Failed to persist catalog");
+        }
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        info!("Catalog: Dropped collection {} (id {})", removed.name, id);
+
+        // Shards holding it open drain their batches first; the last one deletes the files
+        if !state.handles.contains_key(&id) {
+            self.remove_files(&mut state, id);
+        }
+        Ok(true)
+    }
+
+    /// Deletes the directory of dropped collection `id` and clears its tombstone.
+    fn remove_files(&self, state: &mut CatalogState, id: u32) {
+        if let Err(e) = std::fs::remove_dir_all(self.collection_dir(id)) {
+            if e.kind() != ErrorKind::NotFound {
+                error!("Catalog: Failed to delete files of collection {}: {}", id, e);
+                return;
+            }
+        }
+        state.dropped.retain(|&d| d != id);
+        match self.persist(state) {
+            Ok(()) => info!("Catalog: Deleted files of collection {}", id),
+            Err(e) => error!("Catalog: Failed to clear the tombstone of collection {}: {}", id, e),
+        }
+    }
+
+    fn resolve_spec(&self, mut spec: CollectionSpec) -> Result<CollectionSpec, &'static str> {
+        if spec.dimension == 0 || spec.dimension > MAX_DIMENSION {
+            return Err("Collection dimension out of range");
+        }
+        if Metric::from_code(spec.metric).is_none() {
+            return Err("Unsupported distance metric");
+        }
+        if spec.max_elements == 0 {
+            spec.max_elements = self.default_max_elements as u32;
+        }
+        if spec.m == 0 {
+            spec.m = HnswIndex::DEFAULT_M as u16;
+        }
+        if spec.ef_construction == 0 {
+            spec.ef_construction = HnswIndex::DEFAULT_EF_CONSTRUCTION as u16;
+        }
+        if spec.m < 2 || spec.m > 64 {
+            return Err("HNSW m must be between 2 and 64");
+        }
+        spec.reserved = [0; 3];
+        Ok(spec)
+    }
+
+    fn persist(&self, state: &CatalogState) -> std::io::Result<()> {
+        let mut contents = format!("next_id={}\n", state.next_id);
+        for c in state.collections.iter().filter(|c| c.id != DEFAULT_COLLECTION) {
+            contents.push_str(&format!(
+                "collection id={} name={} dimension={} max_elements={} m={} ef_construction={} metric={}\n",
+                c.id, c.name, c.spec.dimension, c.spec.max_elements, c.spec.m, c.spec.ef_construction, c.spec.metric
+            ));
+        }
+        for id in &state.dropped {
+            contents.push_str(&format!("dropped id={}\n", id));
+        }
+        manifest::write_atomic(&Path::new(&self.base_path).join(CATALOG_FILE), &contents)
+    }
+
+    fn parse(contents: &str, state: &mut CatalogState) -> std::io::Result<()> {
+        let invalid = |msg: &str| Error::new(ErrorKind::InvalidData, format!("Catalog corrupted: {}", msg));
+        for line in contents.lines() {
+            if let Some(value) = line.strip_prefix("next_id=") {
+                state.next_id = value.trim().parse().map_err(|_| invalid("bad next_id"))?;
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("dropped id=") {
+                state.dropped.push(value.trim().parse().map_err(|_| invalid(line))?);
+                continue;
+            }
+            let Some(fields) = line.strip_prefix("collection ") else { continue };
+
+            let mut info = CollectionInfo {
+                id: 0,
+                spec: CollectionSpec { dimension: 0, max_elements: 0, m: 0, ef_construction: 0, metric: 0, reserved: [0; 3] },
+                name: String::new(),
+            };
+            for field in fields.split_whitespace() {
+                let (key, value) = field.split_once('=').ok_or_else(|| invalid(field))?;
+                let number = || value.parse::<u32>().map_err(|_| invalid(field));
+                match key {
+                    "id" => info.id = number()?,
+                    "name" => info.name = value.to_string(),
+                    "dimension" => info.spec.dimension = number()?,
+                    "max_elements" => info.spec.max_elements = number()?,
+                    "m" => info.spec.m = number()? as u16,
+                    "ef_construction" => info.spec.ef_construction = number()? as u16,
+                    "metric" => info.spec.metric = number()? as u8,
+                    _ => {}
+                }
+            }
+            if info.id == DEFAULT_COLLECTION || info.name.is_empty() || info.spec.dimension == 0 {
+                return Err(invalid(line));
+            }
+            state.next_id = state.next_id.max(info.id + 1);
+            state.collections.push(info);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_persists_collections() {
+        let dir = std::env::temp_dir().join(format!("vortex_catalog_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir = dir.to_str().unwrap();
+        let spec = |dimension| CollectionSpec { dimension, max_elements: 0, m: 0, ef_construction: 0, metric: vortex_rpc::METRIC_L2, reserved: [0; 3] };
+
+        let catalog = Catalog::open(dir, 128, Metric::InnerProduct, 1000).unwrap();
+        let text = catalog.create("text", spec(384)).unwrap();
+        let image = catalog.create("image", spec(512)).unwrap();
+        assert_eq!(text.spec.max_elements, 1000);
+        assert_eq!(text.spec.m as usize, HnswIndex::DEFAULT_M);
+        assert_eq!(catalog.create("text", spec(8)).unwrap_err(), "Collection name already exists");
+        assert!(catalog.create("zero", spec(0)).is_err());
+        assert_eq!(catalog.create("bad", CollectionSpec { metric: 9, ..spec(8) }).unwrap_err(), "Unsupported distance metric");
+        assert!(catalog.drop_collection(DEFAULT_COLLECTION).is_err());
+        assert_eq!(catalog.drop_collection(text.id), Ok(true));
+        assert_eq!(catalog.drop_collection(text.id), Ok(false));
+        assert_eq!(catalog.generation(), 3);
+
+        // IDs are never reused, so stale WAL streams cannot be picked up by a new collection
+        let reopened = Catalog::open(dir, 128, Metric::InnerProduct, 1000).unwrap();
+        let names: Vec<_> = reopened.collections().into_iter().map(|c| (c.id, c.name)).collect();
+        assert_eq!(names, vec![(0, "default".to_string()), (image.id, "image".to_string())]);
+        assert_eq!(reopened.collections()[1].spec.metric, vortex_rpc::METRIC_L2);
+        assert_eq!(reopened.create("text", spec(384)).unwrap().id, image.id + 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_drop_waits_for_every_shard_to_close() {
+        let dir = std::env::temp_dir().join(format!("vortex_catalog_drop_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir = dir.to_str().unwrap();
+        let spec = CollectionSpec { dimension: 8, max_elements: 0, m: 0, ef_construction: 0, metric: vortex_rpc::METRIC_L2, reserved: [0; 3] };
+
+        let catalog = Catalog::open(dir, 8, Metric::L2, 100).unwrap();
+        let text = catalog.create("text", spec).unwrap();
+        let files = catalog.collection_dir(text.id);
+        assert!(catalog.attach(text.id) && catalog.attach(text.id));
+
+        // Both shards still hold the WAL open
+        assert_eq!(catalog.drop_collection(text.id), Ok(true));
+        assert!(!catalog.attach(text.id));
+        catalog.detach(text.id);
+        assert!(Path::new(&files).exists());
+        catalog.detach(text.id);
+        assert!(!Path::new(&files).exists());
+
+        // A drop interrupted by a restart is finished at the next boot
+        let image = catalog.create("image", spec).unwrap();
+        assert!(catalog.attach(image.id));
+        assert_eq!(catalog.drop_collection(image.id), Ok(true));
+        drop(catalog);
+        Catalog::open(dir, 8, Metric::L2, 100).unwrap();
+        assert!(!Path::new(&format!("{}/collection_{}", dir, image.id)).exists());
+        assert!(!std::fs::read_to_string(Path::new(dir).join(CATALOG_FILE)).unwrap().contains("dropped"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,64 +1,105 @@
-#[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::*;
+use crate::index::simd;
+use vortex_rpc::{METRIC_COSINE, METRIC_INNER_PRODUCT, METRIC_L2};
 
-/// L2 Square Distance (Euclidean)
-/// Automatically selects best SIMD implementation based on CPU features.
-pub fn l2_distance(v1: &[f32], v2: &[f32]) -> f32 {
-    #[cfg(target_arch = "x86_64")]
-    {
-        if is_x86_feature_detected!("avx2") {
-            return unsafe { l2_distance_avx2(v1, v2) };
+/// Distance metric of an index. Every metric is a distance: smaller is closer.
+///
+/// # Distances
+/// - `InnerProduct`: `-dot(a, b)`
+/// - `L2`: squared Euclidean distance
+/// - `Cosine`: `1 - cos(a, b)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    #[default]
+    InnerProduct,
+    L2,
+    Cosine,
+}
+
+impl Metric {
+    /// Decodes a `METRIC_*` wire code.
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            METRIC_INNER_PRODUCT => Some(Self::InnerProduct),
+            METRIC_L2 => Some(Self::L2),
+            METRIC_COSINE => Some(Self::Cosine),
+            _ => None,
         }
     }
-    
-    // Fallback: Scalar implementation
-    l2_distance_scalar(v1, v2)
-}
 
-fn l2_distance_scalar(v1: &[f32], v2: &[f32]) -> f32 {
-    v1.iter().zip(v2.iter()).map(|(x, y)| (x - y) * (x - y)).sum()
+    /// The `METRIC_*` wire code.
+    pub fn code(self) -> u8 {
+        match self {
+            Self::InnerProduct => METRIC_INNER_PRODUCT,
+            Self::L2 => METRIC_L2,
+            Self::Cosine => METRIC_COSINE,
+        }
+    }
+
+    /// Returns the fastest full-precision kernel for this metric on the running CPU.
+    pub fn kernel(self) -> simd::SimdFunc {
+        match self {
+            Self::InnerProduct => simd::get_vector_kernel(),
+            Self::L2 => simd::get_l2_kernel(),
+            Self::Cosine => simd::get_cosine_kernel(),
+        }
+    }
+
+    /// Turns the quantized kernel output into this metric's (approximate) distance.
+    ///
+    /// `ScalarQuantizer` stores `u ≈ (x / |x| + 1) * 127.5` and queries `q ≈ y / |y| * 127`,
+    /// so `Σ u·q ≈ 127.5 * 127 * cos + 127.5 * Σ q`. The cosine estimate is then rescaled
+    /// with the original magnitudes the quantizer kept.
+    ///
+    /// * `neg_dot` - Kernel output (`-Σ u·q`).
+    /// * `code_sum` - `Σ q` over the query codes.
+    #[inline(always)]
+    pub fn coarse_distance(self, neg_dot: i32, code_sum: i32, query_magnitude: f32, node_magnitude: f32) -> f32 {
+        let cos = (-(neg_dot as f32) - 127.5 * code_sum as f32) / (127.5 * 127.0);
+        match self {
+            Self::InnerProduct => -cos * node_magnitude * query_magnitude,
+            Self::L2 => node_magnitude * node_magnitude + query_magnitude * query_magnitude
+                - 2.0 * node_magnitude * query_magnitude * cos,
+            Self::Cosine => 1.0 - cos,
+        }
+    }
 }
 
-#[cfg(target_arch = "x86_64")]
-unsafe fn l2_distance_avx2(v1: &[f32], v2: &[f32]) -> f32 {
-    let mut sum_vec = _mm256_setzero_ps();
-    let n = v1.len();
-    let n_simd = n - (n % 8);
-    
-    for i in (0..n_simd).step_by(8) {
-        let x = _mm256_loadu_ps(v1.as_ptr().add(i));
-        let y = _mm256_loadu_ps(v2.as_ptr().add(i));
-        let diff = _mm256_sub_ps(x, y);
-        let sq = _mm256_mul_ps(diff, diff);
-        sum_vec = _mm256_add_ps(sum_vec, sq);
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InnerProduct => write!(f, "ip"),
+            Self::L2 => write!(f, "l2"),
+            Self::Cosine => write!(f, "cosine"),
+        }
     }
-    
-    let mut results = [0.0f32; 8];
-    _mm256_storeu_ps(results.as_mut_ptr(), sum_vec);
-    
-    let mut sum = results.iter().sum::<f32>();
-    
-    // Tail
-    for i in n_simd..n {
-        sum += (v1[i] - v2[i]) * (v1[i] - v2[i]);
+}
+
+impl std::str::FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ip" | "inner_product" | "dot" => Ok(Self::InnerProduct),
+            "l2" | "euclidean" => Ok(Self::L2),
+            "cosine" => Ok(Self::Cosine),
+            _ => Err(format!("unknown metric '{}' (expected ip, l2 or cosine)", s)),
+        }
     }
-    
-    sum
+}
+
+/// L2 Square Distance (Euclidean)
+/// Automatically selects best SIMD implementation based on CPU features.
+pub fn l2_distance(v1: &[f32], v2: &[f32]) -> f32 {
+    let n = v1.len().min(v2.len());
+    // SAFETY: Both slices hold at least `n` values.
+    unsafe { simd::get_l2_kernel()(v1.as_ptr(), v2.as_ptr(), n) }
 }
 
 /// Cosine Similarity (DotProduct / (NormA * NormB))
 /// Range: [-1.0, 1.0]
 #[inline]
 pub fn cosine_similarity(v1: &[f32], v2: &[f32]) -> f32 {
-    // Scalar implementation for baseline verification.
-    // In production, we would use AVX2 FMA (Fused Multiply Add).
-    let dot_product: f32 = v1.iter().zip(v2.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = v1.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b: f32 = v2.iter().map(|x| x * x).sum::<f32>().sqrt();
-    
-    if norm_a == 0.0 || norm_b == 0.0 {
-        return 0.0;
-    }
-    
-    dot_product / (norm_a * norm_b)
+    let n = v1.len().min(v2.len());
+    // SAFETY: Both slices hold at least `n` values.
+    1.0 - unsafe { simd::get_cosine_kernel()(v1.as_ptr(), v2.as_ptr(), n) }
 }
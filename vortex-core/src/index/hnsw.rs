@@ -1,7 +1,9 @@
-use crate::index::VectorIndex;
+use crate::index::{SearchParams, VectorIndex};
 use crate::index::simd;
+use crate::index::distance::Metric;
 use super::quantization;
 use log::{info, error};
+use vortex_rpc::Metadata;
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap};
 use std::sync::RwLock;
@@ -18,17 +20,63 @@ struct Candidate {
 
 impl Eq for Candidate {}
 
+/// A quantized query plus what `Metric::coarse_distance` needs to rescale integer scores.
+struct QuantizedQuery {
+    codes: Vec<i8>,
+    code_sum: i32,
+    magnitude: f32,
+}
+
+impl QuantizedQuery {
+    fn new(query: &[f32]) -> Self {
+        let (codes, magnitude) = quantization::ScalarQuantizer::quantize_query(query);
+        let code_sum = codes.iter().map(|&c| c as i32).sum();
+        Self { codes, code_sum, magnitude }
+    }
+}
+
+/// Bounds-checked cursor over a snapshot body.
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> std::io::Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Snapshot rejected: truncated"));
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> std::io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> std::io::Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> std::io::Result<u64> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(b))
+    }
+}
+
 // Wrapper for Min-Heap (BinaryHeap pops largest, so Reverse comparisons)
 #[derive(PartialEq, Eq)]
 struct MinCandidate(Candidate);
 impl PartialOrd for MinCandidate {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.0.distance.partial_cmp(&self.0.distance)
+        Some(self.cmp(other))
     }
 }
 impl Ord for MinCandidate {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+        other.0.distance.partial_cmp(&self.0.distance).unwrap_or(Ordering::Equal)
     }
 }
 
@@ -37,12 +85,12 @@ impl Ord for MinCandidate {
 struct MaxCandidate(Candidate);
 impl PartialOrd for MaxCandidate {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.0.distance.partial_cmp(&other.0.distance)
+        Some(self.cmp(other))
     }
 }
 impl Ord for MaxCandidate {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+        self.0.distance.partial_cmp(&other.0.distance).unwrap_or(Ordering::Equal)
     }
 }
 
@@ -72,6 +120,18 @@ pub struct HnswIndex {
     // Maps User ID -> Logical Index
     map: RwLock<HashMap<u64, usize>>,
 
+    // Deleted flag per Logical Index. Tombstoned nodes keep their links for navigation.
+    tombstones: RwLock<Vec<bool>>,
+
+    // Tombstoned Logical Indices, handed out again before the arenas grow
+    free_slots: Vec<usize>,
+
+    // Optional metadata per Logical Index, evaluated by filtered searches
+    metadata: RwLock<Vec<Option<Box<Metadata>>>>,
+
+    // Snapshot copy in progress, filled between writes (see `begin_snapshot`)
+    pending_snapshot: std::cell::RefCell<Option<FrozenIndex>>,
+
     // Entry Point for the HNSW Graph
     entry_point: AtomicU32,
     max_layer_active: AtomicU32,
@@ -80,20 +140,30 @@ pub struct HnswIndex {
     visited_tags: RwLock<Vec<u32>>,
     global_search_id: AtomicU32,
 
-    // High-speed distance kernel
+    // High-speed distance kernels for the configured metric
+    metric: Metric,
     metric_kernel: simd::SimdFunc,
+    quantized_kernel: simd::QuantizedFunc,
 
     // Telemetry (Rule 11: Minimal Observer Effect)
     pub dist_calc_count: std::cell::Cell<u64>,
 }
 
 impl HnswIndex {
+    /// Default links per node on upper layers.
+    pub const DEFAULT_M: usize = 16;
+    /// Default build beam width.
+    pub const DEFAULT_EF_CONSTRUCTION: usize = 128;
+
     pub fn new(dimension: usize, max_elements: usize) -> Self {
-        info!("Initializing Multi-Layer HNSW Index (Dim: {}, Max: {})", dimension, max_elements);
+        Self::with_params(dimension, max_elements, Self::DEFAULT_M, Self::DEFAULT_EF_CONSTRUCTION, Metric::default())
+    }
+
+    /// Builds an index with explicit HNSW parameters. Layer 0 keeps `2 * m` links per node.
+    pub fn with_params(dimension: usize, max_elements: usize, m: usize, ef_construction: usize, metric: Metric) -> Self {
+        info!("Initializing Multi-Layer HNSW Index (Dim: {}, Max: {}, M: {}, EF: {}, Metric: {})", dimension, max_elements, m, ef_construction, metric);
         
-        let m = 16;
-        let m0 = 32;
-        let ef_construction = 128;
+        let m0 = m * 2;
         let max_layers = 16; 
         
         let link_stride = m0 + (max_layers - 1) * m;
@@ -112,15 +182,203 @@ impl HnswIndex {
             external_ids: RwLock::new(Vec::with_capacity(1000)),
             link_arena: RwLock::new(Vec::with_capacity(1000 * link_stride)),
             map: RwLock::new(HashMap::with_capacity(1000)),
+            tombstones: RwLock::new(Vec::with_capacity(1000)),
+            free_slots: Vec::new(),
+            metadata: RwLock::new(Vec::with_capacity(1000)),
+            pending_snapshot: std::cell::RefCell::new(None),
             entry_point: AtomicU32::new(u32::MAX),
             max_layer_active: AtomicU32::new(0),
             visited_tags: RwLock::new(Vec::with_capacity(1000)),
             global_search_id: AtomicU32::new(1),
-            metric_kernel: simd::get_vector_kernel(),
+            metric,
+            metric_kernel: metric.kernel(),
+            quantized_kernel: simd::get_quantized_kernel(),
             dist_calc_count: std::cell::Cell::new(0),
         }
     }
 
+    /// Returns the vector dimension this index was built for.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the distance metric this index ranks by.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Whether an insert of `id` would be kept: it replaces a live entry, or capacity remains.
+    pub fn has_room_for(&self, id: u64) -> bool {
+        let map = self.map.read().unwrap();
+        map.len() < self.max_elements || map.contains_key(&id)
+    }
+
+    /// Starts a point-in-time copy of the graph for a snapshot, replacing any copy in progress.
+    ///
+    /// Nothing is copied yet: `continue_snapshot` copies the nodes in bounded steps, and
+    /// every write in between first saves the pre-image of the node it touches.
+    pub fn begin_snapshot(&self) {
+        let nodes = self.external_ids.read().unwrap().len();
+        let dim = self.dimension;
+        let stride = self.link_stride();
+        *self.pending_snapshot.borrow_mut() = Some(FrozenIndex {
+            header: [self.dimension as u32, self.m as u32, self.ef_construction as u32, self.metric.code() as u32],
+            dimension: dim,
+            stride,
+            entry_point: self.entry_point.load(AtomicOrdering::Relaxed),
+            max_layer: self.max_layer_active.load(AtomicOrdering::Relaxed),
+            // Zeroed allocations stay lazy: the pages are only touched when a node is copied
+            arena: vec![0.0; nodes * dim],
+            q_arena: vec![0; nodes * dim],
+            magnitudes: vec![0.0; nodes],
+            external_ids: vec![0; nodes],
+            tombstones: vec![false; nodes],
+            link_arena: vec![0; nodes * stride],
+            metadata: vec![None; nodes],
+            cursor: 0,
+            data_saved: vec![false; nodes],
+            links_saved: vec![false; nodes],
+        });
+    }
+
+    /// Copies up to `max_nodes` more nodes into the snapshot started by `begin_snapshot`.
+    /// Returns the finished image once every node is copied.
+    pub fn continue_snapshot(&self, max_nodes: usize) -> Option<FrozenIndex> {
+        let mut pending = self.pending_snapshot.borrow_mut();
+        let frozen = pending.as_mut()?;
+        let arena = self.arena.read().unwrap();
+        let q_arena = self.quantized_arena.read().unwrap();
+        let magnitudes = self.magnitudes.read().unwrap();
+        let external_ids = self.external_ids.read().unwrap();
+        let link_arena = self.link_arena.read().unwrap();
+        let tombstones = self.tombstones.read().unwrap();
+        let metadata = self.metadata.read().unwrap();
+
+        let end = frozen.cursor.saturating_add(max_nodes).min(frozen.nodes());
+        for node in frozen.cursor..end {
+            if !frozen.data_saved[node] {
+                frozen.copy_data(node, &arena, &q_arena, &magnitudes, &external_ids, &tombstones, &metadata);
+            }
+            if !frozen.links_saved[node] {
+                frozen.copy_links(node, &link_arena);
+            }
+        }
+        frozen.cursor = end;
+        if end < frozen.nodes() {
+            return None;
+        }
+        pending.take()
+    }
+
+    /// Saves the links of `node` into a snapshot in progress before they change.
+    fn preserve_links(&self, node: usize, link_arena: &[u32]) {
+        if let Some(frozen) = self.pending_snapshot.borrow_mut().as_mut() {
+            if node >= frozen.cursor && node < frozen.nodes() && !frozen.links_saved[node] {
+                frozen.copy_links(node, link_arena);
+                frozen.links_saved[node] = true;
+            }
+        }
+    }
+
+    /// Saves the vector, ID, tombstone and metadata of `node` into a snapshot in progress
+    /// before they change.
+    fn preserve_data(&self, node: usize, arena: &[f32], external_ids: &[u64]) {
+        if let Some(frozen) = self.pending_snapshot.borrow_mut().as_mut() {
+            if node >= frozen.cursor && node < frozen.nodes() && !frozen.data_saved[node] {
+                let q_arena = self.quantized_arena.read().unwrap();
+                let magnitudes = self.magnitudes.read().unwrap();
+                let tombstones = self.tombstones.read().unwrap();
+                let metadata = self.metadata.read().unwrap();
+                frozen.copy_data(node, arena, &q_arena, &magnitudes, external_ids, &tombstones, &metadata);
+                frozen.data_saved[node] = true;
+            }
+        }
+    }
+
+    /// Loads a graph written by `FrozenIndex::encode` into this (empty) index.
+    ///
+    /// # Errors
+    /// Returns `ErrorKind::InvalidData` if the snapshot was taken with other parameters
+    /// or is truncated. The index must be discarded on error.
+    pub fn restore_snapshot(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Snapshot rejected: {}", msg));
+        let mut r = ByteReader { bytes };
+        let params = [r.u32()?, r.u32()?, r.u32()?, r.u32()?];
+        if params != [self.dimension as u32, self.m as u32, self.ef_construction as u32, self.metric.code() as u32] {
+            return Err(invalid("index parameters differ from the collection"));
+        }
+        let nodes = r.u64()? as usize;
+        let entry_point = r.u32()?;
+        let max_layer = r.u32()?;
+        let dim = self.dimension;
+        let stride = self.link_stride();
+        // Check the size up front so a corrupted count cannot trigger a giant allocation
+        if nodes.checked_mul(dim * 5 + 13 + stride * 4).is_none_or(|len| len > r.bytes.len()) {
+            return Err(invalid("node count exceeds the snapshot size"));
+        }
+        if (entry_point != u32::MAX && entry_point as usize >= nodes) || max_layer as usize >= self.max_layers {
+            return Err(invalid("entry point out of range"));
+        }
+
+        let arena: Vec<f32> = r.take(nodes * dim * 4)?.chunks_exact(4).map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect();
+        let q_arena = r.take(nodes * dim)?.to_vec();
+        let magnitudes: Vec<f32> = r.take(nodes * 4)?.chunks_exact(4).map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect();
+        let external_ids: Vec<u64> = r.take(nodes * 8)?.chunks_exact(8).map(|c| u64::from_le_bytes(c.try_into().unwrap_or([0; 8]))).collect();
+        let tombstones: Vec<bool> = r.take(nodes)?.iter().map(|&t| t != 0).collect();
+        let link_arena: Vec<u32> = r.take(nodes * stride * 4)?.chunks_exact(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect();
+        if link_arena.iter().any(|&l| l != u32::MAX && l as usize >= nodes) {
+            return Err(invalid("link out of range"));
+        }
+        let mut metadata = Vec::with_capacity(nodes);
+        for _ in 0..nodes {
+            metadata.push(match r.u8()? {
+                0 => None,
+                _ => {
+                    // parse_block expects the length prefix, so keep it in the slice
+                    let block = r.bytes;
+                    let len = r.u32()? as usize;
+                    r.take(len)?;
+                    Some(Box::new(Metadata::parse_block(&block[..4 + len]).map_err(invalid)?.unwrap_or_default()))
+                }
+            });
+        }
+        if !r.bytes.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+
+        let mut map = HashMap::with_capacity(nodes);
+        let mut free_slots = Vec::new();
+        for (node, (&id, &dead)) in external_ids.iter().zip(&tombstones).enumerate() {
+            if dead {
+                free_slots.push(node);
+            } else {
+                map.insert(id, node);
+            }
+        }
+
+        *self.arena.get_mut().unwrap() = arena;
+        *self.quantized_arena.get_mut().unwrap() = q_arena;
+        *self.magnitudes.get_mut().unwrap() = magnitudes;
+        *self.external_ids.get_mut().unwrap() = external_ids;
+        *self.tombstones.get_mut().unwrap() = tombstones;
+        *self.link_arena.get_mut().unwrap() = link_arena;
+        *self.metadata.get_mut().unwrap() = metadata;
+        *self.map.get_mut().unwrap() = map;
+        self.free_slots = free_slots;
+        *self.visited_tags.get_mut().unwrap() = vec![0; nodes];
+        self.entry_point.store(entry_point, AtomicOrdering::Relaxed);
+        self.max_layer_active.store(max_layer, AtomicOrdering::Relaxed);
+        Ok(())
+    }
+
+    /// Coarse distance from the quantized query to node `node` (integer kernel + metric rescale).
+    #[inline(always)]
+    fn coarse_distance(&self, query: &QuantizedQuery, q_arena: &[u8], magnitudes: &[f32], node: usize) -> f32 {
+        // SAFETY: `node` is a logical index below `q_arena.len() / dimension`.
+        let neg_dot = unsafe { (self.quantized_kernel)(query.codes.as_ptr(), q_arena.as_ptr().add(node * self.dimension), self.dimension) };
+        self.metric.coarse_distance(neg_dot, query.code_sum, query.magnitude, magnitudes[node])
+    }
+
     #[inline(always)]
     fn link_stride(&self) -> usize {
         self.m0 + (self.max_layers - 1) * self.m
@@ -141,14 +399,17 @@ impl HnswIndex {
         &slice[..count]
     }
 
-    fn add_neighbor(&self, link_arena: &mut [u32], node_id: usize, level: usize, neighbor_id: u32) {
+    /// Appends a link if there is a free slot. Returns false if the list is full.
+    fn add_neighbor(&self, link_arena: &mut [u32], node_id: usize, level: usize, neighbor_id: u32) -> bool {
+        self.preserve_links(node_id, link_arena);
         let offset = self.link_offset(node_id, level);
         let max_links = if level == 0 { self.m0 } else { self.m };
         let slice = &mut link_arena[offset..offset + max_links];
-        for i in 0..max_links {
-            if slice[i] == u32::MAX { slice[i] = neighbor_id; return; }
-            if slice[i] == neighbor_id { return; }
+        for slot in slice.iter_mut() {
+            if *slot == u32::MAX { *slot = neighbor_id; return true; }
+            if *slot == neighbor_id { return true; }
         }
+        false
     }
 
     fn next_search_version(&self) -> u32 {
@@ -199,25 +460,30 @@ impl HnswIndex {
     #[allow(clippy::too_many_arguments)]
     fn search_layer_u8(
         &self,
-        q_i8: &[i8],
+        query: &QuantizedQuery,
         ep: usize,
         ef: usize,
         level: usize,
         q_arena: &[u8],
+        magnitudes: &[f32],
         link_arena: &[u32],
         visited: &mut [u32],
         search_id: u32,
+        is_live: impl Fn(usize) -> bool,
     ) -> Vec<Candidate> {
+        // Rejected nodes (tombstoned or filtered out) still route the beam (graph navigation)
+        // but never enter the result set, so a filtered search still fills `ef` matches
         let mut candidates = BinaryHeap::new();
-        let mut results = BinaryHeap::new();
-        let dist = unsafe { simd::dot_product_u8_avx2(q_i8.as_ptr(), q_arena.as_ptr().add(ep * self.dimension), self.dimension) } as f32;
+        let mut results: BinaryHeap<MaxCandidate> = BinaryHeap::new();
+        let dist = self.coarse_distance(query, q_arena, magnitudes, ep);
         self.dist_calc_count.set(self.dist_calc_count.get() + 1);
         let entry = Candidate { node_id: ep, distance: dist };
         candidates.push(MinCandidate(entry.clone()));
-        results.push(MaxCandidate(entry));
+        if is_live(ep) { results.push(MaxCandidate(entry)); }
         visited[ep] = search_id;
         while let Some(MinCandidate(top)) = candidates.pop() {
-            if top.distance > results.peek().unwrap().0.distance && results.len() >= ef { break; }
+            let worst = results.peek().map_or(f32::INFINITY, |mc| mc.0.distance);
+            if top.distance > worst && results.len() >= ef { break; }
             let neighbors = self.get_neighbors(link_arena, top.node_id, level);
             for (i, &nid) in neighbors.iter().enumerate() {
                 if let Some(&next_id) = neighbors.get(i + 1) {
@@ -226,13 +492,16 @@ impl HnswIndex {
                 }
                 if visited[nid as usize] == search_id { continue; }
                 visited[nid as usize] = search_id;
-                let d = unsafe { simd::dot_product_u8_avx2(q_i8.as_ptr(), q_arena.as_ptr().add(nid as usize * self.dimension), self.dimension) } as f32;
+                let d = self.coarse_distance(query, q_arena, magnitudes, nid as usize);
                 self.dist_calc_count.set(self.dist_calc_count.get() + 1);
-                if results.len() < ef || d < results.peek().unwrap().0.distance {
+                let worst = results.peek().map_or(f32::INFINITY, |mc| mc.0.distance);
+                if results.len() < ef || d < worst {
                     let c = Candidate { node_id: nid as usize, distance: d };
                     candidates.push(MinCandidate(c.clone()));
-                    results.push(MaxCandidate(c));
-                    if results.len() > ef { results.pop(); }
+                    if is_live(c.node_id) {
+                        results.push(MaxCandidate(c));
+                        if results.len() > ef { results.pop(); }
+                    }
                 }
             }
         }
@@ -249,24 +518,106 @@ impl HnswIndex {
         level
     }
 
-    fn prune_connections(&self, link_arena: &mut [u32], node_id: usize, level: usize, arena: &[f32]) {
+    /// Links `node_id -> neighbor_id`, evicting the farthest existing link when the list is full.
+    ///
+    /// Without eviction, back-links to late inserts would be dropped once early nodes saturate
+    /// their lists, leaving new nodes unreachable from the entry point.
+    fn link_or_evict_farthest(&self, link_arena: &mut [u32], node_id: usize, level: usize, neighbor_id: u32, arena: &[f32]) {
+        if self.add_neighbor(link_arena, node_id, level, neighbor_id) { return; }
         let offset = self.link_offset(node_id, level);
         let max_links = if level == 0 { self.m0 } else { self.m };
+        // SAFETY: node_id and every linked id are logical indices below arena.len() / dimension.
+        let node_ptr = unsafe { arena.as_ptr().add(node_id * self.dimension) };
+        let dist_to = |nid: u32| unsafe { (self.metric_kernel)(node_ptr, arena.as_ptr().add(nid as usize * self.dimension), self.dimension) };
         let slice = &mut link_arena[offset..offset + max_links];
-        let mut neighbors = Vec::with_capacity(max_links);
-        for &nid in slice.iter() {
-            if nid == u32::MAX { break; }
-            let d = unsafe { (self.metric_kernel)(arena.as_ptr().add(node_id * self.dimension), arena.as_ptr().add(nid as usize * self.dimension), self.dimension) };
-            neighbors.push((nid, d));
+        let mut worst_slot = 0;
+        let mut worst_dist = f32::NEG_INFINITY;
+        for (slot, &nid) in slice.iter().enumerate() {
+            let d = dist_to(nid);
+            if d > worst_dist { worst_dist = d; worst_slot = slot; }
+        }
+        if dist_to(neighbor_id) < worst_dist { slice[worst_slot] = neighbor_id; }
+    }
+}
+
+/// A point-in-time image of an `HnswIndex`, copied by `HnswIndex::continue_snapshot`.
+/// Owns its arenas, so it can be serialized away from the reactor thread.
+pub struct FrozenIndex {
+    header: [u32; 4],
+    dimension: usize,
+    stride: usize,
+    entry_point: u32,
+    max_layer: u32,
+    arena: Vec<f32>,
+    q_arena: Vec<u8>,
+    magnitudes: Vec<f32>,
+    external_ids: Vec<u64>,
+    tombstones: Vec<bool>,
+    link_arena: Vec<u32>,
+    metadata: Vec<Option<Box<Metadata>>>,
+    // Nodes below the cursor are copied; above it, the flags mark pre-images saved early
+    cursor: usize,
+    data_saved: Vec<bool>,
+    links_saved: Vec<bool>,
+}
+
+impl FrozenIndex {
+    fn nodes(&self) -> usize {
+        self.external_ids.len()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn copy_data(&mut self, node: usize, arena: &[f32], q_arena: &[u8], magnitudes: &[f32], external_ids: &[u64], tombstones: &[bool], metadata: &[Option<Box<Metadata>>]) {
+        let span = node * self.dimension..(node + 1) * self.dimension;
+        self.arena[span.clone()].copy_from_slice(&arena[span.clone()]);
+        self.q_arena[span.clone()].copy_from_slice(&q_arena[span]);
+        self.magnitudes[node] = magnitudes[node];
+        self.external_ids[node] = external_ids[node];
+        self.tombstones[node] = tombstones[node];
+        self.metadata[node] = metadata[node].clone();
+    }
+
+    fn copy_links(&mut self, node: usize, link_arena: &[u32]) {
+        let span = node * self.stride..(node + 1) * self.stride;
+        self.link_arena[span.clone()].copy_from_slice(&link_arena[span]);
+    }
+
+    /// Serializes the graph (arenas, links, tombstones, metadata and entry point) into `out`.
+    ///
+    /// # Format (Little-Endian)
+    /// A header of `dimension`, `m`, `ef_construction` (u32 each), `metric` (u32), node count (u64),
+    /// entry point and top layer (u32 each), followed by one section per arena, each holding
+    /// `node count` entries. The ID map is not stored: it is rebuilt from the live nodes.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let nodes = self.nodes();
+        out.reserve(40 + nodes * (self.dimension * 5 + 13 + self.stride * 4));
+        for value in self.header {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out.extend_from_slice(&(nodes as u64).to_le_bytes());
+        out.extend_from_slice(&self.entry_point.to_le_bytes());
+        out.extend_from_slice(&self.max_layer.to_le_bytes());
+
+        self.arena.iter().for_each(|v| out.extend_from_slice(&v.to_le_bytes()));
+        out.extend_from_slice(&self.q_arena);
+        self.magnitudes.iter().for_each(|v| out.extend_from_slice(&v.to_le_bytes()));
+        self.external_ids.iter().for_each(|v| out.extend_from_slice(&v.to_le_bytes()));
+        self.tombstones.iter().for_each(|&t| out.push(t as u8));
+        self.link_arena.iter().for_each(|v| out.extend_from_slice(&v.to_le_bytes()));
+        for entry in self.metadata.iter() {
+            match entry {
+                Some(m) => {
+                    out.push(1);
+                    m.encode_block(out);
+                }
+                None => out.push(0),
+            }
         }
-        if neighbors.len() <= max_links { return; }
-        neighbors.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        for i in 0..max_links { slice[i] = neighbors[i].0; }
     }
 }
 
 impl VectorIndex for HnswIndex {
-    fn insert(&mut self, id: u64, vector: &[f32]) {
+    fn insert_with_metadata(&mut self, id: u64, vector: &[f32], metadata: Option<Metadata>) {
         if vector.len() != self.dimension {
             error!("Shard {} HNSW -> Dimension Mismatch (Expected {}, Got {}). Skipping insert to avoid panic.", 
                 "local", self.dimension, vector.len());
@@ -275,19 +626,44 @@ impl VectorIndex for HnswIndex {
         let mut arena = self.arena.write().unwrap();
         let mut map = self.map.write().unwrap();
         let mut external_ids = self.external_ids.write().unwrap();
-        if map.len() >= self.max_elements { return; }
-        let logical_idx = arena.len() / self.dimension;
-        arena.extend_from_slice(vector);
+        // Capacity counts live IDs only: a re-upsert replaces an existing entry
+        let previous = map.get(&id).copied();
+        if previous.is_none() && map.len() >= self.max_elements { return; }
         let (q_vec, mag) = quantization::ScalarQuantizer::quantize_vector(vector);
-        self.quantized_arena.write().unwrap().extend_from_slice(&q_vec);
-        self.magnitudes.write().unwrap().push(mag);
-        map.insert(id, logical_idx);
-        external_ids.push(id);
         let mut link_arena = self.link_arena.write().unwrap();
         let mut visited_tags = self.visited_tags.write().unwrap();
-        
-        // Lazy Resize (Rule 3: Avoid upfront zeroing of giant arrays)
         let stride = self.link_stride();
+
+        // A re-upsert rewrites its own node and a new ID takes a tombstoned one, so the arenas
+        // never hold more than `max_elements` nodes. The reused node is relinked below;
+        // stale links pointing at it only cost navigation detours.
+        let reused = previous.or_else(|| self.free_slots.pop());
+        let logical_idx = match reused {
+            Some(idx) => {
+                self.preserve_data(idx, &arena, &external_ids);
+                let start = idx * self.dimension;
+                arena[start..start + self.dimension].copy_from_slice(vector);
+                self.quantized_arena.write().unwrap()[start..start + self.dimension].copy_from_slice(&q_vec);
+                self.magnitudes.write().unwrap()[idx] = mag;
+                external_ids[idx] = id;
+                self.tombstones.write().unwrap()[idx] = false;
+                self.metadata.write().unwrap()[idx] = metadata.map(Box::new);
+                idx
+            }
+            None => {
+                let idx = arena.len() / self.dimension;
+                arena.extend_from_slice(vector);
+                self.quantized_arena.write().unwrap().extend_from_slice(&q_vec);
+                self.magnitudes.write().unwrap().push(mag);
+                external_ids.push(id);
+                self.tombstones.write().unwrap().push(false);
+                self.metadata.write().unwrap().push(metadata.map(Box::new));
+                idx
+            }
+        };
+        map.insert(id, logical_idx);
+
+        // Lazy Resize (Rule 3: Avoid upfront zeroing of giant arrays)
         if link_arena.len() < (logical_idx + 1) * stride {
             link_arena.resize((logical_idx + 1) * stride, u32::MAX);
         }
@@ -304,23 +680,27 @@ impl VectorIndex for HnswIndex {
             return;
         }
         let mut curr_obj = ep as usize;
-        let search_id = self.next_search_version();
         if node_level < max_l {
             for level in (node_level + 1..=max_l).rev() {
-                let candidates = self.search_layer_f32(vector, curr_obj, 1, level, &arena, &link_arena, &mut visited_tags, search_id);
-                if let Some(c) = candidates.get(0) { curr_obj = c.node_id; }
+                let candidates = self.search_layer_f32(vector, curr_obj, 1, level, &arena, &link_arena, &mut visited_tags, self.next_search_version());
+                if let Some(c) = candidates.first() { curr_obj = c.node_id; }
             }
         }
         let start_layer = std::cmp::min(node_level, max_l);
         for level in (0..=start_layer).rev() {
-            let candidates = self.search_layer_f32(vector, curr_obj, self.ef_construction, level, &arena, &link_arena, &mut visited_tags, search_id);
+            let candidates = self.search_layer_f32(vector, curr_obj, self.ef_construction, level, &arena, &link_arena, &mut visited_tags, self.next_search_version());
             let max_neighbors = if level == 0 { self.m0 } else { self.m };
-            for c in candidates.iter().take(max_neighbors) {
+            if reused.is_some() {
+                // Old links only served the search above; the node is linked anew on this layer
+                self.preserve_links(logical_idx, &link_arena);
+                let offset = self.link_offset(logical_idx, level);
+                link_arena[offset..offset + max_neighbors].fill(u32::MAX);
+            }
+            for c in candidates.iter().filter(|c| c.node_id != logical_idx).take(max_neighbors) {
                 self.add_neighbor(&mut link_arena, logical_idx, level, c.node_id as u32);
-                self.add_neighbor(&mut link_arena, c.node_id, level, logical_idx as u32);
-                self.prune_connections(&mut link_arena, c.node_id, level, &arena);
+                self.link_or_evict_farthest(&mut link_arena, c.node_id, level, logical_idx as u32, &arena);
             }
-            if let Some(top) = candidates.get(0) { curr_obj = top.node_id; }
+            if let Some(top) = candidates.first() { curr_obj = top.node_id; }
         }
         if node_level > max_l {
             self.entry_point.store(logical_idx as u32, AtomicOrdering::Relaxed);
@@ -328,24 +708,66 @@ impl VectorIndex for HnswIndex {
         }
     }
 
-    fn search(&self, query: &[f32], top_k: usize) -> Vec<(u64, f32)> {
+    fn delete(&mut self, id: u64) -> bool {
+        let mut map = self.map.write().unwrap();
+        let logical_idx = match map.remove(&id) {
+            Some(idx) => idx,
+            None => return false,
+        };
+        self.preserve_data(logical_idx, &self.arena.read().unwrap(), &self.external_ids.read().unwrap());
+        self.tombstones.write().unwrap()[logical_idx] = true;
+        self.free_slots.push(logical_idx);
+        true
+    }
+
+    fn get(&self, id: u64, out: &mut [f32]) -> bool {
+        if out.len() != self.dimension { return false; }
+        let logical_idx = match self.map.read().unwrap().get(&id) {
+            Some(&idx) => idx,
+            None => return false,
+        };
+        let arena = self.arena.read().unwrap();
+        let start = logical_idx * self.dimension;
+        out.copy_from_slice(&arena[start..start + self.dimension]);
+        true
+    }
+
+    fn metadata(&self, id: u64) -> Option<Metadata> {
+        let logical_idx = *self.map.read().unwrap().get(&id)?;
+        self.metadata.read().unwrap()[logical_idx].as_deref().cloned()
+    }
+
+    fn search(&self, query: &[f32], params: SearchParams) -> Vec<(u64, f32)> {
+        if query.len() != self.dimension {
+            error!("HNSW -> Query Dimension Mismatch (Expected {}, Got {}).", self.dimension, query.len());
+            return Vec::new();
+        }
         let arena = self.arena.read().unwrap();
         let link_arena = self.link_arena.read().unwrap();
         let external_ids = self.external_ids.read().unwrap();
         let q_arena = self.quantized_arena.read().unwrap();
-        let (q_i8, _) = quantization::ScalarQuantizer::quantize_query(query);
+        let magnitudes = self.magnitudes.read().unwrap();
+        let q_query = QuantizedQuery::new(query);
         let ep = self.entry_point.load(AtomicOrdering::Relaxed);
         let max_l = self.max_layer_active.load(AtomicOrdering::Relaxed) as usize;
         if ep == u32::MAX || arena.is_empty() { return Vec::new(); }
         let mut visited_tags = self.visited_tags.write().unwrap();
-        let search_id = self.next_search_version();
         let mut curr_obj = ep as usize;
         for level in (1..=max_l).rev() {
-            let candidates = self.search_layer_u8(&q_i8, curr_obj, 1, level, &q_arena, &link_arena, &mut visited_tags, search_id);
-            if let Some(c) = candidates.get(0) { curr_obj = c.node_id; }
+            let candidates = self.search_layer_u8(&q_query, curr_obj, 1, level, &q_arena, &magnitudes, &link_arena, &mut visited_tags, self.next_search_version(), |_| true);
+            if let Some(c) = candidates.first() { curr_obj = c.node_id; }
         }
-        let ef_search = top_k.max(self.ef_construction);
-        let coarse_candidates = self.search_layer_u8(&q_i8, curr_obj, ef_search, 0, &q_arena, &link_arena, &mut visited_tags, search_id);
+        // The beam defaults to ef_construction and must hold at least top_k candidates
+        let ef_search = match params.ef_search {
+            0 => self.ef_construction,
+            ef => ef,
+        }.max(params.top_k);
+        let tombstones = self.tombstones.read().unwrap();
+        let metadata = self.metadata.read().unwrap();
+        let is_live = |node: usize| {
+            !tombstones[node] && params.filter.is_none_or(|f| f.matches(metadata[node].as_deref()))
+        };
+        let coarse_candidates = self.search_layer_u8(&q_query, curr_obj, ef_search, 0, &q_arena, &magnitudes, &link_arena, &mut visited_tags, self.next_search_version(), is_live);
         let mut refined: Vec<(u64, f32)> = coarse_candidates.into_iter()
             .map(|c| {
                 let nid = c.node_id;
@@ -354,7 +776,7 @@ impl VectorIndex for HnswIndex {
                 (external_ids[nid], d)
             }).collect();
         refined.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        refined.truncate(top_k);
+        refined.truncate(params.top_k);
         refined
     }
 }
@@ -368,8 +790,259 @@ mod tests {
         index.insert(0, &[1.0, 0.0, 0.0]);
         index.insert(1, &[0.0, 1.0, 0.0]);
         index.insert(2, &[0.0, 0.0, 1.0]);
-        let results = index.search(&[0.1, 0.9, 0.1], 1);
+        let results = index.search(&[0.1, 0.9, 0.1], SearchParams::new(1));
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].0, 1);
     }
+
+    #[test]
+    fn test_deleted_nodes_are_skipped_but_still_route() {
+        let mut index = HnswIndex::new(3, 100);
+        // Unit vectors on a quarter circle: dot-product ranking follows the angle
+        let point = |id: u64| {
+            let theta = id as f32 * std::f32::consts::FRAC_PI_2 / 50.0;
+            [theta.cos(), theta.sin(), 0.0]
+        };
+        for id in 0..50u64 {
+            index.insert(id, &point(id));
+        }
+        assert!(index.delete(25));
+        assert!(!index.delete(25));
+        assert!(!index.delete(999));
+
+        let results = index.search(&point(25), SearchParams::new(10));
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|(id, _)| *id != 25));
+
+        // Re-inserting a deleted ID makes it visible again
+        index.insert(25, &point(25));
+        let results = index.search(&point(25), SearchParams::new(1));
+        assert_eq!(results[0].0, 25);
+    }
+
+    #[test]
+    fn test_ef_search_is_floored_at_top_k() {
+        let mut index = HnswIndex::with_params(2, 200, 4, 8, Metric::L2);
+        for id in 0..200u64 {
+            index.insert(id, &[id as f32, 0.0]);
+        }
+        // A beam narrower than top_k still yields top_k hits
+        let narrow = index.search(&[100.0, 0.0], SearchParams::new(20).with_ef_search(1));
+        assert_eq!(narrow.len(), 20);
+
+        // A wide beam on a 1-D line is exhaustive enough to be exact
+        let wide = index.search(&[100.2, 0.0], SearchParams::new(5).with_ef_search(200));
+        let ids: Vec<u64> = wide.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![100, 101, 99, 102, 98]);
+    }
+
+    #[test]
+    fn test_filter_is_applied_during_traversal() {
+        let mut index = HnswIndex::with_params(2, 500, 8, 32, Metric::L2);
+        for id in 0..500u64 {
+            // One item in ten is in stock in "eu"; the rest are close decoys
+            let region = if id % 10 == 0 { "eu" } else { "us" };
+            let tags = vortex_rpc::Metadata::new().keyword("region", region).int("stock", (id % 3) as i64);
+            index.insert_with_metadata(id, &[id as f32, 0.0], Some(tags));
+        }
+        let in_stock_eu = vortex_rpc::Filter::And(vec![
+            vortex_rpc::Filter::eq("region", "eu"),
+            vortex_rpc::Filter::range("stock", 1, i64::MAX),
+        ]);
+        // Matching IDs are multiples of 10 with id % 3 != 0: a post-filter of the
+        // top 10 unfiltered hits would return at most one of them
+        let results = index.search(&[250.0, 0.0], SearchParams::new(10).with_filter(Some(&in_stock_eu)));
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|(id, _)| id % 10 == 0 && id % 3 != 0));
+        assert_eq!(results[0].0, 250);
+
+        // Re-upserting without metadata drops the old tags
+        index.insert(250, &[250.0, 0.0]);
+        let results = index.search(&[250.0, 0.0], SearchParams::new(1).with_filter(Some(&in_stock_eu)));
+        assert_ne!(results[0].0, 250);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut index = HnswIndex::with_params(4, 100, 4, 16, Metric::Cosine);
+        for id in 0..60u64 {
+            let v = [id as f32, 1.0, (id % 7) as f32, -(id as f32) / 3.0];
+            let tags = vortex_rpc::Metadata::new().int("bucket", (id % 4) as i64);
+            index.insert_with_metadata(id, &v, if id % 2 == 0 { Some(tags) } else { None });
+        }
+        index.delete(10);
+        index.insert(11, &[0.5, 0.5, 0.5, 0.5]);
+
+        let mut body = Vec::new();
+        index.begin_snapshot();
+        index.continue_snapshot(usize::MAX).unwrap().encode(&mut body);
+        let mut restored = HnswIndex::with_params(4, 100, 4, 16, Metric::Cosine);
+        restored.restore_snapshot(&body).unwrap();
+
+        let query = [3.0, 1.0, 2.0, -1.0];
+        let bucket = vortex_rpc::Filter::range("bucket", 2, 2);
+        for params in [SearchParams::new(10), SearchParams::new(5).with_filter(Some(&bucket))] {
+            assert_eq!(restored.search(&query, params), index.search(&query, params));
+        }
+        let mut out = [0.0f32; 4];
+        assert!(!restored.get(10, &mut out));
+        assert!(restored.get(11, &mut out));
+        assert_eq!(out, [0.5; 4]);
+
+        // The restored graph keeps accepting inserts
+        restored.insert(100, &[61.0, 1.0, 5.0, -20.0]);
+        assert_eq!(restored.search(&[61.0, 1.0, 5.0, -20.0], SearchParams::new(1))[0].0, 100);
+
+        // Parameters and truncation are checked
+        assert!(HnswIndex::with_params(4, 100, 8, 16, Metric::Cosine).restore_snapshot(&body).is_err());
+        assert!(HnswIndex::with_params(4, 100, 4, 16, Metric::Cosine).restore_snapshot(&body[..body.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_chunked_snapshot_keeps_the_starting_image() {
+        let mut index = HnswIndex::with_params(4, 200, 4, 16, Metric::L2);
+        for id in 0..100u64 {
+            let v = [id as f32, (id % 5) as f32, 1.0, -(id as f32)];
+            index.insert_with_metadata(id, &v, (id % 3 == 0).then(|| vortex_rpc::Metadata::new().int("n", id as i64)));
+        }
+        index.begin_snapshot();
+        let mut expected = Vec::new();
+        index.continue_snapshot(usize::MAX).unwrap().encode(&mut expected);
+
+        // Writes land between chunks: new nodes, re-upserts, deletes and reused slots
+        index.begin_snapshot();
+        let mut image = None;
+        for round in 0..20u64 {
+            index.insert(200 + round, &[round as f32 * 5.0, 2.0, 1.0, 0.0]);
+            index.insert(99 - round * 4, &[0.5, 0.5, round as f32, 0.5]);
+            index.delete(round * 3);
+            image = index.continue_snapshot(7);
+            if image.is_some() {
+                break;
+            }
+        }
+        let mut body = Vec::new();
+        image.expect("copy did not finish").encode(&mut body);
+        assert_eq!(body, expected);
+    }
+
+    #[test]
+    fn test_get_returns_latest_vector() {
+        let mut index = HnswIndex::new(3, 10);
+        let mut out = [0.0f32; 3];
+        assert!(!index.get(7, &mut out));
+
+        index.insert(7, &[0.5, -1.0, 2.0]);
+        assert!(index.get(7, &mut out));
+        assert_eq!(out, [0.5, -1.0, 2.0]);
+
+        index.insert(7, &[3.0, 0.0, 0.0]);
+        assert!(index.get(7, &mut out));
+        assert_eq!(out, [3.0, 0.0, 0.0]);
+        assert_eq!(index.metadata(7), None);
+
+        // Metadata is replaced along with the vector
+        let tags = vortex_rpc::Metadata::new().int("stock", 3);
+        index.insert_with_metadata(7, &[3.0, 0.0, 0.0], Some(tags.clone()));
+        assert_eq!(index.metadata(7), Some(tags));
+        index.insert(7, &[3.0, 0.0, 0.0]);
+        assert_eq!(index.metadata(7), None);
+
+        index.delete(7);
+        assert!(!index.get(7, &mut out));
+        assert_eq!(index.metadata(7), None);
+    }
+
+    #[test]
+    fn test_reupsert_replaces_vector() {
+        let mut index = HnswIndex::new(3, 2);
+        index.insert(0, &[1.0, 0.0, 0.0]);
+        index.insert(1, &[0.0, 1.0, 0.0]);
+
+        // At capacity, but replacing an existing ID must still go through
+        index.insert(0, &[0.0, 0.0, 1.0]);
+        let results = index.search(&[0.0, 0.0, 1.0], SearchParams::new(3));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, 0);
+        assert_eq!(results.iter().filter(|(id, _)| *id == 0).count(), 1);
+
+        // The old vector is gone, not just outranked
+        let results = index.search(&[1.0, 0.0, 0.0], SearchParams::new(3));
+        assert!(results.iter().all(|&(id, d)| id != 0 || d > -0.5));
+
+        // New IDs are still refused once live entries reach max_elements
+        index.insert(2, &[1.0, 1.0, 0.0]);
+        assert!(index.search(&[1.0, 1.0, 0.0], SearchParams::new(3)).iter().all(|(id, _)| *id != 2));
+    }
+
+    #[test]
+    fn test_churn_reuses_node_slots() {
+        let mut index = HnswIndex::with_params(2, 50, 4, 16, Metric::L2);
+        for round in 0..20 {
+            for id in 0..50u64 {
+                index.insert(id, &[id as f32, round as f32]);
+            }
+        }
+        // Deleted IDs hand their nodes to new ones
+        for id in 0..10u64 {
+            assert!(index.delete(id));
+        }
+        for id in 100..110u64 {
+            index.insert(id, &[(id - 100) as f32 * 4.0 + 10.5, 19.0]);
+        }
+        assert_eq!(index.external_ids.read().unwrap().len(), 50);
+
+        for id in 10..50u64 {
+            assert_eq!(index.search(&[id as f32, 19.0], SearchParams::new(1))[0].0, id);
+        }
+        for id in 100..110u64 {
+            assert_eq!(index.search(&[(id - 100) as f32 * 4.0 + 10.5, 19.0], SearchParams::new(1))[0].0, id);
+        }
+    }
+
+    #[test]
+    fn test_metric_changes_ranking() {
+        // Same direction, different lengths: inner product prefers the long vector,
+        // L2 the one closest in space, cosine the one closest in angle
+        let vectors: [(u64, [f32; 2]); 3] = [(0, [1.0, 0.0]), (1, [10.0, 0.0]), (2, [0.8, 0.6])];
+        let query = [0.9, 0.5];
+        let top = |metric: Metric| {
+            let mut index = HnswIndex::with_params(2, 10, 4, 16, metric);
+            for (id, v) in &vectors {
+                index.insert(*id, v);
+            }
+            let results = index.search(&query, SearchParams::new(3));
+            assert_eq!(results.len(), 3);
+            results[0].0
+        };
+        assert_eq!(top(Metric::InnerProduct), 1);
+        assert_eq!(top(Metric::L2), 2);
+        assert_eq!(top(Metric::Cosine), 2);
+
+        let mut index = HnswIndex::with_params(2, 10, 4, 16, Metric::L2);
+        index.insert(7, &[3.0, 4.0]);
+        let results = index.search(&[0.0, 0.0], SearchParams::new(1));
+        assert_eq!(results[0], (7, 25.0));
+    }
+
+    #[test]
+    fn test_late_inserts_stay_reachable() {
+        let mut index = HnswIndex::with_params(8, 1000, 4, 16, Metric::L2);
+        // Deterministic pseudo-random points (64-bit LCG)
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        };
+        let points: Vec<Vec<f32>> = (0..1000).map(|_| (0..8).map(|_| next()).collect()).collect();
+        for (id, point) in points.iter().enumerate() {
+            index.insert(id as u64, point);
+        }
+        // Early nodes saturate their link lists long before the last inserts arrive. Without
+        // eviction their back-links are dropped and the late inserts cannot be found at all.
+        let found = points.iter().enumerate().skip(900)
+            .filter(|(id, point)| index.search(point, SearchParams::new(1))[0].0 == *id as u64)
+            .count();
+        assert!(found >= 90, "only {} of 100 late inserts are reachable", found);
+    }
 }
@@ -3,7 +3,48 @@ pub mod distance;
 pub mod simd;
 pub mod quantization;
 
+use vortex_rpc::{Filter, Metadata};
+
+/// Per-query knobs of `VectorIndex::search`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchParams<'a> {
+    /// Number of nearest neighbors to return.
+    pub top_k: usize,
+    /// Candidate beam width. 0 selects the index default; never narrower than `top_k`.
+    pub ef_search: usize,
+    /// Only vectors whose metadata matches are returned. Checked during traversal.
+    pub filter: Option<&'a Filter>,
+}
+
+impl<'a> SearchParams<'a> {
+    /// Returns `top_k` neighbors with the index's default beam width.
+    pub fn new(top_k: usize) -> Self {
+        Self { top_k, ef_search: 0, filter: None }
+    }
+
+    /// Overrides the beam width: small for latency, large for recall.
+    pub fn with_ef_search(mut self, ef_search: usize) -> Self {
+        self.ef_search = ef_search;
+        self
+    }
+
+    /// Restricts the results to vectors matching `filter`.
+    pub fn with_filter<'b>(self, filter: Option<&'b Filter>) -> SearchParams<'b> {
+        SearchParams { top_k: self.top_k, ef_search: self.ef_search, filter }
+    }
+}
+
 pub trait VectorIndex {
-    fn insert(&mut self, id: u64, vector: &[f32]);
-    fn search(&self, query: &[f32], top_k: usize) -> Vec<(u64, f32)>;
+    fn insert(&mut self, id: u64, vector: &[f32]) {
+        self.insert_with_metadata(id, vector, None);
+    }
+    /// Inserts `vector`, replacing both the vector and the metadata of an existing `id`.
+    fn insert_with_metadata(&mut self, id: u64, vector: &[f32], metadata: Option<Metadata>);
+    /// Tombstones `id`. Returns false if the ID is not present.
+    fn delete(&mut self, id: u64) -> bool;
+    /// Copies the stored vector for `id` into `out`. Returns false if the ID is not present.
+    fn get(&self, id: u64, out: &mut [f32]) -> bool;
+    /// Returns a copy of the metadata stored with `id`, or `None` if it has none or is not present.
+    fn metadata(&self, id: u64) -> Option<Metadata>;
+    fn search(&self, query: &[f32], params: SearchParams) -> Vec<(u64, f32)>;
 }
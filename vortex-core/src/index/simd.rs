@@ -6,9 +6,15 @@ pub type SimdFunc = unsafe fn(*const f32, *const f32, usize) -> f32;
 /// Signature for distance functions specifically (e.g. Euclidean).
 pub type DistanceFunc = unsafe fn(*const f32, *const f32, usize) -> f32;
 
+/// Signature for the quantized coarse kernel: i8 query codes against u8 database codes.
+pub type QuantizedFunc = unsafe fn(*const i8, *const u8, usize) -> i32;
+
 /// The Reference Implementation.
 /// Safe loop fallback for "potato hardware."
 /// Returns: Negative Dot Product (Distance Proxy: Lower is Better)
+///
+/// # Safety
+/// `a` and `b` must be valid for reads of `n` consecutive `f32` values.
 pub unsafe fn scalar_dot(a: *const f32, b: *const f32, n: usize) -> f32 {
     let mut sum = 0.0f32;
     for i in 0..n {
@@ -17,7 +23,42 @@ pub unsafe fn scalar_dot(a: *const f32, b: *const f32, n: usize) -> f32 {
     -sum
 }
 
+/// Scalar Squared Euclidean Distance.
+///
+/// # Safety
+/// `a` and `b` must be valid for reads of `n` consecutive `f32` values.
+pub unsafe fn scalar_l2(a: *const f32, b: *const f32, n: usize) -> f32 {
+    let mut sum = 0.0f32;
+    for i in 0..n {
+        let d = *a.add(i) - *b.add(i);
+        sum += d * d;
+    }
+    sum
+}
+
+/// Scalar Cosine Distance: `1 - cos(a, b)`. A zero vector is orthogonal to everything.
+///
+/// # Safety
+/// `a` and `b` must be valid for reads of `n` consecutive `f32` values.
+pub unsafe fn scalar_cosine(a: *const f32, b: *const f32, n: usize) -> f32 {
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for i in 0..n {
+        let (x, y) = (*a.add(i), *b.add(i));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    cosine_from_parts(dot, norm_a, norm_b)
+}
+
+#[inline(always)]
+fn cosine_from_parts(dot: f32, norm_a_sq: f32, norm_b_sq: f32) -> f32 {
+    let denom = (norm_a_sq * norm_b_sq).sqrt();
+    if denom == 0.0 { 1.0 } else { 1.0 - dot / denom }
+}
+
 /// Scalar Integer Dot Product Fallback.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub fn scalar_dot_u8(q: *const i8, v: *const u8, n: usize) -> i32 {
     let mut sum: i32 = 0;
     for i in 0..n {
@@ -30,6 +71,10 @@ pub fn scalar_dot_u8(q: *const i8, v: *const u8, n: usize) -> i32 {
 
 /// The AVX2 Intrinsic Kernel.
 /// Uses 256-bit YMM registers and Fused Multiply-Add (FMA).
+///
+/// # Safety
+/// The CPU must support AVX2 and FMA, and `a`/`b` must be valid for reads
+/// of `n` consecutive `f32` values.
 #[target_feature(enable = "avx2", enable = "fma")]
 pub unsafe fn avx2_dot(a: *const f32, b: *const f32, n: usize) -> f32 {
     let mut acc0 = _mm256_setzero_ps();
@@ -90,11 +135,88 @@ pub unsafe fn avx2_dot(a: *const f32, b: *const f32, n: usize) -> f32 {
     -result
 }
 
+/// Horizontal sum of the 8 lanes of a YMM register.
+#[target_feature(enable = "avx2")]
+unsafe fn hsum256(v: __m256) -> f32 {
+    let sum128 = _mm_add_ps(_mm256_extractf128_ps(v, 1), _mm256_castps256_ps128(v));
+    let sum_h = _mm_hadd_ps(sum128, sum128);
+    _mm_cvtss_f32(_mm_hadd_ps(sum_h, sum_h))
+}
+
+/// The AVX2 Squared Euclidean Kernel.
+///
+/// # Safety
+/// The CPU must support AVX2 and FMA, and `a`/`b` must be valid for reads
+/// of `n` consecutive `f32` values.
+#[target_feature(enable = "avx2", enable = "fma")]
+pub unsafe fn avx2_l2(a: *const f32, b: *const f32, n: usize) -> f32 {
+    let mut acc0 = _mm256_setzero_ps();
+    let mut acc1 = _mm256_setzero_ps();
+
+    let mut i = 0;
+    // 2 accumulators x 8 floats hide the FMA latency
+    while i + 16 <= n {
+        let d0 = _mm256_sub_ps(_mm256_loadu_ps(a.add(i)), _mm256_loadu_ps(b.add(i)));
+        acc0 = _mm256_fmadd_ps(d0, d0, acc0);
+        let d1 = _mm256_sub_ps(_mm256_loadu_ps(a.add(i + 8)), _mm256_loadu_ps(b.add(i + 8)));
+        acc1 = _mm256_fmadd_ps(d1, d1, acc1);
+        i += 16;
+    }
+    while i + 8 <= n {
+        let d = _mm256_sub_ps(_mm256_loadu_ps(a.add(i)), _mm256_loadu_ps(b.add(i)));
+        acc0 = _mm256_fmadd_ps(d, d, acc0);
+        i += 8;
+    }
+
+    let mut result = hsum256(_mm256_add_ps(acc0, acc1));
+    while i < n {
+        let d = *a.add(i) - *b.add(i);
+        result += d * d;
+        i += 1;
+    }
+    result
+}
+
+/// The AVX2 Cosine Distance Kernel: dot product and both norms in a single pass.
+///
+/// # Safety
+/// The CPU must support AVX2 and FMA, and `a`/`b` must be valid for reads
+/// of `n` consecutive `f32` values.
+#[target_feature(enable = "avx2", enable = "fma")]
+pub unsafe fn avx2_cosine(a: *const f32, b: *const f32, n: usize) -> f32 {
+    let mut dot = _mm256_setzero_ps();
+    let mut norm_a = _mm256_setzero_ps();
+    let mut norm_b = _mm256_setzero_ps();
+
+    let mut i = 0;
+    while i + 8 <= n {
+        let va = _mm256_loadu_ps(a.add(i));
+        let vb = _mm256_loadu_ps(b.add(i));
+        dot = _mm256_fmadd_ps(va, vb, dot);
+        norm_a = _mm256_fmadd_ps(va, va, norm_a);
+        norm_b = _mm256_fmadd_ps(vb, vb, norm_b);
+        i += 8;
+    }
+
+    let (mut d, mut na, mut nb) = (hsum256(dot), hsum256(norm_a), hsum256(norm_b));
+    while i < n {
+        let (x, y) = (*a.add(i), *b.add(i));
+        d += x * y;
+        na += x * x;
+        nb += y * y;
+        i += 1;
+    }
+    cosine_from_parts(d, na, nb)
+}
+
 /// The AVX2 Integer Dot Product Kernel (Coarse Optimization).
 /// Input: Query (i8), Database Vector (u8).
 /// Returns: Negative Dot Product (Distance Proxy).
 /// 
-/// SAFETY: Includes i32 widening cascade to prevent maddubs saturation.
+/// Includes i32 widening cascade to prevent maddubs saturation.
+///
+/// # Safety
+/// The CPU must support AVX2, and `q`/`v` must be valid for reads of `n` bytes.
 #[target_feature(enable = "avx2")]
 pub unsafe fn dot_product_u8_avx2(q: *const i8, v: *const u8, n: usize) -> i32 {
     let mut sum_i32 = _mm256_setzero_si256();
@@ -149,6 +271,39 @@ unsafe fn scalar_dot_product_wrapper(a: *const f32, b: *const f32, n: usize) ->
     scalar_dot(a, b, n)
 }
 
+/// Returns the optimal squared Euclidean kernel.
+pub fn get_l2_kernel() -> SimdFunc {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            return avx2_l2;
+        }
+    }
+    scalar_l2
+}
+
+/// Returns the optimal cosine distance kernel.
+pub fn get_cosine_kernel() -> SimdFunc {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            return avx2_cosine;
+        }
+    }
+    scalar_cosine
+}
+
+/// Returns the optimal quantized (i8 x u8) dot product kernel.
+pub fn get_quantized_kernel() -> QuantizedFunc {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return dot_product_u8_avx2;
+        }
+    }
+    scalar_dot_u8
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,6 +326,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_l2_and_cosine_equivalence() {
+        // Odd length exercises both the SIMD body and the scalar tail
+        let n = 131;
+        let a: Vec<f32> = (0..n).map(|i| (i as f32 * 0.37).sin()).collect();
+        let b: Vec<f32> = (0..n).map(|i| (i as f32 * 0.11).cos()).collect();
+
+        unsafe {
+            let l2_ref = scalar_l2(a.as_ptr(), b.as_ptr(), n);
+            let l2_ker = get_l2_kernel()(a.as_ptr(), b.as_ptr(), n);
+            assert!((l2_ref - l2_ker).abs() < 1e-3, "L2 SIMD ({}) and Scalar ({}) mismatch", l2_ker, l2_ref);
+            assert_eq!(get_l2_kernel()(a.as_ptr(), a.as_ptr(), n), 0.0);
+
+            let cos_ref = scalar_cosine(a.as_ptr(), b.as_ptr(), n);
+            let cos_ker = get_cosine_kernel()(a.as_ptr(), b.as_ptr(), n);
+            assert!((cos_ref - cos_ker).abs() < 1e-4, "Cosine SIMD ({}) and Scalar ({}) mismatch", cos_ker, cos_ref);
+            assert!(get_cosine_kernel()(a.as_ptr(), a.as_ptr(), n).abs() < 1e-5);
+
+            let zero = vec![0.0f32; n];
+            assert_eq!(get_cosine_kernel()(a.as_ptr(), zero.as_ptr(), n), 1.0);
+        }
+    }
+
     #[test]
     fn test_dot_product_u8_equivalence() {
         let n = 256;
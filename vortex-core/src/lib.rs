@@ -1,5 +1,7 @@
 pub mod reactor;
 pub mod storage;
 pub mod index;
+pub mod catalog;
+pub mod collection;
 pub mod proxy;
 pub mod telemetry_beacon;
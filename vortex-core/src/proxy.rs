@@ -1,66 +1,130 @@
-use crate::reactor::ShardReactor;
+use crate::reactor::{ShardReactor, DEFAULT_IDLE_TIMEOUT, DEFAULT_KEEPALIVE, DEFAULT_MAX_CONNECTIONS, DEFAULT_READ_DEADLINE, DEFAULT_SNAPSHOT_INTERVAL};
+use crate::storage::wal::DEFAULT_SEGMENT_SIZE;
+use crate::catalog::Catalog;
 use log::info;
 use std::thread;
-use std::sync::Arc;
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use crossbeam_utils::sync::WaitGroup;
+use vortex_io::wake::Waker;
 
 /// ShardProxy: Orchestrates multiple ShardReactors across cores.
 /// 
 /// # Responsibilities
 /// 1. Spawning one OS thread per physical core.
 /// 2. Pinning threads to their respective cores (Rule #7).
-/// 3. Initializing the ShardReactor state.
+/// 3. Initializing the ShardReactor state over one shared collection catalog.
 /// 4. Managing the lifecycle (Spawn -> Run -> Shutdown).
 pub struct ShardProxy {
     num_shards: usize,
-    max_elements_per_shard: usize,
-    storage_dir: String,
+    catalog: Arc<Catalog>,
+    snapshot_interval: u64,
+    wal_segment_size: u64,
+    max_connections: usize,
+    idle_timeout: Option<Duration>,
+    read_deadline: Option<Duration>,
+    keepalive: Option<Duration>,
     running: Arc<AtomicBool>,
+    // Interrupt each shard's wait so shutdown is seen at once, even when idle
+    wakers: Arc<Mutex<Vec<Waker>>>,
 }
 
 impl ShardProxy {
     /// Initializes a new Proxy orchestrator.
-    pub fn new(num_shards: usize, max_elements_per_shard: usize, storage_dir: String) -> Self {
+    pub fn new(num_shards: usize, catalog: Arc<Catalog>) -> Self {
         Self { 
             num_shards, 
-            max_elements_per_shard, 
-            storage_dir,
+            catalog,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            wal_segment_size: DEFAULT_SEGMENT_SIZE,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            read_deadline: Some(DEFAULT_READ_DEADLINE),
+            keepalive: Some(DEFAULT_KEEPALIVE),
             running: Arc::new(AtomicBool::new(true)),
+            wakers: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    /// Overrides the WAL growth between index snapshots for every shard.
+    pub fn with_snapshot_interval(mut self, bytes: u64) -> Self {
+        self.snapshot_interval = bytes;
+        self
+    }
+
+    /// Overrides the WAL segment size for every shard.
+    pub fn with_wal_segment_size(mut self, bytes: u64) -> Self {
+        self.wal_segment_size = bytes;
+        self
+    }
+
+    /// Overrides how many connections each shard serves before answering `STATUS_BUSY`.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Overrides how long connections may sit idle (`None` never reaps them).
+    pub fn with_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Overrides how long a client has to complete a started frame (`None` waits indefinitely).
+    pub fn with_read_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.read_deadline = deadline;
+        self
+    }
+
+    /// Overrides the quiet time before TCP keepalive probes (`None` disables keepalive).
+    pub fn with_keepalive(mut self, idle: Option<Duration>) -> Self {
+        self.keepalive = idle;
+        self
+    }
+
     /// Spawns and pins all Shard Reactor threads.
     /// 
     /// # Arguments
-    /// * `start_port` - The VBP Ingress port. All shards bind to this same port 
-    ///                  using `SO_REUSEPORT` for hardware load balancing.
+    /// * `start_port` - The VBP Ingress port. All shards bind to this same port
+    ///   using `SO_REUSEPORT` for hardware load balancing.
     pub fn spawn_shards(&self, start_port: u16) {
         let wg = WaitGroup::new();
 
         // If num_shards > 1, spawn n-1 shards in threads.
         // The last shard (or the only shard) will run on the calling thread.
-        let background_shards = if self.num_shards > 1 { self.num_shards - 1 } else { 0 };
+        let background_shards = self.num_shards.saturating_sub(1);
 
         let mut actually_spawned = 0;
         for i in 0..background_shards {
             let shard_id = i;
             let port = start_port;
             let wg = wg.clone();
-            let max_el = self.max_elements_per_shard;
-            let dir = self.storage_dir.clone();
+            let catalog = self.catalog.clone();
+            let snapshot_interval = self.snapshot_interval;
+            let wal_segment_size = self.wal_segment_size;
+            let max_connections = self.max_connections;
+            let (idle_timeout, read_deadline, keepalive) = (self.idle_timeout, self.read_deadline, self.keepalive);
             let running = self.running.clone();
+            let wakers = self.wakers.clone();
 
             let result = thread::Builder::new()
                 .name(format!("shard_{}", shard_id))
                 .stack_size(512 * 1024) // 512KB stack (Termux Friendly)
                 .spawn(move || {
                     vortex_io::platform::affinity::pin_thread_to_core(shard_id);
-                    let mut reactor = ShardReactor::new(shard_id, 256, max_el, &dir);
+                    let mut reactor = ShardReactor::with_catalog(shard_id, 256, catalog);
+                    reactor.set_snapshot_interval(snapshot_interval);
+                    reactor.set_wal_segment_size(wal_segment_size);
+                    reactor.set_max_connections(max_connections);
+                    reactor.set_idle_timeout(idle_timeout);
+                    reactor.set_read_deadline(read_deadline);
+                    reactor.set_keepalive(keepalive);
                     if let Err(e) = reactor.listen(port) {
                         panic!("CRITICAL: Shard {} failed to bind port {}: {}", shard_id, port, e);
                     }
                     info!("Shard {} Online (Threaded). Pinned to Core {}.", shard_id, shard_id);
+                    wakers.lock().unwrap_or_else(|e| e.into_inner()).push(reactor.waker());
                     drop(wg);
                     
                     while running.load(Ordering::SeqCst) {
@@ -86,14 +150,20 @@ impl ShardProxy {
         // Run the final shard on the MAIN thread to avoid EAGAIN on constrained systems
         let main_shard_id = self.num_shards - 1;
         let port = start_port;
-        let max_el = self.max_elements_per_shard;
         
         info!("Shard {} Online (Main Thread Fallback). Listening on port {}.", main_shard_id, port);
         
         // This shard must handle its own pinning and setup
         vortex_io::platform::affinity::pin_thread_to_core(main_shard_id);
-        let mut reactor = ShardReactor::new(main_shard_id, 256, max_el, &self.storage_dir);
+        let mut reactor = ShardReactor::with_catalog(main_shard_id, 256, self.catalog.clone());
+        reactor.set_snapshot_interval(self.snapshot_interval);
+        reactor.set_wal_segment_size(self.wal_segment_size);
+        reactor.set_max_connections(self.max_connections);
+        reactor.set_idle_timeout(self.idle_timeout);
+        reactor.set_read_deadline(self.read_deadline);
+        reactor.set_keepalive(self.keepalive);
         reactor.listen(port).expect("Main shard bind failed");
+        self.wakers.lock().unwrap_or_else(|e| e.into_inner()).push(reactor.waker());
 
         // Signal cluster readiness if others are waiting (Wait for those that actually spawned)
         // WaitGroup drops for each successful spawn. We need to drop the remaining ones.
@@ -118,5 +188,8 @@ impl ShardProxy {
     pub fn shutdown(&self) {
         info!("Cluster Proxy: Shutdown signal propagated to all shards.");
         self.running.store(false, Ordering::SeqCst);
+        for waker in self.wakers.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            waker.wake();
+        }
     }
 }
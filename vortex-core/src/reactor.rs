@@ -1,26 +1,69 @@
 use vortex_io::ring::RingDriver;
-use vortex_io::memory::BufferPool;
+use vortex_io::memory::{BufferLease, BufferPool};
 use vortex_io::net::VortexListener;
-use crate::storage::wal::WalManager;
-use crate::storage::batch::BatchAccumulator;
-use crate::index::hnsw::HnswIndex;
-use crate::index::VectorIndex;
-use vortex_rpc::{VBP_MAGIC, ResponseHeader, STATUS_OK, STATUS_ERR};
+use vortex_io::wake::Waker;
+use crate::storage::batch::BatchTag;
+use crate::storage::wal::DEFAULT_SEGMENT_SIZE;
+use crate::catalog::{Catalog, MAX_COLLECTIONS};
+use crate::collection::{Collection, apply_record};
+use crate::index::{SearchParams, VectorIndex};
+use crate::index::distance::Metric;
+use vortex_rpc::{VBP_MAGIC, VBP_VERSION_MIN, VBP_VERSION_MAX, DEFAULT_COLLECTION, RequestHeader, ResponseHeader, Command, parse_command, HelloResponse, SearchHit, CollectionRef, CollectionInfo, Filter, MAX_DIMENSION, MAX_COLLECTION_NAME, MAX_STATUS_MESSAGE, STATUS_OK, STATUS_ERR, STATUS_NOT_FOUND, STATUS_UNKNOWN_COLLECTION, STATUS_FRAME_TOO_LARGE, STATUS_UNKNOWN_OPCODE, STATUS_BAD_MAGIC, STATUS_UNSUPPORTED_VERSION, STATUS_BUSY, STATUS_STORAGE, StoredVector, VBP_VERSION_2, metadata::MAX_METADATA_SIZE};
 use log::{info, error, debug, trace, warn};
 use io_uring::{opcode, types};
-use std::os::unix::io::RawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::Arc;
 use std::time::{Instant, Duration};
-use std::path::Path;
 
 /// User Data Tags to distinguish CQE types
 const TAG_ACCEPT: u64 = 0xFFFF_0000;
 const TAG_READ_PREFIX: u64 = 0xAAAA_0000;
-const TAG_WAL_PREFIX: u64 = 0xBBBB_0000;
 const TAG_WRITE_PREFIX: u64 = 0xCCCC_0000;
+/// Low 16 bits carry the collection slot whose batch was written.
 const TAG_BATCH_WRITE: u64 = 0xDDDD_0000;
+/// Write and close of a connection refused with `STATUS_BUSY`; completions are ignored.
+const TAG_REJECT: u64 = 0xBBBB_0000;
+/// Timeout linked to a connection read; the read itself reports the expiry.
+const TAG_READ_TIMEOUT: u64 = 0xEEEE_0000;
+/// Cancellation of the read of a retired connection; the read itself reports `-ECANCELED`.
+const TAG_READ_CANCEL: u64 = 0x5555_0000;
+/// Tick timer bounding how long `run_tick` waits for completions.
+const TAG_TICK: u64 = 0x7777_0000;
+/// Read on the waker's eventfd, completed by `Waker::wake`.
+const TAG_WAKE: u64 = 0x9999_0000;
 
 const CMD_UPSERT: u8 = 1;
+const CMD_DELETE: u8 = 2;
+const CMD_GET: u8 = 3;
 const CMD_SEARCH: u8 = 5;
+const CMD_CREATE_COLLECTION: u8 = 16;
+const CMD_LIST_COLLECTIONS: u8 = 17;
+const CMD_DROP_COLLECTION: u8 = 18;
+const CMD_HELLO: u8 = 32;
+
+/// Opcodes reported by `OP_HELLO`.
+const SUPPORTED_OPCODES: [u8; 8] = [CMD_UPSERT, CMD_DELETE, CMD_GET, CMD_SEARCH, CMD_CREATE_COLLECTION, CMD_LIST_COLLECTIONS, CMD_DROP_COLLECTION, CMD_HELLO];
+
+/// Size of a VBP request/response header on the wire.
+const HEADER_SIZE: usize = 16;
+/// Size of each RX/TX page leased from the BufferPool.
+const PAGE_BYTES: usize = 65536;
+/// Shadow page space held back for an admin response (a full collection listing).
+const ADMIN_RESPONSE_BYTES: usize = HEADER_SIZE + MAX_COLLECTIONS * (CollectionInfo::PREFIX_SIZE + MAX_COLLECTION_NAME);
+/// WAL growth (per collection and shard) between two index snapshots.
+pub const DEFAULT_SNAPSHOT_INTERVAL: u64 = 64 * 1024 * 1024;
+/// Concurrent connections per shard. Each holds an RX and a TX page while open.
+pub const DEFAULT_MAX_CONNECTIONS: usize = 256;
+/// Connection slots are carried in the low 16 bits of read/write tags.
+const MAX_CONNECTION_SLOTS: usize = 0x1_0000;
+/// Silence after which a connection with no partial frame is closed.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Time a client has to finish a frame once its first bytes arrived.
+pub const DEFAULT_READ_DEADLINE: Duration = Duration::from_secs(30);
+/// Quiet time before the kernel starts probing a connection.
+pub const DEFAULT_KEEPALIVE: Duration = Duration::from_secs(60);
+/// Longest an idle shard waits before it runs its periodic work anyway.
+pub const DEFAULT_TICK_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Debug, Clone, Copy)]
 pub enum FlushReason {
@@ -42,31 +85,62 @@ pub struct ShardReactor {
     ring: RingDriver,
     pool: BufferPool,
     listener: Option<VortexListener>,
-    wal: WalManager,
-    // Shard-local in-memory state (Rule 6: Share Nothing)
-    index: HnswIndex,
+    // Shard-local collections (Rule 6: Share Nothing). The slot index tags batch writes.
+    catalog: Arc<Catalog>,
+    catalog_generation: u64,
+    collections: Vec<Option<Collection>>,
+    snapshot_interval: u64,
+    wal_segment_size: u64,
     pending_submissions: u32,
+    // Bounded waits: a re-armed tick timer, and an eventfd read other threads complete
+    tick_interval: Box<types::Timespec>,
+    tick_armed: bool,
+    waker: Waker,
+    wake_buffer: Box<[u8; 8]>,
+    wake_armed: bool,
     // Map Slot Index -> Socket FD for response
     active_fds: Vec<Option<RawFd>>,
+    // Connection slab: the RX and TX pages leased to each open slot
+    leases: Vec<Option<(BufferLease, BufferLease)>>,
+    // Pre-encoded STATUS_BUSY answer for connections beyond the slab
+    busy_frame: Box<[u8]>,
     
     // Zero-Allocation Recycled Buffers
     completions_buffer: Vec<(u64, i32)>,
-    scratch_query_buffer: Box<[f32; 128]>,
+    scratch_query_buffer: Box<[f32]>,
+    // Filter of the search being decoded, kept next to the scratch query
+    scratch_filter: Option<Filter>,
     
     // TCP Reassembly (Milestone 5 Hardening)
     accumulated_bytes: Vec<usize>, 
     consumed_bytes: Vec<usize>,
     pending_ops: Vec<usize>,
 
-    // Mechanical Sympathy: Batching
-    active_batch: BatchAccumulator,
-    flushing_batch: Option<BatchAccumulator>,
     is_shutting_down: bool,
-    ring_capacity: usize,
     paused_reads: Vec<usize>,
     write_in_flight: Vec<bool>,
+    // Shadow TX lane accounting (bytes/responses per connection slot).
+    // The in-flight write always starts at offset 0; queued responses follow it.
     pending_acks: Vec<usize>,
+    pending_tx_bytes: Vec<usize>,
+    inflight_acks: Vec<usize>,
+    inflight_tx_bytes: Vec<usize>,
+    // Space held back for ACKs of upserts still sitting in a batch
+    reserved_tx_bytes: Vec<usize>,
     read_in_flight: Vec<bool>,
+    // Connections that lost framing: no more reads, closed once their responses drain
+    closing: Vec<bool>,
+    // Newest protocol version each connection may use, lowered by OP_HELLO
+    max_version: Vec<u8>,
+    // Reaping: read timeouts, and when each buffered partial frame started arriving
+    idle_timeout: Option<Duration>,
+    read_deadline: Option<Duration>,
+    keepalive: Option<Duration>,
+    frame_started: Vec<Option<Instant>>,
+    // Linked timeout of each slot's read. The kernel reads it at submit, so it lives here.
+    read_timeouts: Vec<types::Timespec>,
+    // Recycled per-batch marks of the slots owed an ACK write
+    touched: Vec<bool>,
     
     // Phase 11: Foreman Telemetry
     backpressure_count: usize,
@@ -75,109 +149,176 @@ pub struct ShardReactor {
     tick_search_ops: usize,
     tick_ingress_ns: u64,
     tick_flush_ns: u64,
+    tick_idle_closes: usize,
+    tick_deadline_closes: usize,
     last_pulse_report: Instant,
 }
 
 impl ShardReactor {
-    pub fn new(shard_id: usize, ring_entries: u32, max_elements: usize, base_path: &str) -> Self {
+    pub fn new(shard_id: usize, ring_entries: u32, max_elements: usize, dimension: usize, base_path: &str) -> Self {
+        // The manifest pins the dimension the data directory was written with
+        let catalog = Catalog::open(base_path, dimension, Metric::default(), max_elements).expect("Storage manifest rejected configuration");
+        Self::with_catalog(shard_id, ring_entries, Arc::new(catalog))
+    }
+
+    /// Builds a reactor over a catalog shared with the other shards and replays the
+    /// WAL stream of every collection it lists.
+    pub fn with_catalog(shard_id: usize, ring_entries: u32, catalog: Arc<Catalog>) -> Self {
         let ring = RingDriver::new(ring_entries).expect("Failed to init io_uring");
         // Rule #14 Optimization: Double pool for Shadow Response Buffers (RX/TX split)
-        let pool = BufferPool::new((ring_entries * 2) as usize, 65536); 
-        
-        // Initialize WAL in requested directory (Rule #8/Milestone 4)
-        let mut wal = WalManager::new(shard_id, base_path).expect("Failed to init WAL");
-        
-        // Dynamic dimension 128, capacity controlled by caller (Target 0 Scaling)
-        let mut index = HnswIndex::new(128, max_elements);
-
-        // --- THE RESURRECTION (Phase 4 Recovery) ---
-        let wal_path = format!("{}/shard_{}.wal", base_path, shard_id);
-        let start_time = Instant::now();
-        let mut recovered_count = 0;
-
-        if Path::new(&wal_path).exists() {
-            // Replay iterator performs blocking I/O (allowed during boot per Rule #8 exception)
-            if let Ok(mut iter) = wal.replay_iter(&wal_path) {
-                for entry_res in &mut iter {
-                    match entry_res {
-                        Ok(entry) => {
-                            if entry.header.opcode == CMD_UPSERT {
-                                let payload = &entry.payload;
-                                if payload.len() >= 8 {
-                                    // Parse ID (8 bytes)
-                                    let id = u64::from_le_bytes(payload[0..8].try_into().unwrap_or([0; 8]));
-                                    // Parse Vector
-                                    let vec_bytes = &payload[8..];
-                                    let dim = vec_bytes.len() / 4;
-                                    if dim > 0 {
-                                        // SAFETY: WAL content is trusted for replay. Aligned in 4KB pages.
-                                        let vec_slice: &[f32] = unsafe {
-                                            std::slice::from_raw_parts(vec_bytes.as_ptr() as *const f32, dim)
-                                        };
-                                        index.insert(id, vec_slice);
-                                        recovered_count += 1;
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            let corruption_offset = iter.bytes_read();
-                            error!("Shard {}: WAL Replay encountered corruption at offset {}: {}. Truncating log to prune corrupted tail.", 
-                                shard_id, corruption_offset, e);
-                            
-                            // Self-Healing: Truncate the file to the last known good position
-                            if let Err(te) = wal.truncate(corruption_offset) {
-                                error!("Shard {}: Failed to truncate corrupted WAL: {}", shard_id, te);
-                            }
-                            break;
-                        }
-                    }
-                }
-            }
-        }
+        let pool = BufferPool::new(DEFAULT_MAX_CONNECTIONS * 2, PAGE_BYTES);
 
-        let duration = start_time.elapsed();
-        if recovered_count > 0 {
-            info!("Shard {}: Recovered {} records from WAL in {} ms.", 
-                shard_id, recovered_count, duration.as_millis());
-        }
+        // Sized for the largest dimension any collection may use
+        let mut scratch_query_buffer = vec![0.0f32; MAX_DIMENSION as usize].into_boxed_slice();
 
-        Self {
+        // Initialize each collection's WAL in its directory (Rule #8/Milestone 4)
+        let catalog_generation = catalog.generation();
+        let collections = catalog.collections().iter().filter(|info| catalog.attach(info.id)).map(|info| {
+            let dir = catalog.collection_dir(info.id);
+            Some(Collection::open(shard_id, info, &dir, &mut scratch_query_buffer).expect("Failed to init WAL"))
+        }).collect();
+
+        let mut reactor = Self {
             shard_id,
             ring,
             pool,
             listener: None,
-            wal,
-            index,
+            catalog,
+            catalog_generation,
+            collections,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            wal_segment_size: DEFAULT_SEGMENT_SIZE,
             pending_submissions: 0,
+            tick_interval: Box::new(DEFAULT_TICK_INTERVAL.into()),
+            tick_armed: false,
+            waker: Waker::new().expect("Failed to create the reactor eventfd"),
+            wake_buffer: Box::new([0; 8]),
+            wake_armed: false,
             
             // Pre-allocate to avoid malloc in hot loop
             completions_buffer: Vec::with_capacity(ring_entries as usize),
-            scratch_query_buffer: Box::new([0.0f32; 128]),
-            active_fds: vec![None; 32],
-            accumulated_bytes: vec![0; 32],
-            consumed_bytes: vec![0; 32],
-            pending_ops: vec![0; 32],
-            active_batch: BatchAccumulator::new(),
-            flushing_batch: None,
+            scratch_query_buffer,
+            scratch_filter: None,
+            active_fds: Vec::new(),
+            leases: Vec::new(),
+            busy_frame: busy_frame(),
+            accumulated_bytes: Vec::new(),
+            consumed_bytes: Vec::new(),
+            pending_ops: Vec::new(),
             is_shutting_down: false,
-            ring_capacity: ring_entries as usize,
-            paused_reads: Vec::with_capacity(32),
-            write_in_flight: vec![false; ring_entries as usize * 2], // Direct mapping
-            pending_acks: vec![0; 32],
-            read_in_flight: vec![false; 32],
+            paused_reads: Vec::new(),
+            write_in_flight: Vec::new(),
+            pending_acks: Vec::new(),
+            pending_tx_bytes: Vec::new(),
+            inflight_acks: Vec::new(),
+            inflight_tx_bytes: Vec::new(),
+            reserved_tx_bytes: Vec::new(),
+            read_in_flight: Vec::new(),
+            closing: Vec::new(),
+            max_version: Vec::new(),
+            idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+            read_deadline: Some(DEFAULT_READ_DEADLINE),
+            keepalive: Some(DEFAULT_KEEPALIVE),
+            frame_started: Vec::new(),
+            read_timeouts: Vec::new(),
+            touched: Vec::new(),
             backpressure_count: 0,
             last_backpressure_report: Instant::now(),
             tick_search_micros: 0,
             tick_search_ops: 0,
             tick_ingress_ns: 0,
             tick_flush_ns: 0,
+            tick_idle_closes: 0,
+            tick_deadline_closes: 0,
             last_pulse_report: Instant::now(),
+        };
+        reactor.allocate_slab(DEFAULT_MAX_CONNECTIONS);
+        reactor
+    }
+
+    /// Sets how many connections this shard serves at once; further clients are answered
+    /// with `STATUS_BUSY` and closed. Reallocates the page pool, so call it before `listen`.
+    ///
+    /// # Panics
+    /// Panics if the reactor is already listening or `max_connections` is 0 or above 65536.
+    pub fn set_max_connections(&mut self, max_connections: usize) {
+        assert!(self.listener.is_none(), "Connection slab resized after listen");
+        assert!((1..=MAX_CONNECTION_SLOTS).contains(&max_connections), "max_connections must be within 1..=65536");
+        if max_connections != self.leases.len() {
+            self.pool = BufferPool::new(max_connections * 2, PAGE_BYTES);
+            self.allocate_slab(max_connections);
+        }
+    }
+
+    /// Sizes every per-connection table for `slots` connections, all free.
+    fn allocate_slab(&mut self, slots: usize) {
+        self.active_fds = vec![None; slots];
+        self.leases = vec![None; slots];
+        self.accumulated_bytes = vec![0; slots];
+        self.consumed_bytes = vec![0; slots];
+        self.pending_ops = vec![0; slots];
+        self.paused_reads = Vec::with_capacity(slots);
+        self.write_in_flight = vec![false; slots];
+        self.pending_acks = vec![0; slots];
+        self.pending_tx_bytes = vec![0; slots];
+        self.inflight_acks = vec![0; slots];
+        self.inflight_tx_bytes = vec![0; slots];
+        self.reserved_tx_bytes = vec![0; slots];
+        self.read_in_flight = vec![false; slots];
+        self.closing = vec![false; slots];
+        self.max_version = vec![VBP_VERSION_MAX; slots];
+        self.frame_started = vec![None; slots];
+        self.read_timeouts = vec![types::Timespec::new(); slots];
+        self.touched = vec![false; slots];
+    }
+
+    /// Sets the longest wait for completions before `run_tick` returns, bounding how late
+    /// telemetry, batch flushes and the shutdown check run on an idle shard.
+    pub fn set_tick_interval(&mut self, interval: Duration) {
+        *self.tick_interval = interval.into();
+    }
+
+    /// Handle that interrupts this reactor's wait from another thread.
+    pub fn waker(&self) -> Waker {
+        self.waker.clone()
+    }
+
+    /// Sets how long a connection may stay silent between frames before it is closed.
+    /// `None` keeps idle connections open indefinitely.
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+
+    /// Sets how long a client has to complete a frame once its first bytes arrived,
+    /// so a stalled partial frame cannot hold a slot. `None` waits indefinitely.
+    pub fn set_read_deadline(&mut self, deadline: Option<Duration>) {
+        self.read_deadline = deadline;
+    }
+
+    /// Sets the quiet time before TCP keepalive probes start. Call before `listen`;
+    /// `None` leaves keepalive off.
+    pub fn set_keepalive(&mut self, idle: Option<Duration>) {
+        self.keepalive = idle;
+    }
+
+    /// Sets how many WAL bytes a collection accumulates before its index is snapshotted again.
+    pub fn set_snapshot_interval(&mut self, bytes: u64) {
+        self.snapshot_interval = bytes;
+    }
+
+    /// Sets the size at which each collection's active WAL segment is sealed.
+    pub fn set_wal_segment_size(&mut self, bytes: u64) {
+        self.wal_segment_size = bytes;
+        for collection in self.collections.iter_mut().flatten() {
+            collection.wal.set_segment_size(bytes);
         }
     }
 
     pub fn listen(&mut self, port: u16) -> std::io::Result<()> {
         let listener = VortexListener::new_ingress(port)?;
+        if let Some(idle) = self.keepalive {
+            listener.set_keepalive(idle)?;
+        }
         self.listener = Some(listener);
         self.submit_accept();
         Ok(())
@@ -186,7 +327,9 @@ impl ShardReactor {
     pub fn shutdown(&mut self) {
         self.is_shutting_down = true;
         // Force drain all pending batches
-        self.flush_active_batch(FlushReason::Eot);
+        for slot in 0..self.collections.len() {
+            self.flush_active_batch(slot, FlushReason::Eot);
+        }
     }
 
     /// Helper to submit an SQE with Backpressure handling.
@@ -208,6 +351,22 @@ impl ShardReactor {
         }
     }
 
+    /// Pushes `entries` back to back, so a linked chain is never split across submits.
+    fn push_submissions(&mut self, entries: &[io_uring::squeue::Entry]) {
+        loop {
+            // SAFETY: Checked push to pre-allocated ring buffer. Entries are valid.
+            unsafe {
+                if self.ring.submission_queue().push_multiple(entries).is_ok() {
+                    self.pending_submissions += entries.len() as u32;
+                    return;
+                }
+            }
+            if let Err(e) = self.ring.submit() {
+                error!("Critical Ring Submit Error during backpressure flush: {}", e);
+            }
+        }
+    }
+
     fn submit_accept(&mut self) {
         if let Some(ref listener) = self.listener {
             let entry = listener.accept_sqe(std::ptr::null_mut(), std::ptr::null_mut(), TAG_ACCEPT);
@@ -215,23 +374,42 @@ impl ShardReactor {
         }
     }
 
+    /// Posts the tick timer and the waker read unless they are already pending.
+    fn arm_wakeups(&mut self) {
+        if !self.tick_armed {
+            self.tick_armed = true;
+            let tick_e = opcode::Timeout::new(&*self.tick_interval).build().user_data(TAG_TICK);
+            self.push_submission(&tick_e);
+        }
+        if !self.wake_armed {
+            self.wake_armed = true;
+            let wake_e = opcode::Read::new(types::Fd(self.waker.as_raw_fd()), self.wake_buffer.as_mut_ptr(), 8)
+                .build()
+                .user_data(TAG_WAKE);
+            self.push_submission(&wake_e);
+        }
+    }
+
     pub fn run_tick(&mut self) -> bool {
         let _t_start = Instant::now();
         let mut _work_done = false;
         
         // 1Hz Pulse Telemetry (Rule 11/Constraint 1)
         if self.last_pulse_report.elapsed() >= Duration::from_secs(1) {
-             let nodes = self.index.dist_calc_count.get();
-             self.index.dist_calc_count.set(0);
+             let nodes: u64 = self.collections.iter().flatten().map(|c| c.index.dist_calc_count.replace(0)).sum();
              
              // Emit PULSE for dashboard parsing
-             info!("PULSE Shard {} | [Search] ops={} time={}us dist={} | [Health] ingress={}ms flush={}ms",
+             let open = self.leases.iter().filter(|lease| lease.is_some()).count();
+             info!("PULSE Shard {} | [Search] ops={} time={}us dist={} | [Health] ingress={}ms flush={}ms | [Conns] open={} idle_closed={} deadline_closed={}",
                 self.shard_id, 
                 self.tick_search_ops, 
                 self.tick_search_micros,
                 nodes,
                 self.tick_ingress_ns / 1_000_000,
-                self.tick_flush_ns / 1_000_000
+                self.tick_flush_ns / 1_000_000,
+                open,
+                self.tick_idle_closes,
+                self.tick_deadline_closes
              );
              
              // Reset aggregators
@@ -239,11 +417,19 @@ impl ShardReactor {
              self.tick_search_micros = 0;
              self.tick_ingress_ns = 0;
              self.tick_flush_ns = 0;
+             self.tick_idle_closes = 0;
+             self.tick_deadline_closes = 0;
              self.last_pulse_report = Instant::now();
         }
 
+        // Pick up collections created or dropped through other shards
+        if self.catalog.generation() != self.catalog_generation {
+            self.sync_catalog();
+        }
+
         // 1. Process Completions
-        // Opportunistic submit of any pending SQEs
+        // Opportunistic submit of any pending SQEs. The tick timer bounds the wait.
+        self.arm_wakeups();
         if let Err(e) = self.ring.submit_and_wait(1) {
             error!("Shard {} Ring Error: {}", self.shard_id, e);
             return false;
@@ -254,20 +440,69 @@ impl ShardReactor {
         
         {
             let mut cq = self.ring.completion_queue();
-            while let Some(cqe) = cq.next() {
+            for cqe in &mut cq {
                 self.pending_submissions -= 1;
-                self.completions_buffer.push((cqe.user_data() as u64, cqe.result()));
+                self.completions_buffer.push((cqe.user_data(), cqe.result()));
             }
         }
 
         // Iterate over the buffer (borrow checker happy now)
         for i in 0..self.completions_buffer.len() {
             let (tag, result) = self.completions_buffer[i];
+            match tag {
+                TAG_TICK => {
+                    // -ETIME: the interval passed with nothing else to do
+                    self.tick_armed = false;
+                    continue;
+                }
+                TAG_WAKE => {
+                    trace!("Shard {} woken.", self.shard_id);
+                    self.wake_armed = false;
+                    continue;
+                }
+                _ if (tag & 0xFFFF_0000) == TAG_READ_TIMEOUT => {
+                    // -ETIME when it fired (the read then fails with -ECANCELED), else -ECANCELED
+                    continue;
+                }
+                _ if (tag & 0xFFFF_0000) == TAG_READ_CANCEL => {
+                    // 0, or -ENOENT / -EALREADY if the read was already finishing
+                    continue;
+                }
+                _ => {}
+            }
             
             if result < 0 {
                 let err = std::io::Error::from_raw_os_error(-result);
                 if err.kind() == std::io::ErrorKind::WouldBlock { continue; }
+                let idx = (tag & 0x0000_FFFF) as usize;
+                if (tag & 0xFFFF_0000) == TAG_BATCH_WRITE {
+                    // The batch is not durable: fail its requests instead of leaving the pipeline stuck
+                    self.handle_batch_complete(idx, result);
+                    continue;
+                }
+                if (tag & 0xFFFF_0000) == TAG_READ_PREFIX && -result == libc::ECANCELED {
+                    // Cancelled by its linked timeout, or by `retire_connection`
+                    self.read_in_flight[idx] = false;
+                    if self.active_fds[idx].is_some() {
+                        self.reap_connection(idx);
+                    } else {
+                        self.retire_connection(idx);
+                    }
+                    continue;
+                }
                 error!("Shard {} I/O Error on tag 0x{:x}: {}", self.shard_id, tag, err);
+                // A failed socket read or write ends the connection and frees its slot
+                match tag & 0xFFFF_0000 {
+                    TAG_READ_PREFIX => {
+                        self.read_in_flight[idx] = false;
+                        self.retire_connection(idx);
+                    }
+                    TAG_WRITE_PREFIX => {
+                        self.write_in_flight[idx] = false;
+                        self.retire_connection(idx);
+                    }
+                    _ => {}
+                }
                 continue;
             }
 
@@ -278,20 +513,22 @@ impl ShardReactor {
             } else if (tag & 0xFFFF_0000) == TAG_READ_PREFIX {
                 let idx = (tag & 0x0000_FFFF) as usize;
                 self.handle_ingress(idx, result as usize);
-            } else if (tag & 0xFFFF_0000) == TAG_WAL_PREFIX {
-                let idx = (tag & 0x0000_FFFF) as usize;
-                self.handle_wal_complete(idx, result as usize);
             } else if (tag & 0xFFFF_0000) == TAG_WRITE_PREFIX {
                 let idx = (tag & 0x0000_FFFF) as usize;
                 self.handle_write_complete(idx, result as usize);
-            } else if tag == TAG_BATCH_WRITE {
-                self.handle_batch_complete(result as usize);
+            } else if (tag & 0xFFFF_0000) == TAG_BATCH_WRITE {
+                let slot = (tag & 0x0000_FFFF) as usize;
+                self.handle_batch_complete(slot, result);
             }
         }
         
         // EOT (End-Of-Tick) Flush: If we are idle and have pending data, COMMIT.
-        if self.active_batch.is_dirty() && self.flushing_batch.is_none() {
-            self.flush_active_batch(FlushReason::Eot);
+        // Snapshot images in progress advance one bounded chunk per tick.
+        for slot in 0..self.collections.len() {
+            if let Some(collection) = self.collections[slot].as_mut() {
+                collection.snapshot_step(self.shard_id);
+            }
+            self.flush_active_batch(slot, FlushReason::Eot);
         }
 
         // Shard Health Pulse
@@ -314,22 +551,103 @@ impl ShardReactor {
     }
 
     fn submit_read(&mut self, fd: RawFd) {
-        // Enforce 32-connection limit with STATIC mapping (Rule #7)
-        // Connection i -> BufferPage[i] (ingress) and BufferPage[i+32] (shadow)
-        for i in 0..32 {
-            if self.active_fds[i].is_none() {
-                self.active_fds[i] = Some(fd);
-                self.accumulated_bytes[i] = 0; 
-                self.consumed_bytes[i] = 0;
-                self.pending_ops[i] = 0;
-                self.submit_read_at(fd, i, 0);
-                return;
+        // Take a free slot of the connection slab and lease its RX/TX pages (Rule #7)
+        let slot = self.leases.iter().position(Option::is_none);
+        let Some((idx, rx)) = slot.and_then(|idx| self.pool.lease().map(|rx| (idx, rx))) else {
+            self.reject_busy(fd);
+            return;
+        };
+        let Some(tx) = self.pool.lease() else {
+            self.pool.release(rx);
+            self.reject_busy(fd);
+            return;
+        };
+        self.leases[idx] = Some((rx, tx));
+        self.active_fds[idx] = Some(fd);
+        self.max_version[idx] = VBP_VERSION_MAX;
+        self.submit_read_at(fd, idx, 0);
+    }
+
+    /// Answers a client the slab has no room for with `STATUS_BUSY`, then closes it.
+    fn reject_busy(&mut self, fd: RawFd) {
+        warn!("Shard {} Saturation: Refusing FD {} (Limit reached: {}).", self.shard_id, fd, self.leases.len());
+        // Hard links close the socket even if the write fails
+        let write_e = opcode::Write::new(types::Fd(fd), self.busy_frame.as_ptr(), self.busy_frame.len() as u32)
+            .build()
+            .flags(io_uring::squeue::Flags::IO_HARDLINK)
+            .user_data(TAG_REJECT);
+        let close_e = opcode::Close::new(types::Fd(fd)).build().user_data(TAG_REJECT);
+        self.push_submissions(&[write_e, close_e]);
+    }
+
+    /// Closes connection `idx` and returns its slot and pages to the slab once no read,
+    /// write or batched mutation still refers to it. Unsent responses are discarded.
+    fn retire_connection(&mut self, idx: usize) {
+        if let Some(fd) = self.active_fds[idx].take() {
+            // SAFETY: The fd was accepted by this reactor. Operations still in flight hold
+            // their own file reference, so closing does not end them.
+            unsafe { libc::close(fd); }
+            if self.read_in_flight[idx] {
+                // A socket read may never complete on its own: cancel it, and release
+                // the slot when its completion arrives
+                let cancel_e = opcode::AsyncCancel::new(TAG_READ_PREFIX | idx as u64)
+                    .build()
+                    .user_data(TAG_READ_CANCEL | idx as u64);
+                self.push_submission(&cancel_e);
             }
         }
+        if self.read_in_flight[idx] || self.write_in_flight[idx] || self.reserved_tx_bytes[idx] > 0 {
+            // Finished by the completion still outstanding
+            return;
+        }
+        if let Some((rx, tx)) = self.leases[idx].take() {
+            self.pool.release(rx);
+            self.pool.release(tx);
+            trace!("Shard {} Connection slot {} released.", self.shard_id, idx);
+        }
+        self.accumulated_bytes[idx] = 0;
+        self.consumed_bytes[idx] = 0;
+        self.pending_ops[idx] = 0;
+        self.pending_acks[idx] = 0;
+        self.pending_tx_bytes[idx] = 0;
+        self.inflight_acks[idx] = 0;
+        self.inflight_tx_bytes[idx] = 0;
+        self.closing[idx] = false;
+        self.frame_started[idx] = None;
+        self.paused_reads.retain(|&paused| paused != idx);
+    }
+
+    /// Closes connection `idx` after its read timed out, counting why in the telemetry.
+    fn reap_connection(&mut self, idx: usize) {
+        if self.frame_started[idx].is_some() {
+            debug!("Shard {} Connection {} missed the read deadline with a partial frame. Closing.", self.shard_id, idx);
+            self.tick_deadline_closes += 1;
+        } else {
+            debug!("Shard {} Connection {} idle past {:?}. Closing.", self.shard_id, idx, self.idle_timeout);
+            self.tick_idle_closes += 1;
+        }
+        self.retire_connection(idx);
+    }
+
+    /// Time left before the read on slot `idx` gives up: the rest of the read deadline
+    /// while a partial frame is buffered (`offset > 0`), else the idle timeout.
+    fn read_timeout(&mut self, idx: usize, offset: usize) -> Option<Duration> {
+        if offset == 0 {
+            self.frame_started[idx] = None;
+            return self.idle_timeout;
+        }
+        let started = *self.frame_started[idx].get_or_insert_with(Instant::now);
+        self.read_deadline.map(|deadline| deadline.saturating_sub(started.elapsed()))
+    }
+
+    /// Pool page receiving the frames of connection `idx`.
+    fn rx(&self, idx: usize) -> usize {
+        self.leases[idx].expect("Connection slot holds no pages").0.index
+    }
 
-        // Saturation Check: Refuse connection beyond static map capacity
-        warn!("Shard {} Saturation: Disconnecting FD {} (Limit reached: 32).", self.shard_id, fd);
-        unsafe { libc::close(fd); }
+    /// Shadow pool page holding the responses of connection `idx` (RX/TX split).
+    fn tx(&self, idx: usize) -> usize {
+        self.leases[idx].expect("Connection slot holds no pages").1.index
     }
 
     fn submit_read_at(&mut self, fd: RawFd, idx: usize, offset: usize) {
@@ -337,7 +655,7 @@ impl ShardReactor {
             return;
         }
 
-        let page = self.pool.get_page_mut(idx);
+        let page = self.pool.get_page_mut(self.rx(idx));
         let buf = page.as_slice_mut();
         
         if offset >= buf.len() {
@@ -356,57 +674,102 @@ impl ShardReactor {
             .build()
             .user_data(tag);
 
-        self.push_submission(&read_e);
+        // Idle and slow clients are reaped by a timeout linked to the read
+        match self.read_timeout(idx, offset) {
+            Some(timeout) => {
+                self.read_timeouts[idx] = timeout.into();
+                let timeout_e = opcode::LinkTimeout::new(&self.read_timeouts[idx]).build().user_data(TAG_READ_TIMEOUT | idx as u64);
+                self.push_submissions(&[read_e.flags(io_uring::squeue::Flags::IO_LINK), timeout_e]);
+            }
+            None => self.push_submission(&read_e),
+        }
+    }
+
+    /// Free bytes left in the shadow TX page of slot `idx`, after the in-flight write,
+    /// queued responses and reserved upsert ACKs.
+    fn tx_room(&self, idx: usize) -> usize {
+        PAGE_BYTES.saturating_sub(
+            self.inflight_tx_bytes[idx] + self.pending_tx_bytes[idx] + self.reserved_tx_bytes[idx],
+        )
     }
 
-    /// Formats the response buffer using the *shadow* page (RX/TX Split).
-    fn prepare_response_buffer(&mut self, idx: usize, opcode: u8, status: u8, req_id: u64) {
-        // Offset mapping: slot `idx` uses `idx + ring_capacity` for responses
-        let tx_idx = idx + self.ring_capacity;
-        let page = self.pool.get_page_mut(tx_idx);
+    /// Formats a response header in the *shadow* page (RX/TX Split).
+    ///
+    /// Returns the page offset where the caller must write `payload_len` bytes of payload.
+    /// Callers must check `tx_room` first.
+    fn prepare_response_buffer(&mut self, idx: usize, opcode: u8, status: u8, req_id: u64, payload_len: usize) -> usize {
+        // Phase 7.4: Queue behind the in-flight write so it is never overwritten
+        let offset = self.inflight_tx_bytes[idx] + self.pending_tx_bytes[idx];
+        let page = self.pool.get_page_mut(self.tx(idx));
         let data = page.as_slice_mut();
-        
-        // Phase 7.4: Use pending_acks as offset to allow queuing responses while write is in flight
-        let offset = self.pending_acks[idx];
+
         let header = ResponseHeader {
             magic: VBP_MAGIC,
             status,
             opcode,
-            payload_len: 0, 
+            payload_len: payload_len as u32,
             request_id: req_id,
         };
-        
-        // SAFETY: ResponseHeader is #[repr(C)] fixed size.
+
+        // SAFETY: ResponseHeader is #[repr(C)] fixed size and the caller reserved
+        // HEADER_SIZE + payload_len bytes at `offset`. Payloads may leave the cursor unaligned.
         unsafe {
-            let ptr = data.as_mut_ptr().add(offset * 16) as *mut ResponseHeader;
-            *ptr = header;
+            let ptr = data.as_mut_ptr().add(offset) as *mut ResponseHeader;
+            std::ptr::write_unaligned(ptr, header);
         }
         self.pending_acks[idx] += 1;
+        self.pending_tx_bytes[idx] += HEADER_SIZE + payload_len;
+        offset + HEADER_SIZE
+    }
+
+    /// Queues a `status` response carrying `message` as its UTF-8 payload, cut down to
+    /// `MAX_STATUS_MESSAGE` and to the shadow page room left. Callers must check
+    /// `tx_room` covers at least the header.
+    fn prepare_error_response(&mut self, idx: usize, opcode: u8, status: u8, req_id: u64, message: &str) {
+        let mut len = message.len().min(MAX_STATUS_MESSAGE).min(self.tx_room(idx).saturating_sub(HEADER_SIZE));
+        while !message.is_char_boundary(len) {
+            len -= 1;
+        }
+        let offset = self.prepare_response_buffer(idx, opcode, status, req_id, len);
+        let page = self.pool.get_page_mut(self.tx(idx));
+        page.as_slice_mut()[offset..offset + len].copy_from_slice(&message.as_bytes()[..len]);
+    }
+
+    /// Answers the frame at the read cursor with the fatal `status`, then stops reading:
+    /// the stream cannot be resynchronized, so the connection closes once the response drains.
+    fn reject_stream(&mut self, idx: usize, opcode: u8, status: u8, req_id: u64, message: &str) {
+        if self.tx_room(idx) < HEADER_SIZE {
+            // Retried from handle_write_complete once the shadow page drains
+            self.submit_write(idx);
+            self.backpressure_count += 1;
+            return;
+        }
+        self.closing[idx] = true;
+        self.pending_ops[idx] += 1;
+        self.prepare_error_response(idx, opcode, status, req_id, message);
+        self.submit_write(idx);
     }
 
     /// Submits a write to the socket from the shadow response lane.
-    fn submit_write(&mut self, idx: usize, len: Option<usize>) {
+    /// Sends every queued response in one write, unless a write is already in flight.
+    fn submit_write(&mut self, idx: usize) {
         if let Some(fd) = self.active_fds[idx] {
             if self.write_in_flight[idx] {
                 return;
             }
 
-            let write_len = match len {
-                Some(l) => l,
-                None => {
-                    let total = self.pending_acks[idx];
-                    self.pending_acks[idx] = 0;
-                    total * 16
-                }
-            };
-
+            let write_len = self.pending_tx_bytes[idx];
             if write_len == 0 {
                 return;
             }
 
+            self.inflight_tx_bytes[idx] = write_len;
+            self.inflight_acks[idx] = self.pending_acks[idx];
+            self.pending_tx_bytes[idx] = 0;
+            self.pending_acks[idx] = 0;
+
             self.write_in_flight[idx] = true;
-            let tx_idx = idx + self.ring_capacity;
-            let page = self.pool.get_page_mut(tx_idx);
+            let page = self.pool.get_page_mut(self.tx(idx));
             let buf = page.as_slice_mut();
             
             let tag = TAG_WRITE_PREFIX | (idx as u64);
@@ -421,16 +784,16 @@ impl ShardReactor {
     fn handle_ingress(&mut self, idx: usize, bytes: usize) {
         self.read_in_flight[idx] = false;
 
+        // The connection was retired while this read was in flight: only the slot is left to free
+        if self.active_fds[idx].is_none() {
+            self.retire_connection(idx);
+            return;
+        }
+
         // 1. Handle Client Death (EOF)
         if bytes == 0 {
             trace!("Shard {} Ingress -> Client disconnected (EOF).", self.shard_id);
-            self.active_fds[idx] = None;
-            
-            // Only cleanup if no WAL/Write operations are in flight
-            if self.pending_ops[idx] == 0 {
-                self.accumulated_bytes[idx] = 0;
-                self.consumed_bytes[idx] = 0;
-            }
+            self.retire_connection(idx);
             return;
         }
 
@@ -444,7 +807,7 @@ impl ShardReactor {
     }
 
     fn process_ingress(&mut self, idx: usize) {
-        if self.read_in_flight[idx] {
+        if self.read_in_flight[idx] || self.closing[idx] || self.active_fds[idx].is_none() {
             return;
         }
 
@@ -455,7 +818,7 @@ impl ShardReactor {
             
             if available < 16 {
                 if consumed > 0 {
-                    let page = self.pool.get_page_mut(idx);
+                    let page = self.pool.get_page_mut(self.rx(idx));
                     let data = page.as_slice_mut();
                     data.copy_within(consumed..total, 0);
                     self.accumulated_bytes[idx] = available;
@@ -471,23 +834,24 @@ impl ShardReactor {
                 break;
             }
 
-            // Peek Header
-            let (expected, opcode, req_id) = {
-                let page = self.pool.get_page_mut(idx);
-                let data = &page.as_slice_mut()[consumed..consumed + 16];
-                let header = unsafe { &*(data.as_ptr() as *const vortex_rpc::RequestHeader) };
-                
-                if header.magic != vortex_rpc::VBP_MAGIC {
-                    error!("Shard {} PROTOCOL CORRUPTION: Invalid Magic at consumed {}. Available {}.", self.shard_id, consumed, available);
-                    self.active_fds[idx] = None;
-                    return;
-                }
-                (16 + header.payload_len as usize, header.opcode, header.request_id)
+            // Peek Header: validated and copied out, as pipelined frames start at arbitrary offsets
+            let header = RequestHeader::parse(&self.pool.get_page_mut(self.rx(idx)).as_slice_mut()[consumed..consumed + HEADER_SIZE]);
+            let Ok(header) = header else {
+                error!("Shard {} PROTOCOL CORRUPTION: Invalid Magic at consumed {}. Available {}.", self.shard_id, consumed, available);
+                self.reject_stream(idx, 0, STATUS_BAD_MAGIC, 0, "Invalid magic number; closing connection");
+                return;
             };
+            let (expected, version, opcode, req_id) = (16 + header.payload_len as usize, header.version, header.opcode, header.request_id);
+            // The whole frame must fit the RX page; a larger one could never complete
+            if expected > PAGE_BYTES {
+                warn!("Shard {} Frame of {} bytes exceeds the {} byte receive buffer (req {}).", self.shard_id, expected, PAGE_BYTES, req_id);
+                self.reject_stream(idx, opcode, STATUS_FRAME_TOO_LARGE, req_id, "Frame exceeds the receive buffer; closing connection");
+                return;
+            }
 
             if available < expected {
                 if consumed > 0 {
-                    let page = self.pool.get_page_mut(idx);
+                    let page = self.pool.get_page_mut(self.rx(idx));
                     let data = page.as_slice_mut();
                     data.copy_within(consumed..total, 0);
                     self.accumulated_bytes[idx] = available;
@@ -502,44 +866,146 @@ impl ShardReactor {
                 break;
             }
 
+            let body = consumed + HEADER_SIZE;
+            let end = consumed + expected;
+            // A complete frame: the read deadline restarts with the next one
+            self.frame_started[idx] = None;
+
+            // Every frame but the handshake must use a version this connection speaks
+            if opcode != CMD_HELLO && !(VBP_VERSION_MIN..=self.max_version[idx]).contains(&version) {
+                if self.tx_room(idx) < HEADER_SIZE + MAX_STATUS_MESSAGE {
+                    self.submit_write(idx);
+                    self.backpressure_count += 1;
+                    return;
+                }
+                warn!("Shard {} Refusing protocol version {} on connection {} (req {}).", self.shard_id, version, idx, req_id);
+                self.pending_ops[idx] += 1;
+                self.prepare_error_response(idx, opcode, STATUS_UNSUPPORTED_VERSION, req_id, "Unsupported protocol version");
+                self.submit_write(idx);
+                self.consumed_bytes[idx] += expected;
+                continue;
+            }
+
+            // Collection admin and the handshake: rare control-plane frames, answered inline
+            if matches!(opcode, CMD_CREATE_COLLECTION | CMD_LIST_COLLECTIONS | CMD_DROP_COLLECTION | CMD_HELLO) {
+                if self.tx_room(idx) < ADMIN_RESPONSE_BYTES {
+                    self.submit_write(idx);
+                    self.backpressure_count += 1;
+                    return;
+                }
+                self.pending_ops[idx] += 1;
+                self.execute_admin(idx, opcode, req_id, body, end);
+                self.submit_write(idx);
+                self.consumed_bytes[idx] += expected;
+                continue;
+            }
+
+            // Data opcodes address a collection; anything else is refused with STATUS_UNKNOWN_OPCODE
+            let route = match opcode {
+                CMD_UPSERT | CMD_DELETE | CMD_GET | CMD_SEARCH => self.route_collection(idx, version, body, end),
+                _ => Err((STATUS_UNKNOWN_OPCODE, "Unknown opcode")),
+            };
+
+            // Decode the search prefix up front so the egress check knows the response size
+            let search = match route {
+                Ok((slot, start)) if opcode == CMD_SEARCH => self.decode_search_request(idx, slot, start, end),
+                _ => Err((STATUS_ERR, "Not a search frame")),
+            };
+            let response_bytes = HEADER_SIZE + match route {
+                Ok(_) if opcode == CMD_SEARCH => search.map_or(MAX_STATUS_MESSAGE, |params| params.top_k * SearchHit::SIZE),
+                Ok((slot, _)) if opcode == CMD_GET && version >= VBP_VERSION_2 => {
+                    StoredVector::DIMENSION_SIZE + self.collection(slot).index.dimension() * 4 + MAX_METADATA_SIZE
+                }
+                Ok((slot, _)) if opcode == CMD_GET => self.collection(slot).index.dimension() * 4,
+                Ok(_) => 0,
+                Err(_) => MAX_STATUS_MESSAGE,
+            };
+
+            // Egress Backpressure: hold the frame until the shadow page can take its response.
+            // handle_write_complete re-enters process_ingress once the socket drains.
+            if self.tx_room(idx) < response_bytes {
+                self.submit_write(idx);
+                self.backpressure_count += 1;
+                return;
+            }
+
+            let (slot, start) = match route {
+                Ok(route) => route,
+                Err((status, e)) => {
+                    self.pending_ops[idx] += 1;
+                    self.prepare_error_response(idx, opcode, status, req_id, e);
+                    self.submit_write(idx);
+                    self.consumed_bytes[idx] += expected;
+                    continue;
+                }
+            };
+
             // Handle Request
             match opcode {
                 CMD_SEARCH => {
-                    let s_start = Instant::now();
-                    let _results = self.index.search(self.scratch_query_buffer.as_slice(), 10);
-                    let s_dur = s_start.elapsed();
-                    
-                    self.tick_search_ops += 1;
-                    self.tick_search_micros += s_dur.as_micros() as u64;
-
                     self.pending_ops[idx] += 1;
-                    self.prepare_response_buffer(idx, CMD_SEARCH, STATUS_OK, req_id);
-                    self.submit_write(idx, None);
+                    match search {
+                        Ok(params) => self.execute_search(idx, slot, req_id, params),
+                        Err((status, e)) => {
+                            warn!("Shard {} Malformed SEARCH (req {}): {}", self.shard_id, req_id, e);
+                            self.prepare_error_response(idx, CMD_SEARCH, status, req_id, e);
+                        }
+                    }
+                    self.submit_write(idx);
                 },
-                CMD_UPSERT => {
+                CMD_GET => {
+                    self.pending_ops[idx] += 1;
+                    self.execute_get(idx, slot, version, req_id, start, end);
+                    self.submit_write(idx);
+                },
+                _ => {
+                    // CMD_UPSERT | CMD_DELETE: logged through the collection's group commit
                     if self.pending_ops[idx] == 0 {
-                        trace!("Shard {} Ingress -> First UPSERT for connection {}. Starting pipeline.", self.shard_id, idx);
+                        trace!("Shard {} Ingress -> First mutation for connection {}. Starting pipeline.", self.shard_id, idx);
+                    }
+                    // Reject malformed payloads and wrongly sized vectors before they reach the WAL
+                    let dim = self.collection(slot).index.dimension();
+                    let invalid = {
+                        let page = self.pool.get_page_mut(self.rx(idx));
+                        parse_command(opcode, &page.as_slice_mut()[start..end], dim).err()
+                    };
+                    if let Some((status, e)) = invalid {
+                        warn!("Shard {} Mutation (opcode {}, req {}) rejected: {} (dimension {}).", self.shard_id, opcode, req_id, e, dim);
+                        self.prepare_error_response(idx, opcode, status, req_id, e);
+                        self.submit_write(idx);
+                        self.pending_ops[idx] += 1;
+                        self.consumed_bytes[idx] += expected;
+                        continue;
                     }
-                    let tag = idx as u64;
+                    if self.collection(slot).failed {
+                        self.prepare_error_response(idx, opcode, STATUS_ERR, req_id, "Collection WAL failed");
+                        self.submit_write(idx);
+                        self.pending_ops[idx] += 1;
+                        self.consumed_bytes[idx] += expected;
+                        continue;
+                    }
+                    let tag = BatchTag { slot: idx, request_id: req_id };
                     let push_res = {
-                        let page = self.pool.get_page_mut(idx);
-                        let data = &page.as_slice_mut()[consumed..consumed + expected];
-                        self.active_batch.try_add(data, tag)
+                        let page = self.pool.get_page_mut(self.rx(idx));
+                        let data = &page.as_slice_mut()[consumed..end];
+                        let collection = self.collections[slot].as_mut().expect("Routed to an empty collection slot");
+                        collection.active_batch.try_add(data, tag)
                     };
 
-                    if let Err(_) = push_res {
-                        if self.flushing_batch.is_none() {
-                            self.flush_active_batch(FlushReason::Full);
+                    if push_res.is_err() {
+                        if self.collection(slot).flushing_batch.is_none() {
+                            self.flush_active_batch(slot, FlushReason::Full);
                             // Retry in fresh batch
-                            let page = self.pool.get_page_mut(idx);
-                            let data = &page.as_slice_mut()[consumed..consumed + expected];
-                            if let Err(_) = self.active_batch.try_add(data, tag) {
+                            let page = self.pool.get_page_mut(self.rx(idx));
+                            let data = &page.as_slice_mut()[consumed..end];
+                            let collection = self.collections[slot].as_mut().expect("Routed to an empty collection slot");
+                            if collection.active_batch.try_add(data, tag).is_err() {
                                 error!("Shard {} Command too big for batch: {} bytes", self.shard_id, expected);
-                                self.prepare_response_buffer(idx, CMD_UPSERT, STATUS_ERR, req_id);
-                                if !self.write_in_flight[idx] {
-                                    self.submit_write(idx, None);
-                                }
-                                // Bytes are consumed below.
+                                self.prepare_error_response(idx, opcode, STATUS_FRAME_TOO_LARGE, req_id, "Command exceeds the group commit batch");
+                                self.submit_write(idx);
+                                self.pending_ops[idx] += 1;
+                                self.consumed_bytes[idx] += expected;
+                                continue;
                             }
                         } else {
                             if !self.paused_reads.contains(&idx) {
@@ -549,12 +1015,9 @@ impl ShardReactor {
                             return;
                         }
                     }
+                    // The ACK is written after the group commit; hold its slot in the shadow page.
+                    self.reserved_tx_bytes[idx] += HEADER_SIZE;
                     self.pending_ops[idx] += 1;
-                },
-                _ => {
-                    self.pending_ops[idx] += 1;
-                    self.prepare_response_buffer(idx, opcode, STATUS_ERR, req_id);
-                    self.submit_write(idx, None);
                 }
             }
 
@@ -562,74 +1025,393 @@ impl ShardReactor {
         }
     }
 
-    fn flush_active_batch(&mut self, reason: FlushReason) {
+    /// Local collection in `slot`. Only called with slots returned by `route_collection`.
+    fn collection(&self, slot: usize) -> &Collection {
+        self.collections[slot].as_ref().expect("Routed to an empty collection slot")
+    }
+
+    /// Resolves the collection addressed by the data frame in `RX[idx][start..end]`.
+    /// Returns the collection slot and the offset where the opcode payload begins,
+    /// or the status and reason to answer with.
+    fn route_collection(&mut self, idx: usize, version: u8, start: usize, end: usize) -> Result<(usize, usize), (u8, &'static str)> {
+        let split = {
+            let page = self.pool.get_page_mut(self.rx(idx));
+            CollectionRef::split(version, &page.as_slice_mut()[start..end])
+        };
+        let (collection_id, prefix) = split.map_err(|e| {
+            warn!("Shard {} Unroutable frame on connection {}: {}", self.shard_id, idx, e);
+            (STATUS_ERR, e)
+        })?;
+        let slot = self.collection_slot(collection_id).ok_or((STATUS_UNKNOWN_COLLECTION, "Collection does not exist"))?;
+        Ok((slot, start + prefix))
+    }
+
+    /// Finds the local slot of collection `id`, syncing with the catalog first if it
+    /// changed (the collection may have been created through another shard).
+    fn collection_slot(&mut self, id: u32) -> Option<usize> {
+        let find = |collections: &[Option<Collection>]| {
+            collections.iter().position(|c| c.as_ref().is_some_and(|c| c.id == id && !c.dropped))
+        };
+        if let Some(slot) = find(&self.collections) {
+            return Some(slot);
+        }
+        if self.catalog.generation() != self.catalog_generation {
+            self.sync_catalog();
+            return find(&self.collections);
+        }
+        None
+    }
+
+    /// Opens collections created since the last sync and retires dropped ones.
+    ///
+    /// # Rule #8 Exception
+    /// Opening a collection replays its WAL stream with blocking I/O. This only runs
+    /// after an admin opcode, never on the steady-state data path.
+    fn sync_catalog(&mut self) {
+        self.catalog_generation = self.catalog.generation();
+        let live = self.catalog.collections();
+
+        for slot in 0..self.collections.len() {
+            let retired = self.collections[slot].as_ref()
+                .is_some_and(|c| !c.dropped && !live.iter().any(|info| info.id == c.id));
+            if retired {
+                self.retire_collection(slot);
+            }
+        }
+
+        for info in &live {
+            if self.collections.iter().flatten().any(|c| c.id == info.id) || !self.catalog.attach(info.id) {
+                continue;
+            }
+            let dir = self.catalog.collection_dir(info.id);
+            match Collection::open(self.shard_id, info, &dir, &mut self.scratch_query_buffer) {
+                Ok(mut collection) => {
+                    collection.wal.set_segment_size(self.wal_segment_size);
+                    info!("Shard {} Collection {} (id {}) online.", self.shard_id, info.name, info.id);
+                    match self.collections.iter().position(|c| c.is_none()) {
+                        Some(slot) => self.collections[slot] = Some(collection),
+                        None => self.collections.push(Some(collection)),
+                    }
+                }
+                Err(e) => {
+                    error!("Shard {} Failed to open collection {}: {}", self.shard_id, info.name, e);
+                    self.catalog.detach(info.id);
+                }
+            }
+        }
+    }
+
+    /// Stops routing to the collection in `slot`. Queued mutations are still committed
+    /// and ACKed with STATUS_UNKNOWN_COLLECTION; the slot is freed and the catalog told
+    /// once no batch is left.
+    fn retire_collection(&mut self, slot: usize) {
+        let Some(collection) = self.collections[slot].as_mut() else { return };
+        collection.dropped = true;
+        if collection.flushing_batch.is_none() {
+            if collection.active_batch.is_dirty() {
+                self.flush_active_batch(slot, FlushReason::Eot);
+            } else {
+                info!("Shard {} Collection {} retired.", self.shard_id, collection.name);
+                let id = collection.id;
+                // Closes the WAL and joins the snapshot writer before the files may be deleted
+                self.collections[slot] = None;
+                self.catalog.detach(id);
+            }
+        }
+    }
+
+    /// Serves the collection admin opcodes and `OP_HELLO` for the frame payload in `RX[idx][start..end]`.
+    ///
+    /// # Rule #8 Exception
+    /// Catalog changes write control files synchronously. Admin frames are rare
+    /// control-plane operations, like boot.
+    fn execute_admin(&mut self, idx: usize, opcode: u8, req_id: u64, start: usize, end: usize) {
+        match opcode {
+            CMD_CREATE_COLLECTION => {
+                let created = {
+                    let page = self.pool.get_page_mut(self.rx(idx));
+                    match parse_command(opcode, &page.as_slice_mut()[start..end], 0) {
+                        Ok(Command::CreateCollection { spec, name }) => self.catalog.create(name, spec),
+                        Ok(_) => unreachable!("OP_CREATE_COLLECTION parses to Command::CreateCollection"),
+                        Err((_, e)) => Err(e),
+                    }
+                };
+                match created {
+                    Ok(info) => {
+                        self.sync_catalog();
+                        self.write_collection_infos(idx, opcode, req_id, &[info]);
+                    }
+                    Err(e) => {
+                        warn!("Shard {} CREATE_COLLECTION (req {}) refused: {}", self.shard_id, req_id, e);
+                        self.prepare_error_response(idx, opcode, STATUS_ERR, req_id, e);
+                    }
+                }
+            }
+            CMD_LIST_COLLECTIONS => {
+                let infos = self.catalog.collections();
+                self.write_collection_infos(idx, opcode, req_id, &infos);
+            }
+            CMD_HELLO => self.execute_hello(idx, req_id, start, end),
+            _ => {
+                // CMD_DROP_COLLECTION
+                let dropped = {
+                    let page = self.pool.get_page_mut(self.rx(idx));
+                    match parse_command(opcode, &page.as_slice_mut()[start..end], 0) {
+                        Ok(Command::DropCollection(target)) => self.catalog.drop_collection(target.collection_id),
+                        Ok(_) => unreachable!("OP_DROP_COLLECTION parses to Command::DropCollection"),
+                        Err((_, e)) => Err(e),
+                    }
+                };
+                match dropped {
+                    Ok(true) => {
+                        self.sync_catalog();
+                        self.prepare_response_buffer(idx, opcode, STATUS_OK, req_id, 0);
+                    }
+                    Ok(false) => self.prepare_error_response(idx, opcode, STATUS_UNKNOWN_COLLECTION, req_id, "Collection does not exist"),
+                    Err(e) => {
+                        warn!("Shard {} DROP_COLLECTION (req {}) refused: {}", self.shard_id, req_id, e);
+                        self.prepare_error_response(idx, opcode, STATUS_ERR, req_id, e);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Negotiates the protocol version of connection `idx` and answers with the server limits.
+    fn execute_hello(&mut self, idx: usize, req_id: u64, start: usize, end: usize) {
+        let request = {
+            let page = self.pool.get_page_mut(self.rx(idx));
+            match parse_command(CMD_HELLO, &page.as_slice_mut()[start..end], 0) {
+                Ok(Command::Hello(request)) => Ok(request),
+                Ok(_) => unreachable!("OP_HELLO parses to Command::Hello"),
+                Err(rejection) => Err(rejection),
+            }
+        };
+        let version = match request.map(|r| (r, r.negotiate())) {
+            Ok((_, Some(version))) => version,
+            Ok((r, None)) => {
+                warn!("Shard {} HELLO (req {}) offers versions {}..={}; this server speaks {}..={}.",
+                    self.shard_id, req_id, r.min_version, r.max_version, VBP_VERSION_MIN, VBP_VERSION_MAX);
+                self.prepare_error_response(idx, CMD_HELLO, STATUS_UNSUPPORTED_VERSION, req_id, "No protocol version in common");
+                return;
+            }
+            Err((status, e)) => {
+                self.prepare_error_response(idx, CMD_HELLO, status, req_id, e);
+                return;
+            }
+        };
+        self.max_version[idx] = version;
+
+        let dimension = self.collection_slot(DEFAULT_COLLECTION).map_or(0, |slot| self.collection(slot).index.dimension());
+        let hello = HelloResponse {
+            version,
+            max_frame_size: PAGE_BYTES as u32,
+            dimension: dimension as u32,
+            opcodes: SUPPORTED_OPCODES.to_vec(),
+        };
+        let offset = self.prepare_response_buffer(idx, CMD_HELLO, STATUS_OK, req_id, hello.encoded_len());
+        let page = self.pool.get_page_mut(self.tx(idx));
+        hello.write_to(&mut page.as_slice_mut()[offset..]);
+    }
+
+    /// Queues a response whose payload is `infos` encoded back to back.
+    fn write_collection_infos(&mut self, idx: usize, opcode: u8, req_id: u64, infos: &[CollectionInfo]) {
+        let payload_len = infos.iter().map(|info| info.encoded_len()).sum();
+        let mut offset = self.prepare_response_buffer(idx, opcode, STATUS_OK, req_id, payload_len);
+        let page = self.pool.get_page_mut(self.tx(idx));
+        let data = page.as_slice_mut();
+        for info in infos {
+            info.write_to(&mut data[offset..]);
+            offset += info.encoded_len();
+        }
+    }
+
+    /// Validates the `OP_SEARCH` payload in `RX[idx][start..end]` and copies the query
+    /// and filter into the scratch buffers. Returns the query parameters, or the error status and reason.
+    fn decode_search_request(&mut self, idx: usize, slot: usize, start: usize, end: usize) -> Result<SearchParams<'static>, (u8, &'static str)> {
+        let dim = self.collections[slot].as_ref().map_or(0, |c| c.index.dimension());
+        let page = self.pool.get_page_mut(self.rx(idx));
+        let Command::Search { request, query, filter } = parse_command(CMD_SEARCH, &page.as_slice_mut()[start..end], dim)? else {
+            unreachable!("OP_SEARCH parses to Command::Search");
+        };
+
+        // Copy out of the RX page: frames start at arbitrary offsets, so the
+        // f32 values cannot be borrowed in place.
+        vortex_rpc::decode_f32s(query, &mut self.scratch_query_buffer[..]);
+        self.scratch_filter = filter;
+        Ok(SearchParams::new(request.top_k as usize).with_ef_search(request.ef_search as usize))
+    }
+
+    /// Runs the scratch query against the collection index and queues the hits as the response payload.
+    fn execute_search(&mut self, idx: usize, slot: usize, req_id: u64, params: SearchParams<'static>) {
+        let s_start = Instant::now();
+        let index = &self.collection(slot).index;
+        let dim = index.dimension();
+        let results = index.search(&self.scratch_query_buffer[..dim], params.with_filter(self.scratch_filter.as_ref()));
+        let s_dur = s_start.elapsed();
+
+        self.tick_search_ops += 1;
+        self.tick_search_micros += s_dur.as_micros() as u64;
+
+        let payload_len = results.len() * SearchHit::SIZE;
+        let payload_offset = self.prepare_response_buffer(idx, CMD_SEARCH, STATUS_OK, req_id, payload_len);
+
+        let page = self.pool.get_page_mut(self.tx(idx));
+        let data = &mut page.as_slice_mut()[payload_offset..payload_offset + payload_len];
+        for (&(id, distance), out) in results.iter().zip(data.chunks_exact_mut(SearchHit::SIZE)) {
+            SearchHit { id, distance, reserved: 0 }.write_to(out);
+        }
+    }
+
+    /// Answers the `OP_GET` in `RX[idx][start..end]` with the stored vector (or NOT_FOUND).
+    /// v2 answers carry the dimension and the vector's metadata (see `StoredVector`).
+    fn execute_get(&mut self, idx: usize, slot: usize, version: u8, req_id: u64, start: usize, end: usize) {
+        let request = {
+            let page = self.pool.get_page_mut(self.rx(idx));
+            parse_command(CMD_GET, &page.as_slice_mut()[start..end], 0)
+        };
+        let id = match request {
+            Ok(Command::Get(r)) => r.id,
+            Ok(_) => unreachable!("OP_GET parses to Command::Get"),
+            Err((status, e)) => {
+                warn!("Shard {} Malformed GET (req {}): {}", self.shard_id, req_id, e);
+                self.prepare_error_response(idx, CMD_GET, status, req_id, e);
+                return;
+            }
+        };
+
+        let index = &self.collections[slot].as_ref().expect("Routed to an empty collection slot").index;
+        let dim = index.dimension();
+        if !index.get(id, &mut self.scratch_query_buffer[..dim]) {
+            self.prepare_response_buffer(idx, CMD_GET, STATUS_NOT_FOUND, req_id, 0);
+            return;
+        }
+
+        let mut trailer = Vec::new();
+        if version >= VBP_VERSION_2 {
+            if let Some(metadata) = index.metadata(id) {
+                metadata.encode_block(&mut trailer);
+            }
+        }
+        let prefix = if version >= VBP_VERSION_2 { StoredVector::DIMENSION_SIZE } else { 0 };
+        let payload_len = prefix + dim * 4 + trailer.len();
+        let payload_offset = self.prepare_response_buffer(idx, CMD_GET, STATUS_OK, req_id, payload_len);
+        let page = self.pool.get_page_mut(self.tx(idx));
+        let payload = &mut page.as_slice_mut()[payload_offset..payload_offset + payload_len];
+        let (dimension, rest) = payload.split_at_mut(prefix);
+        if prefix > 0 {
+            dimension.copy_from_slice(&(dim as u32).to_le_bytes());
+        }
+        let (vector, metadata) = rest.split_at_mut(dim * 4);
+        vortex_rpc::encode_f32s(&self.scratch_query_buffer[..dim], vector);
+        metadata.copy_from_slice(&trailer);
+    }
+
+    fn flush_active_batch(&mut self, slot: usize, reason: FlushReason) {
         let f_start = Instant::now();
-        if !self.active_batch.is_dirty() { return; }
-        if self.flushing_batch.is_some() { return; } // Pipeline full
+        let Some(collection) = self.collections[slot].as_mut() else { return };
+        if !collection.active_batch.is_dirty() { return; }
+        if collection.flushing_batch.is_some() { return; } // Pipeline full
 
         // Swap to Flushing
-        let mut batch = std::mem::replace(&mut self.active_batch, BatchAccumulator::new());
-        let (ptr, len) = batch.prepare_flush();
-        
-        info!("Shard {} Group Commit -> Flushing batch of {} bytes ({} requests) ({}).", self.shard_id, len, batch.tags.len(), reason);
-        self.flushing_batch = Some(batch);
-        
-        let tag = TAG_BATCH_WRITE;
-        let wal_e = self.wal.write_entry(ptr, len as u32, tag);
+        let mut batch = std::mem::take(&mut collection.active_batch);
+        let tag = TAG_BATCH_WRITE | slot as u64;
+        collection.flushing_from = (collection.wal.current_offset(), collection.wal.next_seq());
+        let (wal_e, len) = collection.wal.write_batch(&mut batch, tag);
+
+        info!("Shard {} Group Commit -> Flushing batch of {} bytes ({} requests) to {} ({}).", self.shard_id, len, batch.tags.len(), collection.name, reason);
+        collection.flushing_batch = Some(batch);
         self.push_submission(&wal_e);
         self.tick_flush_ns += f_start.elapsed().as_nanos() as u64;
     }
 
-    fn handle_batch_complete(&mut self, bytes: usize) {
-        let mut batch = self.flushing_batch.take().expect("Protocol Error: No flushing batch found.");
+    /// Completes the in-flight group commit of `slot` with the raw CQE `result`.
+    ///
+    /// A failed or short write persisted nothing the replay can trust: the WAL is rewound to
+    /// where the batch began and every request in it is answered with the retryable `STATUS_STORAGE`.
+    fn handle_batch_complete(&mut self, slot: usize, result: i32) {
+        let shard_id = self.shard_id;
+        let collection = self.collections[slot].as_mut().expect("Protocol Error: Batch completed for an empty collection slot.");
+        let mut batch = collection.flushing_batch.take().expect("Protocol Error: No flushing batch found.");
+        let dropped = collection.dropped;
         let tags = batch.take_tags();
-        
-        trace!("Shard {} Group Commit -> {} bytes persisted. ACKing {} requests in batch.", self.shard_id, bytes, tags.len());
-        
-        // Group ACKs by connection to avoid Zero-Copy Hazards in egress
-        let mut ack_counts = [0usize; 32];
-        for idx_u64 in tags {
-            let idx = idx_u64 as usize;
-            if idx < 32 {
-                ack_counts[idx] += 1;
+
+        let persisted = usize::try_from(result).is_ok_and(|bytes| bytes == batch.flush_len());
+        if persisted {
+            trace!("Shard {} Group Commit -> {} bytes persisted. ACKing {} requests in batch.", shard_id, result, tags.len());
+        } else {
+            if result < 0 {
+                error!("Shard {} Group Commit -> WAL write to {} failed: {}", shard_id, collection.name, std::io::Error::from_raw_os_error(-result));
+            } else {
+                error!("Shard {} Group Commit -> Short WAL write to {}: {} of {} bytes.", shard_id, collection.name, result, batch.flush_len());
+            }
+            let (offset, seq) = collection.flushing_from;
+            if let Err(e) = collection.wal.rewind(offset, seq) {
+                error!("Shard {} Group Commit -> Cannot rewind the WAL of {} to offset {}: {}. Refusing further mutations.", shard_id, collection.name, offset, e);
+                collection.failed = true;
             }
         }
-        
-        for idx in 0..32 {
-            let count = ack_counts[idx];
-            if count > 0 {
-                // Prepare 'count' ACKs in the shadow TX buffer at the correct offset
-                {
-                    let tx_idx = idx + self.ring_capacity;
-                    let page = self.pool.get_page_mut(tx_idx);
-                    let data = page.as_slice_mut();
-                    
-                    // Phase 7.3: Use pending_acks as offset for deferred aggregation
-                    let offset = self.pending_acks[idx];
-                    for i in 0..count {
-                        let header = vortex_rpc::ResponseHeader {
-                            magic: vortex_rpc::VBP_MAGIC,
-                            status: vortex_rpc::STATUS_OK,
-                            opcode: CMD_UPSERT,
-                            payload_len: 0,
-                            request_id: 0, // In saturation mode, we sacrifice linearization for throughput
-                        };
-                        unsafe {
-                            let ptr = data.as_mut_ptr().add((offset + i) * 16) as *mut vortex_rpc::ResponseHeader;
-                            *ptr = header;
+
+        // Persistence precedes visibility: the batch is durable, so apply it to the
+        // index before any ACK goes out (read-your-writes for the client).
+        // Records and tags were appended together, so they pair up one-to-one.
+        let mut touched = std::mem::take(&mut self.touched);
+        for ((header, payload), tag) in batch.records().zip(tags) {
+            let outcome = match self.collections[slot].as_mut() {
+                _ if !persisted => Err((STATUS_STORAGE, "WAL write failed")),
+                Some(collection) if !dropped => {
+                    match apply_record(&mut collection.index, &header, payload, &mut self.scratch_query_buffer) {
+                        Ok(id) => {
+                            trace!("Shard {} applied op {} for vector id {}.", self.shard_id, header.opcode, id);
+                            Ok(())
+                        }
+                        Err((status, e)) => {
+                            warn!("Shard {} Group Commit -> Rejecting record (req {}): {}", self.shard_id, tag.request_id, e);
+                            Err((status, e))
                         }
                     }
                 }
+                _ => Err((STATUS_UNKNOWN_COLLECTION, "Collection was dropped")),
+            };
 
-                self.pending_acks[idx] += count;
-                if self.write_in_flight[idx] {
-                    continue;
+            // Phase 7.3: Queue the ACK in the shadow TX buffer (space was reserved at ingest)
+            let idx = tag.slot;
+            if idx >= touched.len() {
+                continue;
+            }
+            self.reserved_tx_bytes[idx] = self.reserved_tx_bytes[idx].saturating_sub(HEADER_SIZE);
+            touched[idx] = true;
+            if self.active_fds[idx].is_none() {
+                // The client is gone; nobody is left to ACK
+                continue;
+            }
+            match outcome {
+                Ok(()) => {
+                    self.prepare_response_buffer(idx, header.opcode, STATUS_OK, tag.request_id, 0);
+                }
+                Err((status, e)) => self.prepare_error_response(idx, header.opcode, status, tag.request_id, e),
+            }
+        }
+
+        // A dropped collection drains whatever queued up behind this batch, then frees its slot
+        if dropped {
+            self.retire_collection(slot);
+        } else if let Some(collection) = self.collections[slot].as_mut() {
+            // Phase 4.1: The index now matches the WAL exactly, a consistent point to snapshot
+            collection.maybe_snapshot(self.shard_id, self.snapshot_interval);
+        }
+
+        // Submit ONE aggregated write per connection to avoid Zero-Copy Hazards in egress
+        for (idx, has_acks) in touched.iter_mut().enumerate() {
+            if std::mem::take(has_acks) {
+                if self.active_fds[idx].is_some() {
+                    self.submit_write(idx);
+                } else {
+                    self.retire_connection(idx);
                 }
-                
-                // Submit ONE aggregated write for all ACKs of this connection
-                self.submit_write(idx, None);
             }
         }
+        self.touched = touched;
 
         // Phase 7.2: O(1) Wake-up Logic (Signal all paused readers)
         let pending = std::mem::take(&mut self.paused_reads);
@@ -638,88 +1420,863 @@ impl ShardReactor {
         }
     }
 
-    fn handle_wal_complete(&mut self, idx: usize, bytes: usize) {
-        debug!("Shard {} WAL Persisted ({} bytes). Finalizing command.", self.shard_id, bytes);
-        
-        // 1. Retrieve Payload from SHADOW buffer
-        let shadow_idx = idx + 32;
-        let page = self.pool.get_page_mut(shadow_idx);
-        let data = page.as_slice_mut();
-        
-        let header_size = std::mem::size_of::<vortex_rpc::RequestHeader>();
-        
-        // Safety Check (Protocol Guard II)
-        if bytes < header_size + 8 {
-             error!("Shard {} WAL Complete: Payload too short for Vector ID.", self.shard_id);
-             self.prepare_response_buffer(idx, CMD_UPSERT, STATUS_ERR, 0);
-             self.submit_write(idx, None);
-             return;
-        }
-
-        // 2. Parse ID and Vector using LOGICAL length from the header
-        // Header contains: magic(2) + status(1) + opcode(1) + payload_len(4) + request_id(8) = 16 bytes.
-        // But we are reading the REQUEST header from the WAL: magic(2) + version(1) + opcode(1) + payload_len(4) + request_ids(8) = 16 bytes.
-        let header = unsafe { &*(data.as_ptr() as *const vortex_rpc::RequestHeader) };
-        let logical_payload_len = header.payload_len as usize;
-        
-        // Data is aligned to 4096, so offset 16 is aligned for u64 (8) and f32 (4).
-        let payload_ptr = unsafe { data.as_ptr().add(header_size) };
-        
-        // Parse ID (8 bytes)
-        let id = unsafe { *(payload_ptr as *const u64) };
-        
-        // Parse Vector (logical dimension)
-        let vec_bytes = logical_payload_len - 8;
-        let dim = vec_bytes / 4;
-        
-        if dim == 0 {
-             error!("Shard {} WAL Complete: Logical vector dimension is 0.", self.shard_id);
-             self.prepare_response_buffer(idx, CMD_UPSERT, STATUS_ERR, header.request_id);
-             self.submit_write(idx, None);
-             return;
+    fn handle_write_complete(&mut self, idx: usize, res: usize) {
+        self.write_in_flight[idx] = false;
+        if self.active_fds[idx].is_none() {
+            self.retire_connection(idx);
+            return;
         }
 
-        let vector_slice = unsafe {
-            std::slice::from_raw_parts(payload_ptr.add(8) as *const f32, dim)
-        };
-        
-        // 3. Insert into Index
-        // This is the "Brain Transplant" moment.
-        self.index.insert(id, vector_slice);
-        
-        trace!("Shard {} indexed vector id {} (Dim: {}). Lifecycle complete.", self.shard_id, id, dim);
-        
-        // 4. Send Response (Closing the Circuit)
-        // We need the original Request ID.
-        // It's still in the buffer header!
-        let req_id = match vortex_rpc::verify_header(&data[0..16]) {
-            Ok(h) => h.request_id,
-            Err(_) => 0,
+        let inflight = self.inflight_tx_bytes[idx];
+        let written = res.min(inflight);
+        let remaining = inflight - written + self.pending_tx_bytes[idx];
+
+        // Compact: slide the unsent tail (short write + queued responses) back to offset 0
+        if written > 0 && remaining > 0 {
+            let page = self.pool.get_page_mut(self.tx(idx));
+            page.as_slice_mut().copy_within(written..written + remaining, 0);
+        }
+        self.inflight_tx_bytes[idx] = 0;
+        self.pending_tx_bytes[idx] = remaining;
+
+        // A rejected stream closes once its last response (and any pending ACK) is out
+        if self.closing[idx] && remaining == 0 && written == inflight && self.pending_ops[idx] <= self.inflight_acks[idx] {
+            debug!("Shard {} closing connection {} after a fatal status.", self.shard_id, idx);
+            self.retire_connection(idx);
+            return;
+        }
+
+        let acks_in_write = std::mem::take(&mut self.inflight_acks[idx]);
+        if written < inflight {
+            // Short write: the partially sent responses go out with the next write
+            self.pending_acks[idx] += acks_in_write;
+        } else {
+            self.pending_ops[idx] = self.pending_ops[idx].saturating_sub(acks_in_write);
+        }
+
+        // Result is handled by handle_write_complete and process_ingress for next steps
+        self.submit_write(idx);
+
+        // Phase 7.3.1: Delegate all buffer sovereignty to process_ingress
+        self.process_ingress(idx);
+    }
+}
+
+/// The `STATUS_BUSY` response sent to connections beyond the slab. It answers no
+/// particular request, so opcode and request ID are 0.
+fn busy_frame() -> Box<[u8]> {
+    let message = b"Shard is at max_connections; retry later";
+    let header = ResponseHeader { magic: VBP_MAGIC, status: STATUS_BUSY, opcode: 0, payload_len: message.len() as u32, request_id: 0 };
+    let mut frame = header.to_bytes().to_vec();
+    frame.extend_from_slice(message);
+    frame.into_boxed_slice()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::hnsw::HnswIndex;
+    use crate::storage::manifest::Manifest;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use vortex_rpc::{CollectionSpec, CreateCollectionRequest, HelloRequest, UpsertRequest, DeleteRequest, GetRequest, SearchRequest, Metadata, OP_CREATE_COLLECTION, OP_DELETE, OP_DROP_COLLECTION, OP_GET, OP_LIST_COLLECTIONS, OP_SEARCH, OP_UPSERT, OP_HELLO, METRIC_INNER_PRODUCT, MAX_SEARCH_EF, STATUS_DIMENSION_MISMATCH, VBP_VERSION_1, VBP_VERSION_2};
+
+    fn test_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("vortex_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().into_owned()
+    }
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    fn frame(opcode: u8, request_id: u64, payload: &[u8]) -> Vec<u8> {
+        let header = RequestHeader::new(1, opcode, payload.len() as u32, request_id);
+        let mut packet = Vec::with_capacity(HEADER_SIZE + payload.len());
+        packet.extend_from_slice(&header.to_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    /// A v2 frame: the payload starts with the collection address.
+    fn v2(opcode: u8, request_id: u64, collection_id: u32, body: &[u8]) -> Vec<u8> {
+        let mut packet = frame(opcode, request_id, &CollectionRef { collection_id, reserved: 0 }.encode(body));
+        packet[2] = VBP_VERSION_2;
+        packet
+    }
+
+    fn read_response(stream: &mut TcpStream) -> (ResponseHeader, Vec<u8>) {
+        let mut raw = [0u8; HEADER_SIZE];
+        stream.read_exact(&mut raw).unwrap();
+        let header = ResponseHeader::parse(&raw).unwrap();
+        let mut payload = vec![0u8; header.payload_len as usize];
+        stream.read_exact(&mut payload).unwrap();
+        (header, payload)
+    }
+
+    /// Drives the reactor on the current thread until `client` returns.
+    fn run_with_client<F>(reactor: &mut ShardReactor, port: u16, client: F)
+    where
+        F: FnOnce(&mut TcpStream) + Send + 'static,
+    {
+        struct DoneGuard(Arc<AtomicBool>);
+        impl Drop for DoneGuard {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let done = Arc::new(AtomicBool::new(false));
+        let done_flag = done.clone();
+        let handle = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            // Declared after the stream so it fires first (even on panic); the
+            // stream drop then delivers EOF, which wakes the final tick.
+            let _guard = DoneGuard(done_flag);
+            client(&mut stream);
+        });
+        while !done.load(Ordering::SeqCst) {
+            reactor.run_tick();
+        }
+        handle.join().unwrap();
+    }
+
+    fn default_index(reactor: &mut ShardReactor) -> &mut HnswIndex {
+        &mut reactor.collections[0].as_mut().unwrap().index
+    }
+
+    fn basis_vector(axis: usize) -> Vec<f32> {
+        let mut v = vec![0.0f32; 128];
+        v[axis] = 1.0;
+        v
+    }
+
+    #[test]
+    fn test_search_returns_hits() {
+        let dir = test_dir("search_hits");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        for id in 0..8u64 {
+            default_index(&mut reactor).insert(id, &basis_vector(id as usize));
+        }
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            let mut query = basis_vector(3);
+            query[5] = 0.5;
+            let payload = SearchRequest { top_k: 2, ef_search: 0 }.encode(&query);
+            stream.write_all(&frame(OP_SEARCH, 77, &payload)).unwrap();
+
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_OK);
+            assert_eq!(header.request_id, 77);
+            let hits = SearchHit::decode_all(&body).unwrap();
+            assert_eq!(hits.len(), 2);
+            assert_eq!(hits[0].id, 3);
+            assert_eq!(hits[1].id, 5);
+
+            // Wrong dimension is rejected without dropping the connection
+            let bad = SearchRequest { top_k: 2, ef_search: 0 }.encode(&[1.0; 3]);
+            stream.write_all(&frame(OP_SEARCH, 78, &bad)).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_DIMENSION_MISMATCH);
+            assert_eq!(header.request_id, 78);
+            assert_eq!(body, b"Query dimension mismatch");
+
+            // Per-query beam width: accepted up to the server limit, refused beyond it
+            let wide = SearchRequest { top_k: 8, ef_search: 64 }.encode(&query);
+            stream.write_all(&frame(OP_SEARCH, 79, &wide)).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_OK);
+            assert_eq!(SearchHit::decode_all(&body).unwrap().len(), 8);
+
+            let greedy = SearchRequest { top_k: 2, ef_search: MAX_SEARCH_EF + 1 }.encode(&query);
+            stream.write_all(&frame(OP_SEARCH, 80, &greedy)).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_ERR);
+            assert_eq!(body, b"ef_search out of range");
+        });
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_acked_upserts_are_searchable() {
+        let dir = test_dir("read_your_writes");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            let mut packet = Vec::new();
+            for id in 0..4u64 {
+                let payload = UpsertRequest { id: 100 + id }.encode(&basis_vector(id as usize));
+                packet.extend_from_slice(&frame(OP_UPSERT, id, &payload));
+            }
+            stream.write_all(&packet).unwrap();
+            for _ in 0..4 {
+                let (header, _) = read_response(stream);
+                assert_eq!(header.status, STATUS_OK);
+                assert_eq!(header.opcode, OP_UPSERT);
+            }
+
+            // No restart: the ACKed vectors must already be in the index
+            let payload = SearchRequest { top_k: 1, ef_search: 0 }.encode(&basis_vector(2));
+            stream.write_all(&frame(OP_SEARCH, 9, &payload)).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_OK);
+            let hits = SearchHit::decode_all(&body).unwrap();
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].id, 102);
+        });
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_pipelined_acks_echo_request_ids() {
+        let dir = test_dir("pipelined_ids");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            // Out-of-order request IDs, one bad record and a search in the middle of the pipeline
+            let mut packet = Vec::new();
+            packet.extend_from_slice(&frame(OP_UPSERT, 42, &UpsertRequest { id: 1 }.encode(&basis_vector(1))));
+            packet.extend_from_slice(&frame(OP_UPSERT, 7, &UpsertRequest { id: 2 }.encode(&[1.0; 3])));
+            packet.extend_from_slice(&frame(OP_SEARCH, 1000, &SearchRequest { top_k: 1, ef_search: 0 }.encode(&basis_vector(1))));
+            packet.extend_from_slice(&frame(OP_UPSERT, 3, &UpsertRequest { id: 3 }.encode(&basis_vector(3))));
+            stream.write_all(&packet).unwrap();
+
+            let mut upsert_acks = Vec::new();
+            let mut search_seen = false;
+            for _ in 0..4 {
+                let (header, _) = read_response(stream);
+                if header.opcode == OP_SEARCH {
+                    assert_eq!(header.request_id, 1000);
+                    search_seen = true;
+                } else {
+                    upsert_acks.push((header.request_id, header.status));
+                }
+            }
+            assert!(search_seen);
+            // The wrongly sized vector is refused at ingest, ahead of the group-commit ACKs
+            assert_eq!(upsert_acks, vec![(7, STATUS_DIMENSION_MISMATCH), (42, STATUS_OK), (3, STATUS_OK)]);
+        });
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_delete_survives_restart() {
+        let dir = test_dir("delete_replay");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            // One op per batch: each is ACKed before the next is sent
+            for id in 0..3u64 {
+                let payload = UpsertRequest { id }.encode(&basis_vector(id as usize));
+                stream.write_all(&frame(OP_UPSERT, id, &payload)).unwrap();
+                assert_eq!(read_response(stream).0.status, STATUS_OK);
+            }
+            stream.write_all(&frame(OP_DELETE, 10, &DeleteRequest { id: 1 }.encode())).unwrap();
+            let (header, _) = read_response(stream);
+            assert_eq!((header.opcode, header.request_id, header.status), (OP_DELETE, 10, STATUS_OK));
+
+            let payload = SearchRequest { top_k: 3, ef_search: 0 }.encode(&basis_vector(1));
+            stream.write_all(&frame(OP_SEARCH, 11, &payload)).unwrap();
+            let hits = SearchHit::decode_all(&read_response(stream).1).unwrap();
+            assert_eq!(hits.len(), 2);
+            assert!(hits.iter().all(|h| h.id != 1));
+        });
+        drop(reactor);
+
+        // The delete is in the WAL, so replay must not resurrect the vector
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        let results = default_index(&mut reactor).search(&basis_vector(1), SearchParams::new(3));
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|&(id, _)| id != 1));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_filtered_search_survives_restart() {
+        let dir = test_dir("filtered_search");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        let eu = Filter::eq("region", "eu");
+        let eu_query = SearchRequest { top_k: 2, ef_search: 0 }.encode_with_filter(&basis_vector(0), &eu);
+        run_with_client(&mut reactor, port, move |stream| {
+            for id in 0..6u64 {
+                let region = if id % 2 == 0 { "us" } else { "eu" };
+                let tags = Metadata::new().keyword("region", region);
+                let payload = UpsertRequest { id }.encode_with_metadata(&basis_vector(id as usize), &tags);
+                stream.write_all(&frame(OP_UPSERT, id, &payload)).unwrap();
+                assert_eq!(read_response(stream).0.status, STATUS_OK);
+            }
+
+            stream.write_all(&frame(OP_SEARCH, 10, &eu_query)).unwrap();
+            let hits = SearchHit::decode_all(&read_response(stream).1).unwrap();
+            assert_eq!(hits.len(), 2);
+            assert!(hits.iter().all(|h| h.id % 2 == 1));
+
+            // v2 GET answers carry the metadata; v1 answers stay vector-only
+            stream.write_all(&v2(OP_GET, 12, 0, &GetRequest { id: 3 }.encode())).unwrap();
+            let stored = StoredVector::parse(&read_response(stream).1, VBP_VERSION_2).unwrap();
+            assert_eq!((stored.vector, stored.metadata), (basis_vector(3), Some(Metadata::new().keyword("region", "eu"))));
+            stream.write_all(&frame(OP_GET, 13, &GetRequest { id: 3 }.encode())).unwrap();
+            assert_eq!(read_response(stream).1.len(), 128 * 4);
+
+            // A malformed metadata block never reaches the WAL
+            let mut bad = UpsertRequest { id: 9 }.encode(&basis_vector(9));
+            bad.extend_from_slice(&[1, 0, 0, 0, 9]);
+            stream.write_all(&frame(OP_UPSERT, 11, &bad)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_ERR);
+        });
+        drop(reactor);
+
+        // Metadata is logged with the vector, so replay restores it
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        let results = default_index(&mut reactor).search(&basis_vector(0), SearchParams::new(6).with_filter(Some(&eu)));
+        let mut ids: Vec<u64> = results.iter().map(|&(id, _)| id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 3, 5]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_restart_restores_snapshot_and_replays_tail() {
+        let dir = test_dir("snapshot_tail");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        // Every ACKed upsert is its own 4KB batch after the header page: snapshot after the second one
+        reactor.set_snapshot_interval(3 * 4096);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            for id in 0..3u64 {
+                let payload = UpsertRequest { id }.encode(&basis_vector(id as usize));
+                stream.write_all(&frame(OP_UPSERT, id, &payload)).unwrap();
+                assert_eq!(read_response(stream).0.status, STATUS_OK);
+            }
+        });
+        drop(reactor);
+
+        // The WAL opens with a header page, then one page per batch
+        let snap = crate::storage::snapshot::read(&crate::storage::snapshot::path(&dir, 0)).unwrap().unwrap();
+        assert_eq!((snap.wal_offset, snap.next_seq), (3 * 4096, 3));
+
+        // Corrupt the first WAL record: a full replay would truncate the log right there,
+        // so recovering every vector proves the snapshot was used and only the tail replayed
+        let wal_path = format!("{}/shard_0.wal", dir);
+        let mut wal = std::fs::read(&wal_path).unwrap();
+        wal[4096 + 100] ^= 0xFF;
+        std::fs::write(&wal_path, &wal).unwrap();
+
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        let index = default_index(&mut reactor);
+        for id in 0..3u64 {
+            let results = index.search(&basis_vector(id as usize), SearchParams::new(1));
+            assert_eq!(results[0].0, id);
+        }
+        assert_eq!(std::fs::metadata(&wal_path).unwrap().len(), 4 * 4096);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_boot_upgrades_a_pre_checksum_wal() {
+        let dir = test_dir("legacy_wal");
+        // The baseline format: bare VBP frames packed into batches zero-padded to 4KB
+        let mut legacy = Vec::new();
+        for batch in [0..2u64, 2..5] {
+            for id in batch {
+                let payload = UpsertRequest { id }.encode(&basis_vector(id as usize));
+                legacy.extend_from_slice(&frame(OP_UPSERT, id, &payload));
+            }
+            legacy.resize((legacy.len() + 4095) & !4095, 0);
+        }
+        let wal_path = format!("{}/shard_0.wal", dir);
+        std::fs::write(&wal_path, &legacy).unwrap();
+
+        for _ in 0..2 {
+            let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+            let index = default_index(&mut reactor);
+            for id in 0..5u64 {
+                assert_eq!(index.search(&basis_vector(id as usize), SearchParams::new(1))[0].0, id);
+            }
+        }
+        assert_eq!(std::fs::read(format!("{}.legacy", wal_path)).unwrap(), legacy);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_restart_replays_every_record_of_batched_upserts() {
+        let dir = test_dir("batched_replay");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 2000, 128, &dir);
+        reactor.listen(port).unwrap();
+        let vector_of = |id: u64| {
+            let mut v = basis_vector(id as usize % 128);
+            v[0] += id as f32;
+            v
         };
-        
-        self.prepare_response_buffer(idx, CMD_UPSERT, STATUS_OK, req_id);
-        self.submit_write(idx, None);
-        
-        // CRITICAL: Do NOT drop lease here. 
-        // Logic flows to handle_write_complete.
+
+        // Pipeline every upsert before reading an ACK, so group commit packs many records per batch
+        run_with_client(&mut reactor, port, move |stream| {
+            let mut burst = Vec::new();
+            for id in 0..1000u64 {
+                burst.extend_from_slice(&frame(OP_UPSERT, id, &UpsertRequest { id }.encode(&vector_of(id))));
+            }
+            stream.write_all(&burst).unwrap();
+            for _ in 0..1000 {
+                assert_eq!(read_response(stream).0.status, STATUS_OK);
+            }
+        });
+        drop(reactor);
+
+        // Far fewer pages than records: the log really holds multi-record batches
+        let wal_len = std::fs::metadata(format!("{}/shard_0.wal", dir)).unwrap().len();
+        assert!(wal_len < 500 * 4096, "WAL is {} bytes", wal_len);
+
+        let mut reactor = ShardReactor::new(0, 64, 2000, 128, &dir);
+        let index = default_index(&mut reactor);
+        let mut stored = vec![0.0f32; 128];
+        for id in 0..1000u64 {
+            assert!(index.get(id, &mut stored), "id {} lost on restart", id);
+            assert_eq!(stored, vector_of(id));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
-    
-    fn handle_write_complete(&mut self, idx: usize, _res: usize) {
-        self.write_in_flight[idx] = false;
 
-        // Result is handled by handle_write_complete and process_ingress for next steps
-        self.submit_write(idx, None);
-        
-        if self.pending_ops[idx] > 0 {
-            let acks_in_write = _res / 16;
-            if acks_in_write > self.pending_ops[idx] {
-                self.pending_ops[idx] = 0;
-            } else {
-                self.pending_ops[idx] -= acks_in_write;
+    #[test]
+    fn test_checkpoint_deletes_segments_covered_by_snapshot() {
+        let dir = test_dir("wal_segments");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        // A header page and two 4KB batches per segment, one snapshot after the third batch
+        reactor.set_wal_segment_size(3 * 4096);
+        reactor.set_snapshot_interval(5 * 4096);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            for id in 0..6u64 {
+                let payload = UpsertRequest { id }.encode(&basis_vector(id as usize));
+                stream.write_all(&frame(OP_UPSERT, id, &payload)).unwrap();
+                assert_eq!(read_response(stream).0.status, STATUS_OK);
             }
+        });
+        drop(reactor);
+
+        // The segment at 0 ends before the snapshot at 20480; the one holding it is kept
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        assert_eq!(crate::storage::wal::list_segments(&dir, 0).unwrap(), vec![3 * 4096, 6 * 4096]);
+        let index = default_index(&mut reactor);
+        for id in 0..6u64 {
+            let results = index.search(&basis_vector(id as usize), SearchParams::new(1));
+            assert_eq!(results[0].0, id);
         }
+        drop(reactor);
 
-        // Phase 7.3.1: Delegate all buffer sovereignty to process_ingress
-        self.process_ingress(idx);
+        // The trimmed log still boots
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        assert_eq!(default_index(&mut reactor).search(&basis_vector(5), SearchParams::new(1))[0].0, 5);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_get_returns_stored_vector() {
+        let dir = test_dir("get_by_id");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            let mut vector = basis_vector(5);
+            vector[9] = -0.25;
+            stream.write_all(&frame(OP_UPSERT, 1, &UpsertRequest { id: 77 }.encode(&vector))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_OK);
+
+            stream.write_all(&frame(OP_GET, 2, &GetRequest { id: 77 }.encode())).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!((header.opcode, header.request_id, header.status), (OP_GET, 2, STATUS_OK));
+            let mut fetched = vec![0.0f32; 128];
+            assert_eq!(vortex_rpc::decode_f32s(&body, &mut fetched), 128);
+            assert_eq!(fetched, vector);
+
+            stream.write_all(&frame(OP_GET, 3, &GetRequest { id: 78 }.encode())).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!((header.request_id, header.status), (3, STATUS_NOT_FOUND));
+            assert!(body.is_empty());
+
+            stream.write_all(&frame(OP_GET, 4, &[0u8; 3])).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_ERR);
+        });
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_configured_dimension_is_enforced_at_ingest() {
+        let dir = test_dir("dimension_384");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 384, &dir);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            let vector: Vec<f32> = (0..384).map(|i| i as f32 / 384.0).collect();
+            stream.write_all(&frame(OP_UPSERT, 1, &UpsertRequest { id: 5 }.encode(&vector))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_OK);
+
+            stream.write_all(&frame(OP_UPSERT, 2, &UpsertRequest { id: 6 }.encode(&basis_vector(0)))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_DIMENSION_MISMATCH);
+
+            let payload = SearchRequest { top_k: 1, ef_search: 0 }.encode(&basis_vector(0));
+            stream.write_all(&frame(OP_SEARCH, 3, &payload)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_DIMENSION_MISMATCH);
+
+            stream.write_all(&frame(OP_GET, 4, &GetRequest { id: 5 }.encode())).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_OK);
+            assert_eq!(body.len(), 384 * 4);
+        });
+        drop(reactor);
+
+        // The data directory now belongs to 384-dimensional vectors
+        assert!(Manifest::open(&dir, 128, Metric::default()).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_hello_negotiates_the_connection_version() {
+        let dir = test_dir("hello");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, |stream| {
+            // Versions outside the supported range are refused, framing intact
+            let get = GetRequest { id: 1 }.encode();
+            stream.write_all(&RequestHeader::new(VBP_VERSION_MAX + 1, OP_GET, get.len() as u32, 1).to_bytes()).unwrap();
+            stream.write_all(&get).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!((header.status, header.request_id), (STATUS_UNSUPPORTED_VERSION, 1));
+            assert_eq!(body, b"Unsupported protocol version");
+
+            // The handshake reports the limits and pins the connection to v1
+            let offer = HelloRequest { min_version: 0, max_version: 1 }.encode();
+            stream.write_all(&frame(OP_HELLO, 2, &offer)).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_OK);
+            let hello = HelloResponse::parse(&body).unwrap();
+            assert_eq!((hello.version, hello.max_frame_size, hello.dimension), (VBP_VERSION_1, PAGE_BYTES as u32, 128));
+            assert!(SUPPORTED_OPCODES.iter().all(|&op| hello.supports(op)));
+
+            stream.write_all(&v2(OP_GET, 3, 0, &get)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_UNSUPPORTED_VERSION);
+            stream.write_all(&frame(OP_GET, 4, &get)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_NOT_FOUND);
+
+            // No common version leaves the connection as it was
+            let future = HelloRequest { min_version: VBP_VERSION_MAX + 1, max_version: VBP_VERSION_MAX + 2 }.encode();
+            stream.write_all(&frame(OP_HELLO, 5, &future)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_UNSUPPORTED_VERSION);
+            stream.write_all(&frame(OP_HELLO, 6, &HelloRequest::current().encode())).unwrap();
+            assert_eq!(HelloResponse::parse(&read_response(stream).1).unwrap().version, VBP_VERSION_MAX);
+            stream.write_all(&v2(OP_GET, 7, 0, &get)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_NOT_FOUND);
+        });
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_failures_carry_a_status_and_message() {
+        let dir = test_dir("status_codes");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 2, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, move |stream| {
+            for id in 0..2u64 {
+                stream.write_all(&frame(OP_UPSERT, id, &UpsertRequest { id }.encode(&basis_vector(id as usize)))).unwrap();
+                assert_eq!(read_response(stream).0.status, STATUS_OK);
+            }
+            // A full collection still takes replacements, but refuses new IDs
+            stream.write_all(&frame(OP_UPSERT, 2, &UpsertRequest { id: 1 }.encode(&basis_vector(4)))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_OK);
+            stream.write_all(&frame(OP_UPSERT, 3, &UpsertRequest { id: 9 }.encode(&basis_vector(9)))).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!((header.status, header.request_id), (vortex_rpc::STATUS_CAPACITY_FULL, 3));
+            assert_eq!(body, b"Collection is at max_elements");
+
+            stream.write_all(&frame(42, 4, &[])).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!((header.status, header.opcode), (STATUS_UNKNOWN_OPCODE, 42));
+            assert_eq!(body, b"Unknown opcode");
+
+            // A malformed DELETE is refused at ingress and never reaches the WAL
+            stream.write_all(&frame(OP_DELETE, 5, &[1, 2, 3])).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!((header.status, header.request_id), (STATUS_ERR, 5));
+            assert_eq!(body, b"Delete payload must be exactly one vector ID");
+
+            // Lost framing is answered before the connection closes
+            let mut garbage = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            garbage.write_all(&[0xAB; 16]).unwrap();
+            let (header, body) = read_response(&mut garbage);
+            assert_eq!(header.status, STATUS_BAD_MAGIC);
+            assert!(!body.is_empty());
+            assert_eq!(garbage.read(&mut [0u8; 1]).unwrap(), 0);
+
+            let mut oversized = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            oversized.write_all(&RequestHeader::new(1, OP_UPSERT, PAGE_BYTES as u32, 5).to_bytes()).unwrap();
+            let (header, _) = read_response(&mut oversized);
+            assert_eq!((header.status, header.request_id), (STATUS_FRAME_TOO_LARGE, 5));
+            assert_eq!(oversized.read(&mut [0u8; 1]).unwrap(), 0);
+
+            // The first connection was never disturbed
+            stream.write_all(&frame(OP_GET, 6, &GetRequest { id: 1 }.encode())).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_OK);
+        });
+        drop(reactor);
+
+        // Replay refuses the same records, so the index comes back identical
+        let mut reactor = ShardReactor::new(0, 64, 2, 128, &dir);
+        let mut out = vec![0.0f32; 128];
+        assert!(!default_index(&mut reactor).get(9, &mut out));
+        assert!(default_index(&mut reactor).get(1, &mut out));
+        assert_eq!(out, basis_vector(4));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_collections_are_isolated_and_survive_restart() {
+        let dir = test_dir("collections");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        let image_id = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let image_id_out = image_id.clone();
+
+        run_with_client(&mut reactor, port, move |stream| {
+            let spec = CollectionSpec { dimension: 4, max_elements: 0, m: 8, ef_construction: 0, metric: METRIC_INNER_PRODUCT, reserved: [0; 3] };
+            stream.write_all(&frame(OP_CREATE_COLLECTION, 1, &CreateCollectionRequest::encode(&spec, "image"))).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_OK);
+            let created = CollectionInfo::decode_all(&body).unwrap().remove(0);
+            assert_eq!((created.name.as_str(), created.spec.dimension, created.spec.m), ("image", 4, 8));
+            image_id_out.store(created.id, Ordering::SeqCst);
+
+            stream.write_all(&frame(OP_CREATE_COLLECTION, 2, &CreateCollectionRequest::encode(&spec, "image"))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_ERR);
+
+            // Same external ID in both collections, different dimensions
+            stream.write_all(&v2(OP_UPSERT, 3, created.id, &UpsertRequest { id: 1 }.encode(&[0.0, 0.0, 1.0, 0.0]))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_OK);
+            stream.write_all(&frame(OP_UPSERT, 4, &UpsertRequest { id: 1 }.encode(&basis_vector(0)))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_OK);
+            stream.write_all(&v2(OP_UPSERT, 5, created.id, &UpsertRequest { id: 2 }.encode(&basis_vector(0)))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_DIMENSION_MISMATCH);
+            stream.write_all(&v2(OP_UPSERT, 6, 999, &UpsertRequest { id: 2 }.encode(&[1.0; 4]))).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_UNKNOWN_COLLECTION);
+
+            let query = SearchRequest { top_k: 5, ef_search: 0 }.encode(&[0.0, 0.0, 1.0, 0.0]);
+            stream.write_all(&v2(OP_SEARCH, 7, created.id, &query)).unwrap();
+            let hits = SearchHit::decode_all(&read_response(stream).1).unwrap();
+            assert_eq!(hits.iter().map(|h| h.id).collect::<Vec<_>>(), vec![1]);
+
+            stream.write_all(&frame(OP_LIST_COLLECTIONS, 8, &[])).unwrap();
+            let listed = CollectionInfo::decode_all(&read_response(stream).1).unwrap();
+            assert_eq!(listed.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["default", "image"]);
+        });
+        drop(reactor);
+
+        // Restart: the catalog and the collection's own WAL stream come back
+        let image_id = image_id.load(Ordering::SeqCst);
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+        run_with_client(&mut reactor, port, move |stream| {
+            stream.write_all(&v2(OP_GET, 1, image_id, &GetRequest { id: 1 }.encode())).unwrap();
+            let (header, body) = read_response(stream);
+            assert_eq!(header.status, STATUS_OK);
+            assert_eq!(StoredVector::parse(&body, VBP_VERSION_2).unwrap().vector, vec![0.0, 0.0, 1.0, 0.0]);
+
+            let drop_payload = CollectionRef { collection_id: image_id, reserved: 0 }.encode(&[]);
+            stream.write_all(&frame(OP_DROP_COLLECTION, 2, &drop_payload)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_OK);
+            stream.write_all(&v2(OP_DELETE, 3, image_id, &DeleteRequest { id: 1 }.encode())).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_UNKNOWN_COLLECTION);
+
+            let default_drop = CollectionRef { collection_id: 0, reserved: 0 }.encode(&[]);
+            stream.write_all(&frame(OP_DROP_COLLECTION, 4, &default_drop)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_ERR);
+        });
+        assert!(!std::path::Path::new(&format!("{}/collection_{}", dir, image_id)).exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_retiring_a_connection_cancels_its_pending_read() {
+        let dir = test_dir("retire_read");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(port).unwrap();
+
+        let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let mut idx = None;
+        for _ in 0..50 {
+            reactor.run_tick();
+            idx = reactor.read_in_flight.iter().position(|&reading| reading);
+            if idx.is_some() {
+                break;
+            }
+        }
+        let idx = idx.expect("connection was not accepted");
+        // One more tick hands the read to the kernel
+        reactor.run_tick();
+        assert!(reactor.read_in_flight[idx]);
+
+        // The client stays silent: only the cancellation can complete the read
+        reactor.retire_connection(idx);
+        for _ in 0..50 {
+            if reactor.leases[idx].is_none() {
+                break;
+            }
+            reactor.run_tick();
+        }
+        assert!(reactor.leases[idx].is_none());
+        assert!(!reactor.read_in_flight[idx]);
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(client.read(&mut [0u8; 1]).unwrap(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_connections_beyond_the_slab_are_refused_busy() {
+        let dir = test_dir("busy");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.set_max_connections(2);
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, move |stream| {
+            let get = GetRequest { id: 1 }.encode();
+            stream.write_all(&frame(OP_GET, 1, &get)).unwrap();
+            assert_eq!(read_response(stream).0.status, STATUS_NOT_FOUND);
+            let mut second = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            second.write_all(&frame(OP_GET, 2, &get)).unwrap();
+            assert_eq!(read_response(&mut second).0.status, STATUS_NOT_FOUND);
+
+            // A third client is told to come back later, then closed
+            let mut third = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            let (header, body) = read_response(&mut third);
+            assert_eq!((header.status, header.opcode, header.request_id), (STATUS_BUSY, 0, 0));
+            assert!(vortex_rpc::is_retryable(header.status));
+            assert!(!body.is_empty());
+            assert_eq!(third.read(&mut [0u8; 1]).unwrap(), 0);
+
+            // A closed connection hands its slot to the next client
+            drop(second);
+            let mut served = false;
+            for _ in 0..50 {
+                let mut retry = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                retry.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+                if retry.read(&mut [0u8; 1]).is_ok() {
+                    continue;
+                }
+                retry.set_read_timeout(None).unwrap();
+                retry.write_all(&frame(OP_GET, 3, &get)).unwrap();
+                served = read_response(&mut retry).0.status == STATUS_NOT_FOUND;
+                break;
+            }
+            assert!(served);
+        });
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_idle_and_stalled_connections_are_reaped() {
+        let dir = test_dir("reaping");
+        let port = free_port();
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.set_idle_timeout(Some(Duration::from_millis(300)));
+        reactor.set_read_deadline(Some(Duration::from_millis(100)));
+        reactor.listen(port).unwrap();
+
+        run_with_client(&mut reactor, port, move |stream| {
+            // Steady traffic outlives the idle timeout; silence does not
+            let get = GetRequest { id: 1 }.encode();
+            for id in 0..6 {
+                stream.write_all(&frame(OP_GET, id, &get)).unwrap();
+                assert_eq!(read_response(stream).0.status, STATUS_NOT_FOUND);
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            let quiet = Instant::now();
+            assert_eq!(stream.read(&mut [0u8; 1]).unwrap(), 0);
+            assert!(quiet.elapsed() >= Duration::from_millis(100));
+
+            // A frame dribbled out with gaps under the idle timeout still misses the read deadline
+            let mut slow = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            let cut_off = frame(OP_GET, 7, &get).iter().any(|byte| {
+                std::thread::sleep(Duration::from_millis(60));
+                slow.write_all(std::slice::from_ref(byte)).is_err()
+            });
+            assert!(cut_off);
+
+            // A live connection again, so the helper's EOF wakes the final tick
+            *stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        });
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_idle_shard_ticks_and_wakes_on_demand() {
+        let dir = test_dir("ticks");
+        let mut reactor = ShardReactor::new(0, 64, 100, 128, &dir);
+        reactor.listen(free_port()).unwrap();
+
+        // No traffic at all: the tick timer still ends every wait
+        reactor.set_tick_interval(Duration::from_millis(20));
+        for _ in 0..5 {
+            assert!(reactor.run_tick());
+        }
+
+        // With a long interval, a wake from another thread ends the wait instead
+        reactor.set_tick_interval(Duration::from_secs(60));
+        let waker = reactor.waker();
+        let start = Instant::now();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            waker.wake();
+        });
+        reactor.shutdown();
+        assert!(!reactor.run_tick());
+        assert!(start.elapsed() < Duration::from_secs(30));
+        handle.join().unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
@@ -1,4 +1,6 @@
 use vortex_io::memory::BufferPage;
+use vortex_rpc::RequestHeader;
+use crate::storage::wal::{self, RECORD_HEADER_SIZE};
 use std::ptr;
 
 /// High-Performance WAL Batch Accumulator (Mechanical Sympathy BP)
@@ -6,38 +8,70 @@ use std::ptr;
 /// # Purpose
 /// Aggregates multiple small vector updates into a single 16KB hardware sector
 /// to bypass the physical IOPS limit of synchronous disk writes.
+///
+/// Every request is stored as a WAL record (see `storage::wal`): a 16-byte record
+/// header, the VBP frame, then zeros up to the next 8-byte boundary.
 pub struct BatchAccumulator {
     buffer: BufferPage,
     cursor: usize,
-    pub tags: Vec<u64>,
+    sealed_len: usize,
+    /// One tag per appended record, in append order.
+    pub tags: Vec<BatchTag>,
     capacity: usize,
 }
 
+/// Size of one group-commit buffer: 256KB (64 Pages). No WAL record is larger.
+pub const BATCH_CAPACITY: usize = 262144;
+
+/// Identifies who is waiting for the ACK of one batched record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchTag {
+    /// Connection slot that submitted the record.
+    pub slot: usize,
+    /// Client correlation ID echoed back in the ACK.
+    pub request_id: u64,
+}
+
+/// Returned by `BatchAccumulator::try_add` when the record does not fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchFull;
+
+impl Default for BatchAccumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BatchAccumulator {
     pub fn new() -> Self {
-        let capacity = 262144; // 256KB (64 Pages)
+        let capacity = BATCH_CAPACITY;
         let (buffer, _) = BufferPage::new(capacity);
         Self {
             buffer,
             cursor: 0,
+            sealed_len: 0,
             tags: Vec::with_capacity(32),
             capacity,
         }
     }
 
-    /// Appends data to the batch. Returns Err(()) if capacity is exceeded.
-    pub fn try_add(&mut self, data: &[u8], tag: u64) -> Result<(), ()> {
-        if self.cursor + data.len() > self.capacity {
-            return Err(());
+    /// Appends one VBP frame to the batch as a WAL record. Returns `Err(BatchFull)` if capacity is exceeded.
+    pub fn try_add(&mut self, data: &[u8], tag: BatchTag) -> Result<(), BatchFull> {
+        let record_len = wal::record_size(data.len());
+        if self.cursor + record_len > self.capacity {
+            return Err(BatchFull);
         }
 
         // SAFETY: Bounds checked above. buffer is mlocked and aligned.
+        // The CRC and sequence number are stamped by `prepare_flush`.
         unsafe {
             let dst = self.buffer.as_slice_mut().as_mut_ptr().add(self.cursor);
-            ptr::copy_nonoverlapping(data.as_ptr(), dst, data.len());
+            ptr::write_bytes(dst, 0, record_len);
+            ptr::copy_nonoverlapping((data.len() as u32).to_le_bytes().as_ptr(), dst, 4);
+            ptr::copy_nonoverlapping(data.as_ptr(), dst.add(RECORD_HEADER_SIZE), data.len());
         }
 
-        self.cursor += data.len();
+        self.cursor += record_len;
         self.tags.push(tag);
         Ok(())
     }
@@ -48,15 +82,31 @@ impl BatchAccumulator {
     }
 
     /// Preparces the buffer for O_DIRECT flush.
+    /// Numbers the records from `first_seq` and seals each one with its CRC32C.
     /// Returns: (Pointer, Sector-Aligned Length)
     /// 
     /// # Safety
     /// Zeroes the tail to next 4KB boundary to satisfy mechanical sympathy.
-    pub fn prepare_flush(&mut self) -> (*const u8, usize) {
+    pub fn prepare_flush(&mut self, first_seq: u64) -> (*const u8, usize) {
         if self.cursor == 0 {
             return (ptr::null(), 0);
         }
 
+        // 0. Stamp sequence numbers and checksums (Phase 4 integrity)
+        let cursor = self.cursor;
+        let data = &mut self.buffer.as_slice_mut()[..cursor];
+        let mut offset = 0;
+        let mut seq = first_seq;
+        while offset < cursor {
+            let len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap_or([0; 4]));
+            let frame_start = offset + RECORD_HEADER_SIZE;
+            let crc = wal::record_crc(len, seq, &data[frame_start..frame_start + len as usize]);
+            data[offset + 4..offset + 8].copy_from_slice(&crc.to_le_bytes());
+            data[offset + 8..offset + 16].copy_from_slice(&seq.to_le_bytes());
+            offset += wal::record_size(len as usize);
+            seq += 1;
+        }
+
         // 1. Sector Alignment (Rule #9 Scaling)
         let aligned_len = (self.cursor + 4095) & !4095;
         
@@ -71,13 +121,27 @@ impl BatchAccumulator {
         let ptr = self.buffer.as_ptr();
         let len = aligned_len;
         
-        // Reset cursor for next usage (if reused) or tracking
+        // Reset cursor for next usage (if reused) or tracking.
+        // The framed length is kept so the committed records can be replayed into the index.
+        self.sealed_len = self.cursor;
         self.cursor = 0;
         
         (ptr, len)
     }
 
-    pub fn take_tags(&mut self) -> Vec<u64> {
+    /// Sector-aligned length written by the last `prepare_flush`.
+    pub fn flush_len(&self) -> usize {
+        (self.sealed_len + 4095) & !4095
+    }
+
+    /// Iterates the framed requests sealed by the last `prepare_flush`, in arrival order.
+    pub fn records(&self) -> BatchRecords<'_> {
+        // SAFETY: `sealed_len` never exceeds capacity and the buffer outlives the borrow.
+        let data = unsafe { std::slice::from_raw_parts(self.buffer.as_ptr(), self.sealed_len) };
+        BatchRecords { data, cursor: 0 }
+    }
+
+    pub fn take_tags(&mut self) -> Vec<BatchTag> {
         std::mem::take(&mut self.tags)
     }
 
@@ -87,6 +151,36 @@ impl BatchAccumulator {
 
     pub fn reset(&mut self) {
         self.cursor = 0;
+        self.sealed_len = 0;
         self.tags.clear();
     }
 }
+
+/// Iterator over the `(header, payload)` frames of a sealed batch, without their WAL record headers.
+pub struct BatchRecords<'a> {
+    data: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> Iterator for BatchRecords<'a> {
+    type Item = (RequestHeader, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header_size = std::mem::size_of::<RequestHeader>();
+        let frame_start = self.cursor + RECORD_HEADER_SIZE;
+        if frame_start + header_size > self.data.len() {
+            return None;
+        }
+        let len = u32::from_le_bytes(self.data[self.cursor..self.cursor + 4].try_into().unwrap_or([0; 4])) as usize;
+
+        let header = RequestHeader::parse(&self.data[frame_start..]).ok()?;
+        let payload_start = frame_start + header_size;
+        let payload_end = payload_start + header.payload_len as usize;
+        if payload_end != frame_start + len || payload_end > self.data.len() {
+            return None;
+        }
+
+        self.cursor += wal::record_size(len);
+        Some((header, &self.data[payload_start..payload_end]))
+    }
+}
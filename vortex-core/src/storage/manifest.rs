@@ -0,0 +1,149 @@
+use crate::index::distance::Metric;
+use log::info;
+use std::io::{Error, ErrorKind, Write};
+use std::path::Path;
+
+/// Name of the manifest file inside the storage directory.
+pub const MANIFEST_FILE: &str = "vortex.manifest";
+
+/// Dimension of every vector written before the manifest existed.
+const LEGACY_DIMENSION: usize = 128;
+
+/// Persistent, immutable properties of a storage directory.
+///
+/// # Purpose
+/// WAL records carry raw vectors without describing their shape. The manifest pins
+/// the dimension and metric the data was written with, so a restart with different values
+/// is refused instead of silently skipping every record or ranking by the wrong distance.
+///
+/// # Format
+/// Plain `key=value` lines. Unknown keys are ignored; a missing `metric` means inner product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Manifest {
+    pub dimension: usize,
+    pub metric: Metric,
+}
+
+impl Manifest {
+    /// Opens the manifest in `base_path`, creating it on first boot.
+    ///
+    /// # Errors
+    /// Returns `ErrorKind::InvalidInput` if the directory already holds data written
+    /// with a different dimension or metric, or `ErrorKind::InvalidData` if the manifest is unreadable.
+    pub fn open(base_path: &str, dimension: usize, metric: Metric) -> std::io::Result<Self> {
+        let path = Path::new(base_path).join(MANIFEST_FILE);
+
+        let stored = if path.exists() {
+            Self::parse(&std::fs::read_to_string(&path)?)?
+        } else if Self::has_wal_data(base_path)? {
+            // Pre-manifest data directory: persist what it was implicitly written with
+            let legacy = Self { dimension: LEGACY_DIMENSION, metric: Metric::InnerProduct };
+            legacy.write(&path)?;
+            legacy
+        } else {
+            let fresh = Self { dimension, metric };
+            std::fs::create_dir_all(base_path)?;
+            fresh.write(&path)?;
+            info!("Storage manifest created at {:?} (Dim: {}, Metric: {})", path, dimension, metric);
+            fresh
+        };
+
+        if stored.dimension != dimension {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} holds {}-dimensional vectors, refusing to start with dimension {}", base_path, stored.dimension, dimension),
+            ));
+        }
+        if stored.metric != metric {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} was indexed with metric {}, refusing to start with metric {}", base_path, stored.metric, metric),
+            ));
+        }
+        Ok(stored)
+    }
+
+    fn parse(contents: &str) -> std::io::Result<Self> {
+        let mut dimension = None;
+        let mut metric = Some(Metric::InnerProduct);
+        for line in contents.lines() {
+            match line.split_once('=') {
+                Some(("dimension", value)) => dimension = value.trim().parse::<usize>().ok(),
+                Some(("metric", value)) => metric = value.trim().parse::<Metric>().ok(),
+                _ => {}
+            }
+        }
+        match (dimension, metric) {
+            (Some(dimension), Some(metric)) if dimension > 0 => Ok(Self { dimension, metric }),
+            (_, None) => Err(Error::new(ErrorKind::InvalidData, "Manifest holds an unknown metric")),
+            _ => Err(Error::new(ErrorKind::InvalidData, "Manifest is missing a valid dimension")),
+        }
+    }
+
+    fn write(&self, path: &Path) -> std::io::Result<()> {
+        write_atomic(path, &format!("dimension={}\nmetric={}\n", self.dimension, self.metric))
+    }
+
+    fn has_wal_data(base_path: &str) -> std::io::Result<bool> {
+        let entries = match std::fs::read_dir(base_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let is_wal = entry.file_name().to_string_lossy().ends_with(".wal");
+            if is_wal && entry.metadata()?.len() > 0 {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Writes `contents` through a temporary file, an fsync and a rename, so a crash never
+/// leaves a half-written control file behind. The directory is synced so the rename
+/// itself survives a power loss.
+pub(crate) fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+    if let Some(dir) = path.parent() {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_refuses_dimension_change() {
+        let dir = std::env::temp_dir().join(format!("vortex_manifest_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let dir = dir.to_str().unwrap();
+
+        assert_eq!(Manifest::open(dir, 384, Metric::L2).unwrap().dimension, 384);
+        assert_eq!(Manifest::open(dir, 384, Metric::L2).unwrap().metric, Metric::L2);
+        assert_eq!(Manifest::open(dir, 768, Metric::L2).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(Manifest::open(dir, 384, Metric::Cosine).unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        // A pre-manifest directory with WAL data was written at the legacy dimension
+        std::fs::remove_file(Path::new(dir).join(MANIFEST_FILE)).unwrap();
+        std::fs::write(Path::new(dir).join("shard_0.wal"), [1u8; 16]).unwrap();
+        assert!(Manifest::open(dir, 384, Metric::InnerProduct).is_err());
+        assert_eq!(Manifest::open(dir, 128, Metric::InnerProduct).unwrap().dimension, 128);
+
+        // Manifests written before metrics existed rank by inner product
+        std::fs::write(Path::new(dir).join(MANIFEST_FILE), "dimension=128\n").unwrap();
+        assert_eq!(Manifest::open(dir, 128, Metric::InnerProduct).unwrap().metric, Metric::InnerProduct);
+        assert!(Manifest::open(dir, 128, Metric::L2).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod wal;
 pub mod batch;
+pub mod manifest;
+pub mod snapshot;
@@ -1,25 +1,270 @@
 use vortex_io::storage::DirectFile;
-use log::info;
+use vortex_io::memory::BufferPage;
+use crate::storage::batch::{BatchAccumulator, BATCH_CAPACITY};
+use log::{error, info, warn};
 use io_uring;
 
 /// Manages the Write-Ahead Log (WAL) for a specific Shard.
-/// 
+///
 /// # Purpose
 /// Ensures ACID durability by appending mutations to a disk-resident log file
 /// using strict O_DIRECT / O_DSYNC semantics before they are applied to the
 /// in-memory index.
 ///
+/// # Segments
+/// The log is a sequence of segment files addressed by one continuous byte offset.
+/// A segment is named after the offset of its first byte (`shard_{id}.{base:020}.wal`);
+/// the first segment keeps the original `shard_{id}.wal` name. Writes rotate to a new
+/// segment once the active one reaches `segment_size`, and `checkpoint` deletes
+/// segments that a durable snapshot already covers.
+///
+/// A pre-checksum log (bare VBP frames, no header page) is rewritten into a version 1
+/// segment on open; see `upgrade_legacy_log`.
+///
+/// # Format (Version 1, Little-Endian)
+/// Each segment opens with a header page:
+/// - `magic` (8 bytes): `VXWALSEG`
+/// - `version` (4 bytes), `shard_id` (4 bytes)
+/// - `first_seq` (8 bytes): sequence number of the first record in the segment
+/// - `crc` (4 bytes): CRC32C of the fields above; the rest of the page is zero
+///
+/// It is followed by group-commit batches, each zero-padded to 4KB. A batch packs
+/// records on 8-byte boundaries:
+/// - `len` (4 bytes): length of the VBP frame (0 marks batch padding)
+/// - `crc` (4 bytes): CRC32C of `len`, `seq` and the frame
+/// - `seq` (8 bytes): consecutive across the whole log
+/// - the VBP frame (request header + payload)
+///
 /// # Thread Safety
 /// This struct is intended to be owned by a single `ShardReactor` thread.
 /// It is NOT `Sync` and should not be shared across threads (Rule #6).
 pub struct WalManager {
+    shard_id: usize,
+    dir: String,
     file: DirectFile,
+    /// Base offsets of the segments on disk, ascending. The last one is active.
+    segments: Vec<u64>,
     current_offset: u64,
+    segment_size: u64,
+    /// Sequence number of the next record to be written.
+    next_seq: u64,
 }
 
 /// Standard Page Size for NVMe/SSD alignment (4KB).
 pub const PAGE_SIZE: usize = 4096;
 
+/// Size at which the active segment is sealed and a new one started.
+pub const DEFAULT_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// WAL format written by this build.
+pub const WAL_FORMAT_VERSION: u32 = 1;
+
+/// Every segment starts with one header page.
+pub const SEGMENT_HEADER_SIZE: u64 = PAGE_SIZE as u64;
+
+/// Size of the `len`/`crc`/`seq` header in front of every record.
+pub const RECORD_HEADER_SIZE: usize = 16;
+
+/// Records start on this alignment, so batch padding always reads as `len == 0`.
+const RECORD_ALIGN: usize = 8;
+
+/// Identifies a VORTEX WAL segment.
+const SEGMENT_MAGIC: [u8; 8] = *b"VXWALSEG";
+
+/// Encoded size of the segment header fields (magic, version, shard, first_seq, crc).
+const SEGMENT_HEADER_FIELDS: usize = 28;
+
+/// Bytes taken in a batch by a record holding a `frame_len`-byte VBP frame.
+pub fn record_size(frame_len: usize) -> usize {
+    (RECORD_HEADER_SIZE + frame_len + RECORD_ALIGN - 1) & !(RECORD_ALIGN - 1)
+}
+
+/// CRC32C protecting one record: its length, its sequence number and its VBP frame.
+pub fn record_crc(len: u32, seq: u64, frame: &[u8]) -> u32 {
+    let crc = crc32c::crc32c(&len.to_le_bytes());
+    let crc = crc32c::crc32c_append(crc, &seq.to_le_bytes());
+    crc32c::crc32c_append(crc, frame)
+}
+
+/// Encodes the header fields of a segment owned by `shard_id` whose first record is `first_seq`.
+pub fn encode_segment_header(shard_id: usize, first_seq: u64) -> [u8; SEGMENT_HEADER_FIELDS] {
+    let mut out = [0u8; SEGMENT_HEADER_FIELDS];
+    out[0..8].copy_from_slice(&SEGMENT_MAGIC);
+    out[8..12].copy_from_slice(&WAL_FORMAT_VERSION.to_le_bytes());
+    out[12..16].copy_from_slice(&(shard_id as u32).to_le_bytes());
+    out[16..24].copy_from_slice(&first_seq.to_le_bytes());
+    let crc = crc32c::crc32c(&out[..24]);
+    out[24..28].copy_from_slice(&crc.to_le_bytes());
+    out
+}
+
+/// Validates a segment header and returns its `first_seq`.
+///
+/// # Errors
+/// Returns `ErrorKind::InvalidData` for a foreign or damaged header, an unknown
+/// format version or a segment written by another shard.
+fn parse_segment_header(bytes: &[u8], shard_id: usize) -> std::io::Result<u64> {
+    let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+    if bytes.len() < SEGMENT_HEADER_FIELDS || bytes[0..8] != SEGMENT_MAGIC {
+        return Err(invalid("WAL segment header is missing (pre-checksum WAL or foreign file)".to_string()));
+    }
+    let field = |range: std::ops::Range<usize>| bytes[range].iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    if field(24..28) as u32 != crc32c::crc32c(&bytes[..24]) {
+        return Err(invalid("WAL segment header checksum mismatch".to_string()));
+    }
+    let version = field(8..12) as u32;
+    if version != WAL_FORMAT_VERSION {
+        return Err(invalid(format!("Unsupported WAL format version {} (expected {})", version, WAL_FORMAT_VERSION)));
+    }
+    let owner = field(12..16) as usize;
+    if owner != shard_id {
+        return Err(invalid(format!("WAL segment belongs to shard {}, not {}", owner, shard_id)));
+    }
+    Ok(field(16..24))
+}
+
+/// Reads and validates the header of the segment file at `path`.
+fn read_segment_header(path: &str, shard_id: usize) -> std::io::Result<u64> {
+    use std::io::Read;
+    let mut bytes = [0u8; SEGMENT_HEADER_FIELDS];
+    std::fs::File::open(path)?.read_exact(&mut bytes)?;
+    parse_segment_header(&bytes, shard_id)
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", path, e)))
+}
+
+/// Writes a header page at the start of an O_DIRECT segment file.
+fn write_segment_header(file: &DirectFile, shard_id: usize, first_seq: u64) -> std::io::Result<()> {
+    let (mut page, _) = BufferPage::new(PAGE_SIZE);
+    let bytes = page.as_slice_mut();
+    bytes.fill(0);
+    bytes[..SEGMENT_HEADER_FIELDS].copy_from_slice(&encode_segment_header(shard_id, first_seq));
+    file.write_all_at(bytes, 0)
+}
+
+/// Path of the segment of shard `shard_id` in `dir` that starts at offset `base`.
+pub fn segment_path(dir: &str, shard_id: usize, base: u64) -> String {
+    if base == 0 {
+        format!("{}/shard_{}.wal", dir, shard_id)
+    } else {
+        format!("{}/shard_{}.{:020}.wal", dir, shard_id, base)
+    }
+}
+
+/// Lists the base offsets of every segment of shard `shard_id` in `dir`, ascending.
+pub fn list_segments(dir: &str, shard_id: usize) -> std::io::Result<Vec<u64>> {
+    let legacy = format!("shard_{}.wal", shard_id);
+    let prefix = format!("shard_{}.", shard_id);
+    let mut bases = Vec::new();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(bases),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let name = entry?.file_name();
+        let name = name.to_string_lossy();
+        if name == legacy {
+            bases.push(0);
+        } else if let Some(base) = name.strip_prefix(&prefix).and_then(|n| n.strip_suffix(".wal")) {
+            if let Ok(base) = base.parse::<u64>() {
+                bases.push(base);
+            }
+        }
+    }
+    bases.sort_unstable();
+    Ok(bases)
+}
+
+/// Rewrites a pre-checksum `shard_{id}.wal` into a version 1 segment. Returns false,
+/// leaving the file alone, if `path` is missing, empty or not a legacy log.
+///
+/// The legacy format packs bare VBP frames into batches zero-padded to 4KB. Its frames
+/// are numbered from 1 in log order; a torn or damaged tail ends the copy, as it ended
+/// legacy replay. The original file is kept as `shard_{id}.wal.legacy`.
+///
+/// # Rule #8 Exception
+/// Boot-time blocking I/O, once per data directory.
+fn upgrade_legacy_log(path: &str, shard_id: usize) -> std::io::Result<bool> {
+    use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+    let mut reader = match std::fs::File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let mut head = [0u8; vortex_rpc::RequestHeader::SIZE];
+    match reader.read_exact(&mut head) {
+        Ok(_) if vortex_rpc::RequestHeader::parse(&head).is_ok() => {}
+        Ok(_) => return Ok(false),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(false),
+        Err(e) => return Err(e),
+    }
+    reader.seek(SeekFrom::Start(0))?;
+    info!("Shard {} WAL {} has the pre-checksum format, upgrading it to version {}", shard_id, path, WAL_FORMAT_VERSION);
+
+    let tmp = format!("{}.tmp", path);
+    let mut out = BufWriter::new(std::fs::File::create(&tmp)?);
+    let mut page = [0u8; PAGE_SIZE];
+    page[..SEGMENT_HEADER_FIELDS].copy_from_slice(&encode_segment_header(shard_id, 1));
+    out.write_all(&page)?;
+
+    let mut offset = 0u64;
+    let mut written = SEGMENT_HEADER_SIZE;
+    let mut seq = 1u64;
+    loop {
+        match reader.read_exact(&mut head) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        // A zero magic is batch padding: the next frame starts on the next 4KB boundary
+        if head[..2] == [0, 0] {
+            let next_page = (offset + 4095) & !4095;
+            if next_page == offset {
+                break;
+            }
+            reader.seek(SeekFrom::Start(next_page))?;
+            offset = next_page;
+            continue;
+        }
+        let Ok(header) = vortex_rpc::RequestHeader::parse(&head) else {
+            warn!("Shard {} legacy WAL damaged at offset {}, keeping {} records", shard_id, offset, seq - 1);
+            break;
+        };
+        let mut frame = head.to_vec();
+        frame.resize(head.len() + header.payload_len as usize, 0);
+        if record_size(frame.len()) > BATCH_CAPACITY || reader.read_exact(&mut frame[head.len()..]).is_err() {
+            warn!("Shard {} legacy WAL torn at offset {}, keeping {} records", shard_id, offset, seq - 1);
+            break;
+        }
+
+        let len = frame.len() as u32;
+        out.write_all(&len.to_le_bytes())?;
+        out.write_all(&record_crc(len, seq, &frame).to_le_bytes())?;
+        out.write_all(&seq.to_le_bytes())?;
+        out.write_all(&frame)?;
+        out.write_all(&[0u8; RECORD_ALIGN][..record_size(frame.len()) - RECORD_HEADER_SIZE - frame.len()])?;
+        written += record_size(frame.len()) as u64;
+        offset += frame.len() as u64;
+        seq += 1;
+    }
+    // Pad the last batch so appends stay 4KB aligned
+    let padding = ((written + 4095) & !4095) - written;
+    page.fill(0);
+    out.write_all(&page[..padding as usize])?;
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+
+    match std::fs::hard_link(path, format!("{}.legacy", path)) {
+        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    std::fs::rename(&tmp, path)?;
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    info!("Shard {} WAL upgraded: {} records, original kept as {}.legacy", shard_id, seq - 1, path);
+    Ok(true)
+}
+
 impl WalManager {
     /// Initializes a new WAL Manager.
     ///
@@ -28,59 +273,205 @@ impl WalManager {
     /// * `base_path` - The directory where WAL files will be stored.
     ///
     /// # Errors
-    /// Returns `std::io::Result` if the file cannot be opened or created.
+    /// Returns `std::io::Result` if the file cannot be opened or created, or
+    /// `ErrorKind::InvalidData` if the active segment has no valid version 1 header.
     pub fn new(shard_id: usize, base_path: &str) -> std::io::Result<Self> {
-        let wal_path = format!("{}/shard_{}.wal", base_path, shard_id);
-        
+        let mut segments = list_segments(base_path, shard_id)?;
+        if segments.is_empty() {
+            segments.push(0);
+        }
+        if segments == [0] {
+            upgrade_legacy_log(&segment_path(base_path, shard_id, 0), shard_id)?;
+        }
+
+        // A segment shorter than its header was cut off by a crash during rotation: it holds no records
+        while segments.len() > 1 {
+            let path = segment_path(base_path, shard_id, *segments.last().unwrap_or(&0));
+            if std::fs::metadata(&path)?.len() >= SEGMENT_HEADER_SIZE {
+                break;
+            }
+            info!("Shard {} WAL discarding incomplete segment {}", shard_id, path);
+            std::fs::remove_file(&path)?;
+            segments.pop();
+        }
+
+        let active_base = *segments.last().unwrap_or(&0);
+        let wal_path = segment_path(base_path, shard_id, active_base);
+
         // Open with kernel-bypass flags (O_DIRECT | O_DSYNC)
         let file = DirectFile::open_wal(&wal_path)?;
-        
-        // RECOVERY LOGIC: Seek to the end of the file to determine the append cursor.
+
+        // RECOVERY LOGIC: Seek to the end of the active segment to determine the append cursor.
         // This allows the system to restart and continue appending to the existing log
         // without overwriting committed data.
-        let current_offset = file.file_size()?;
-        
-        info!("Shard {} WAL Manager initialized at {} (Offset: {})", shard_id, wal_path, current_offset);
-        
+        let mut file_size = file.file_size()?;
+        let next_seq = if file_size >= SEGMENT_HEADER_SIZE {
+            // A write torn mid-page leaves a ragged end: zero-fill it so appends stay aligned
+            let aligned = (file_size + PAGE_SIZE as u64 - 1) & !(PAGE_SIZE as u64 - 1);
+            if aligned > file_size {
+                file.truncate(aligned)?;
+                file_size = aligned;
+            }
+            read_segment_header(&wal_path, shard_id)?
+        } else if active_base == 0 {
+            // Fresh log: sequence numbers start at 1
+            file.truncate(0)?;
+            write_segment_header(&file, shard_id, 1)?;
+            1
+        } else {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: WAL segment header is missing", wal_path)));
+        };
+        let current_offset = active_base + file_size.max(SEGMENT_HEADER_SIZE);
+
+        info!("Shard {} WAL Manager initialized at {} (Offset: {}, Segments: {})", shard_id, wal_path, current_offset, segments.len());
+
         Ok(Self {
+            shard_id,
+            dir: base_path.to_string(),
             file,
+            segments,
             current_offset,
+            segment_size: DEFAULT_SEGMENT_SIZE,
+            next_seq,
         })
     }
 
-    /// Prepares a Write SQE for the io_uring submission queue.
+    /// Sets the size at which the active segment is sealed.
+    pub fn set_segment_size(&mut self, bytes: u64) {
+        self.segment_size = bytes.max(SEGMENT_HEADER_SIZE + PAGE_SIZE as u64);
+    }
+
+    /// Sequence number the next record will be written with.
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Resumes numbering after the last record found by replay.
+    pub fn set_next_seq(&mut self, seq: u64) {
+        self.next_seq = seq;
+    }
+
+    /// Seals `batch` (sequence numbers and checksums) and prepares its Write SQE
+    /// for the io_uring submission queue. Returns the entry and the aligned length.
     ///
     /// # Logic
-    /// Creates an `io_uring::opcode::Write` entry pointing to `buf`.
+    /// Creates an `io_uring::opcode::Write` entry pointing to the batch buffer,
+    /// rotating to a new segment first if this write would overflow the active one.
     /// Does NOT submit the entry; the Reactor must push it to the ring.
     ///
     /// # Safety
-    /// * `buf` must be a valid pointer to memory that will NOT be dropped 
-    ///   until the completion event is received by the Reactor (Rule #8).
-    /// * `len` should ideally be 4096-aligned for optimal O_DIRECT performance.
-    pub fn write_entry(&mut self, buf: *const u8, len: u32, user_data: u64) -> io_uring::squeue::Entry {
+    /// * `batch` must NOT be dropped or reused until the completion event is
+    ///   received by the Reactor (Rule #8).
+    /// * No earlier write may still be in flight: rotation closes the active segment.
+    pub fn write_batch(&mut self, batch: &mut BatchAccumulator, user_data: u64) -> (io_uring::squeue::Entry, usize) {
+        let first_seq = self.next_seq;
+        self.next_seq += batch.tags.len() as u64;
+        let (buf, len) = batch.prepare_flush(first_seq);
+
+        let active_len = self.current_offset - self.active_base();
+        if active_len > SEGMENT_HEADER_SIZE && active_len + len as u64 > self.segment_size {
+            self.rotate(first_seq);
+        }
+
         // Prepare the IO uring entry
-        let entry = self.file.write_sqe(buf, len, self.current_offset, user_data);
-        
+        let entry = self.file.write_sqe(buf, len as u32, self.current_offset - self.active_base(), user_data);
+
         // Advance offset state immediately (Optimistic Append)
         self.current_offset += len as u64;
-        
-        entry
+
+        (entry, len)
+    }
+
+    /// Seals the active segment and opens the next one at the current offset.
+    ///
+    /// # Rule #8 Exception
+    /// One `open(2)` and one header write per segment. On failure the active segment keeps growing.
+    fn rotate(&mut self, first_seq: u64) {
+        let path = segment_path(&self.dir, self.shard_id, self.current_offset);
+        let opened = DirectFile::open_wal(&path)
+            .and_then(|file| write_segment_header(&file, self.shard_id, first_seq).map(|_| file));
+        match opened {
+            Ok(file) => {
+                info!("Shard {} WAL rotated to segment {}", self.shard_id, path);
+                self.file = file;
+                self.segments.push(self.current_offset);
+                self.current_offset += SEGMENT_HEADER_SIZE;
+            }
+            Err(e) => {
+                error!("Shard {} WAL rotation to {} failed, extending the active segment: {}", self.shard_id, path, e);
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+
+    fn active_base(&self) -> u64 {
+        *self.segments.last().unwrap_or(&0)
     }
 
     /// Truncates the WAL to a specific offset.
-    /// Used during recovery to prune corrupted tails.
+    /// Used during recovery to prune corrupted tails. Segments starting at or
+    /// after `offset` are deleted, and the segment holding it becomes active.
+    ///
+    /// The cut is zero-filled up to the next 4KB boundary, where appends resume:
+    /// O_DIRECT writes need aligned offsets, and replay skips the zeros as batch padding.
     pub fn truncate(&mut self, offset: u64) -> std::io::Result<()> {
-        self.file.truncate(offset)?;
-        self.current_offset = offset;
+        while self.segments.len() > 1 && self.active_base() >= offset {
+            let base = self.segments.pop().unwrap_or(0);
+            std::fs::remove_file(segment_path(&self.dir, self.shard_id, base))?;
+            self.file = DirectFile::open_wal(&segment_path(&self.dir, self.shard_id, self.active_base()))?;
+        }
+        let mut local = offset.saturating_sub(self.active_base());
+        self.file.truncate(local)?;
+        if local < SEGMENT_HEADER_SIZE {
+            // The header itself was damaged: start the segment over
+            write_segment_header(&self.file, self.shard_id, self.next_seq)?;
+            local = SEGMENT_HEADER_SIZE;
+        }
+        let aligned = (local + PAGE_SIZE as u64 - 1) & !(PAGE_SIZE as u64 - 1);
+        if aligned > local {
+            self.file.truncate(aligned)?;
+            local = aligned;
+        }
+        self.current_offset = self.active_base() + local;
         Ok(())
     }
 
-    /// Returns the current write offset (file size).
+    /// Discards a failed append: truncates the log back to `offset` and restarts numbering at `next_seq`.
+    pub fn rewind(&mut self, offset: u64, next_seq: u64) -> std::io::Result<()> {
+        self.next_seq = next_seq;
+        self.truncate(offset)
+    }
+
+    /// Deletes every sealed segment that lies entirely below `covered_offset`, the WAL
+    /// offset a durable snapshot covers. Returns the number of segments removed.
+    ///
+    /// # Rule #8 Exception
+    /// Unlinks files synchronously. Runs once per snapshot, not per request.
+    pub fn checkpoint(&mut self, covered_offset: u64) -> std::io::Result<usize> {
+        let mut removed = 0;
+        // A sealed segment ends where the next one begins
+        while self.segments.len() > 1 && self.segments[1] <= covered_offset {
+            std::fs::remove_file(segment_path(&self.dir, self.shard_id, self.segments[0]))?;
+            self.segments.remove(0);
+            removed += 1;
+        }
+        if removed > 0 {
+            info!("Shard {} WAL checkpoint at offset {}: {} segment(s) deleted.", self.shard_id, covered_offset, removed);
+        }
+        Ok(removed)
+    }
+
+    /// Returns the current write offset (end of the log).
     pub fn current_offset(&self) -> u64 {
         self.current_offset
     }
 
+    /// Returns the offset of the oldest byte still on disk.
+    /// Non-zero once a checkpoint has deleted segments.
+    pub fn first_offset(&self) -> u64 {
+        self.segments.first().copied().unwrap_or(0)
+    }
+
     /// Creates a blocking iterator for WAL replay during boot.
     ///
     /// # Rule #8 Exception
@@ -88,40 +479,112 @@ impl WalManager {
     /// forbidden by the 12 Commandments. However, during boot (before the
     /// Reactor is online), blocking is acceptable and simpler than async.
     ///
+    /// # Arguments
+    /// * `start_offset` - 0, or the 4KB-aligned offset covered by a snapshot.
+    /// * `next_seq` - Sequence number expected at `start_offset`, if known (from the snapshot).
+    ///   Otherwise it is taken from the header of the first segment.
+    ///
     /// # Returns
-    /// A `WalIterator` that yields WAL entries sequentially from offset 0.
-    pub fn replay_iter(&self, wal_path: &str) -> std::io::Result<WalIterator> {
-        WalIterator::new(wal_path)
+    /// A `WalIterator` that yields WAL entries sequentially across all segments.
+    pub fn replay_iter(&self, start_offset: u64, next_seq: Option<u64>) -> std::io::Result<WalIterator> {
+        let paths = self.segments.iter().map(|&base| (base, segment_path(&self.dir, self.shard_id, base))).collect();
+        WalIterator::new(self.shard_id, paths, start_offset, next_seq)
     }
 }
 
 /// Iterator for sequentially reading WAL entries during crash recovery.
 ///
+/// Replay stops at the first record that fails its checksum or breaks the
+/// sequence; `bytes_read` then points at that record.
+///
 /// # Boot-Time Only
 /// This struct uses blocking `std::fs::File::read_exact` which violates Rule #8.
 /// It is ONLY safe to use during boot before the Reactor starts.
 pub struct WalIterator {
+    shard_id: usize,
     file: std::fs::File,
+    /// Base offset of the open segment.
+    base: u64,
+    /// Segments after the open one, as (base offset, path).
+    remaining: std::collections::VecDeque<(u64, String)>,
     bytes_read: u64,
+    next_seq: Option<u64>,
 }
 
 impl WalIterator {
-    fn new(wal_path: &str) -> std::io::Result<Self> {
-        let file = std::fs::File::open(wal_path)?;
+    fn new(shard_id: usize, segments: Vec<(u64, String)>, start_offset: u64, next_seq: Option<u64>) -> std::io::Result<Self> {
+        use std::io::Seek;
+        // Start in the last segment beginning at or before the requested offset
+        let first = segments.iter().rposition(|&(base, _)| base <= start_offset).unwrap_or(0);
+        let mut remaining: std::collections::VecDeque<_> = segments.into_iter().skip(first).collect();
+        let (base, path) = remaining.pop_front().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "WAL has no segments"))?;
+
+        let first_seq = read_segment_header(&path, shard_id)?;
+        let start_offset = start_offset.max(base + SEGMENT_HEADER_SIZE);
+        let next_seq = if start_offset == base + SEGMENT_HEADER_SIZE {
+            match next_seq {
+                Some(seq) if seq != first_seq => {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!(
+                        "{}: segment starts at sequence {}, expected {}", path, first_seq, seq)));
+                }
+                _ => Some(first_seq),
+            }
+        } else {
+            next_seq
+        };
+
+        let mut file = std::fs::File::open(path)?;
+        file.seek(std::io::SeekFrom::Start(start_offset - base))?;
         Ok(Self {
+            shard_id,
             file,
-            bytes_read: 0,
+            base,
+            remaining,
+            bytes_read: start_offset,
+            next_seq,
         })
     }
 
-    /// Returns the total number of bytes processed so far.
+    /// Returns the log offset reached so far.
     pub fn bytes_read(&self) -> u64 {
         self.bytes_read
     }
+
+    /// Sequence number of the record after the last one replayed.
+    pub fn next_seq(&self) -> Option<u64> {
+        self.next_seq
+    }
+
+    /// Moves on to the next segment. Returns false at the end of the log.
+    fn next_segment(&mut self) -> std::io::Result<bool> {
+        let Some((base, path)) = self.remaining.pop_front() else { return Ok(false) };
+        // A damaged header is reported at the segment base, so truncation drops the whole segment
+        self.bytes_read = base;
+        let first_seq = read_segment_header(&path, self.shard_id)?;
+        if let Some(expected) = self.next_seq.filter(|&seq| seq != first_seq) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!(
+                "{}: segment starts at sequence {}, expected {}", path, first_seq, expected)));
+        }
+        self.file = std::fs::File::open(path)?;
+        self.base = base;
+        self.next_seq = Some(first_seq);
+        self.seek_to(base + SEGMENT_HEADER_SIZE)?;
+        Ok(true)
+    }
+
+    /// Moves the read cursor to log offset `offset` within the open segment.
+    fn seek_to(&mut self, offset: u64) -> std::io::Result<()> {
+        use std::io::Seek;
+        self.file.seek(std::io::SeekFrom::Start(offset - self.base))?;
+        self.bytes_read = offset;
+        Ok(())
+    }
 }
 
 /// A single WAL entry read during replay.
 pub struct WalEntry {
+    /// Position of the record in the log
+    pub seq: u64,
     /// The raw request header (16 bytes)
     pub header: vortex_rpc::RequestHeader,
     /// The payload (ID + Vector bytes)
@@ -135,81 +598,274 @@ impl Iterator for WalIterator {
         use std::io::Read;
 
         let entry_start_offset = self.bytes_read;
+        let corrupt = |msg: String| Some(Err(std::io::Error::new(std::io::ErrorKind::InvalidData, msg)));
 
-        // 1. Read Header (16 bytes)
-        let mut header_buf = [0u8; 16];
-        match self.file.read_exact(&mut header_buf) {
+        // 1. Read the record header (len, crc, seq)
+        let mut record_buf = [0u8; RECORD_HEADER_SIZE];
+        match self.file.read_exact(&mut record_buf) {
             Ok(_) => {},
             Err(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                // EOF: Clean termination
-                return None;
+                // EOF: Clean termination, or the end of a sealed segment
+                return match self.next_segment() {
+                    Ok(true) => self.next(),
+                    Ok(false) => None,
+                    Err(e) => Some(Err(e)),
+                };
             },
-            Err(e) => {
-                // Partial read = Corruption
-                return Some(Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("WAL Truncation detected at offset {}: {}", entry_start_offset, e)
-                )));
+            Err(e) => return Some(Err(e)),
+        }
+        let len = u32::from_le_bytes(record_buf[0..4].try_into().unwrap_or([0; 4]));
+        let crc = u32::from_le_bytes(record_buf[4..8].try_into().unwrap_or([0; 4]));
+        let seq = u64::from_le_bytes(record_buf[8..16].try_into().unwrap_or([0; 8]));
+
+        // 2. SECTOR ALIGNMENT RECOVERY (BP Rule 12)
+        // A zero length is batch padding: jump to the next 4KB boundary.
+        // A batch never starts with padding, so an empty page start is a torn write.
+        if len == 0 {
+            let next_page = (entry_start_offset + 4095) & !4095;
+            if next_page == entry_start_offset {
+                return corrupt(format!("WAL Corruption: empty record at offset {}", entry_start_offset));
             }
+            if let Err(e) = self.seek_to(next_page) {
+                return Some(Err(e));
+            }
+            // Recursively call next to start from the fresh page
+            return self.next();
+        }
+        let header_size = std::mem::size_of::<vortex_rpc::RequestHeader>();
+        if (len as usize) < header_size || record_size(len as usize) > BATCH_CAPACITY {
+            return corrupt(format!("WAL Corruption: invalid record length {} at offset {}", len, entry_start_offset));
         }
 
-        // 2. Parse Header
-        // SAFETY: RequestHeader is #[repr(C)] fixed size
-        let header = unsafe {
-            std::ptr::read(header_buf.as_ptr() as *const vortex_rpc::RequestHeader)
+        // 3. Read and verify the frame
+        let mut frame = vec![0u8; len as usize];
+        if let Err(e) = self.file.read_exact(&mut frame) {
+            return corrupt(format!("WAL Truncation in record at offset {}: {}", entry_start_offset, e));
+        }
+        if record_crc(len, seq, &frame) != crc {
+            return corrupt(format!("WAL Corruption: checksum mismatch in record {} at offset {}", seq, entry_start_offset));
+        }
+        if let Some(expected) = self.next_seq.filter(|&expected| expected != seq) {
+            return corrupt(format!("WAL Corruption: record {} at offset {} (expected {})", seq, entry_start_offset, expected));
+        }
+
+        // 4. Parse and validate the VBP header
+        let header = match vortex_rpc::RequestHeader::parse(&frame) {
+            Ok(header) if header.payload_len as usize == len as usize - header_size => header,
+            _ => return corrupt(format!("WAL Corruption: Invalid frame header at offset {}", entry_start_offset)),
         };
 
-        // 3. SECTOR ALIGNMENT RECOVERY (BP Rule 12)
-        // If magic is 0, we hit padding. Jump to the next 4KB boundary.
-        if header.magic == 0 {
-            let next_page = (self.bytes_read + 4095) & !4095;
-            if next_page > self.bytes_read {
-                use std::io::Seek;
-                if let Err(e) = self.file.seek(std::io::SeekFrom::Start(next_page)) {
-                    return Some(Err(e));
-                }
-                self.bytes_read = next_page;
-                // Recursively call next to start from the fresh page
-                return self.next();
-            }
+        // 5. Skip the record alignment padding; advance only after full validation
+        if let Err(e) = self.seek_to(entry_start_offset + record_size(len as usize) as u64) {
+            return Some(Err(e));
         }
+        self.next_seq = Some(seq + 1);
 
-        // 4. Validate Magic
-        if header.magic != vortex_rpc::VBP_MAGIC {
-            return Some(Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("WAL Corruption: Invalid magic 0x{:x} at offset {}", header.magic, entry_start_offset)
-            )));
-        }
+        // 6. Yield Entry
+        frame.drain(..header_size);
+        Some(Ok(WalEntry { seq, header, payload: frame }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::batch::BatchTag;
+    use vortex_rpc::{UpsertRequest, VBP_MAGIC, OP_UPSERT};
 
-        // Advance count only after header is fully validated
-        self.bytes_read += 16;
+    /// A 440-byte record: 16-byte record header, 16-byte VBP header, 408-byte upsert payload.
+    const RECORD: usize = 440;
 
-        // 4. Read Payload
-        let payload_len = header.payload_len as usize;
-        let mut payload = vec![0u8; payload_len];
+    fn test_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("vortex_wal_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().into_owned()
+    }
+
+    /// Seals one batch of upserts for `ids` exactly as the reactor writes it.
+    fn batch(first_seq: u64, ids: std::ops::Range<u64>) -> Vec<u8> {
+        let mut batch = upserts(ids);
+        let (ptr, len) = batch.prepare_flush(first_seq);
+        // SAFETY: `prepare_flush` returns the sealed prefix of the batch buffer, alive until `batch` drops.
+        unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec()
+    }
 
-        if let Err(e) = self.file.read_exact(&mut payload) {
-            return Some(Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("WAL Truncation in payload at offset {}: {}", self.bytes_read, e)
-            )));
+    /// An unsealed batch of upserts for `ids`.
+    fn upserts(ids: std::ops::Range<u64>) -> BatchAccumulator {
+        let mut batch = BatchAccumulator::new();
+        for id in ids {
+            let payload = UpsertRequest { id }.encode(&[id as f32; 100]);
+            let mut frame = Vec::with_capacity(16 + payload.len());
+            frame.extend_from_slice(&VBP_MAGIC.to_le_bytes());
+            frame.extend_from_slice(&[1, OP_UPSERT]);
+            frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            frame.extend_from_slice(&id.to_le_bytes());
+            frame.extend_from_slice(&payload);
+            batch.try_add(&frame, BatchTag { slot: 0, request_id: id }).unwrap();
+        }
+        batch
+    }
+
+    /// Recovers the log the way `Collection::open` does, then appends upserts for `ids`
+    /// through the O_DIRECT write path.
+    fn recover_and_append(dir: &str, ids: std::ops::Range<u64>) {
+        let mut wal = WalManager::new(0, dir).unwrap();
+        let mut iter = wal.replay_iter(0, None).unwrap();
+        let mut stop = None;
+        for entry in &mut iter {
+            if entry.is_err() {
+                stop = Some(iter.bytes_read());
+                break;
+            }
+        }
+        if let Some(seq) = iter.next_seq() {
+            wal.set_next_seq(seq);
         }
+        if let Some(offset) = stop {
+            wal.truncate(offset).unwrap();
+        }
+        assert_eq!(wal.current_offset() % PAGE_SIZE as u64, 0);
 
-        self.bytes_read += payload_len as u64;
+        let mut ring = io_uring::IoUring::new(4).unwrap();
+        let mut batch = upserts(ids);
+        let (entry, len) = wal.write_batch(&mut batch, 0);
+        // SAFETY: `batch` outlives the write, which completes before this function returns.
+        unsafe { ring.submission().push(&entry).unwrap() };
+        ring.submit_and_wait(1).unwrap();
+        assert_eq!(ring.completion().next().unwrap().result(), len as i32);
+    }
 
-        // 5. SECURE ALIGNMENT: Each WAL entry was written with O_DIRECT (aligned to 4KB).
-        // To read the NEXT entry, we must skip the padding by aligning bytes_read.
-        let next_aligned = (self.bytes_read + 4095) & !4095;
-        if next_aligned > self.bytes_read {
-            use std::io::Seek;
-            if let Err(e) = self.file.seek(std::io::SeekFrom::Start(next_aligned)) {
-                return Some(Err(e));
+    /// `ids` followed by the records `recover_and_append` adds.
+    fn with_appended(ids: std::ops::Range<u64>) -> Vec<u64> {
+        ids.chain(100..103).collect()
+    }
+
+    /// Writes a segment-0 log of the given batches and returns its path.
+    fn write_log(dir: &str, batches: &[Vec<u8>]) -> String {
+        let mut log = vec![0u8; PAGE_SIZE];
+        log[..SEGMENT_HEADER_FIELDS].copy_from_slice(&encode_segment_header(0, 1));
+        for batch in batches {
+            log.extend_from_slice(batch);
+        }
+        let path = segment_path(dir, 0, 0);
+        std::fs::write(&path, &log).unwrap();
+        path
+    }
+
+    /// Replays the log, returning the request IDs read and the offset replay stopped at, if it failed.
+    fn replay(dir: &str) -> (Vec<u64>, Option<u64>) {
+        let wal = WalManager::new(0, dir).unwrap();
+        let mut iter = wal.replay_iter(0, None).unwrap();
+        let mut ids = Vec::new();
+        for entry in &mut iter {
+            match entry {
+                Ok(entry) => ids.push(entry.header.request_id),
+                Err(_) => return (ids, Some(iter.bytes_read())),
             }
-            self.bytes_read = next_aligned;
         }
+        (ids, None)
+    }
 
-        // 6. Yield Entry
-        Some(Ok(WalEntry { header, payload }))
+    #[test]
+    fn test_replay_round_trips_packed_batches() {
+        let dir = test_dir("round_trip");
+        // A lone record, a batch spanning pages and one filling most of the batch buffer
+        let batches = [batch(1, 0..1), batch(2, 1..21), batch(22, 21..521)];
+        let path = write_log(&dir, &batches);
+
+        let (ids, stop) = replay(&dir);
+        assert_eq!(ids, (0..521).collect::<Vec<_>>());
+        assert_eq!(stop, None);
+
+        // Reopening appends after the last page and continues the sequence
+        let wal = WalManager::new(0, &dir).unwrap();
+        let mut iter = wal.replay_iter(0, None).unwrap();
+        assert_eq!(iter.by_ref().count(), 521);
+        assert_eq!(iter.next_seq(), Some(522));
+        assert_eq!(wal.current_offset(), std::fs::metadata(&path).unwrap().len());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_torn_page_stops_replay() {
+        let dir = test_dir("torn_page");
+        // 20 records span three pages; only the first page of the batch reached the disk
+        let mut first = batch(1, 0..20);
+        first[PAGE_SIZE..].fill(0);
+        write_log(&dir, &[first, batch(21, 20..25)]);
+
+        // Record 9 straddles the torn boundary: its checksum fails and nothing after it is replayed
+        let (ids, stop) = replay(&dir);
+        assert_eq!(ids, (0..9).collect::<Vec<_>>());
+        assert_eq!(stop, Some((PAGE_SIZE + 9 * RECORD) as u64));
+
+        // Writes resume on the next page boundary after the cut and replay after a restart
+        recover_and_append(&dir, 100..103);
+        assert_eq!(replay(&dir), (with_appended(0..9), None));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_partial_sector_write_stops_replay() {
+        let dir = test_dir("partial_sector");
+        // Only the first 512-byte sector of the second batch landed; the rest of its page is stale
+        let mut second = batch(21, 20..25);
+        second[512..].fill(0x5A);
+        write_log(&dir, &[batch(1, 0..20), second]);
+
+        let (ids, stop) = replay(&dir);
+        assert_eq!(ids, (0..21).collect::<Vec<_>>());
+        assert_eq!(stop, Some((PAGE_SIZE * 4 + RECORD) as u64));
+
+        recover_and_append(&dir, 100..103);
+        assert_eq!(replay(&dir), (with_appended(0..21), None));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_sequence_gap_and_bit_flip_stop_replay() {
+        let dir = test_dir("sequence_gap");
+        // A lost batch leaves a hole in the sequence
+        write_log(&dir, &[batch(1, 0..2), batch(6, 5..7)]);
+        assert_eq!(replay(&dir), (vec![0, 1], Some(2 * PAGE_SIZE as u64)));
+        recover_and_append(&dir, 100..103);
+        assert_eq!(replay(&dir), (with_appended(0..2), None));
+
+        // A single flipped payload bit fails the checksum
+        let mut second = batch(3, 2..4);
+        second[RECORD + 100] ^= 0x01;
+        write_log(&dir, &[batch(1, 0..2), second]);
+        assert_eq!(replay(&dir), (vec![0, 1, 2], Some((2 * PAGE_SIZE + RECORD) as u64)));
+        recover_and_append(&dir, 100..103);
+        assert_eq!(replay(&dir), (with_appended(0..3), None));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rewind_discards_a_failed_append() {
+        let dir = test_dir("rewind");
+        let path = write_log(&dir, &[batch(1, 0..1)]);
+        let end = std::fs::metadata(&path).unwrap().len();
+
+        // Half of the next batch reached the disk before its write failed
+        let mut log = std::fs::read(&path).unwrap();
+        log.extend_from_slice(&batch(2, 1..3)[..512]);
+        std::fs::write(&path, &log).unwrap();
+
+        // Reopening pads the ragged end to a page, so appends stay aligned
+        let mut wal = WalManager::new(0, &dir).unwrap();
+        assert_eq!(wal.current_offset(), end + PAGE_SIZE as u64);
+        wal.rewind(end, 2).unwrap();
+        assert_eq!(wal.current_offset(), end);
+        assert_eq!(wal.next_seq(), 2);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), end);
+        assert_eq!(replay(&dir), (vec![0], None));
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
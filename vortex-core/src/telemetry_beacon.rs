@@ -21,10 +21,15 @@ pub struct LiveHistogram {
     buckets: [AtomicU64; 12],
 }
 
+impl Default for LiveHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LiveHistogram {
     pub fn new() -> Self {
-        const ZERO: AtomicU64 = AtomicU64::new(0);
-        Self { buckets: [ZERO; 12] }
+        Self { buckets: std::array::from_fn(|_| AtomicU64::new(0)) }
     }
 
     pub fn record(&self, elapsed: Duration) {
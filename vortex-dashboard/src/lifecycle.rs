@@ -22,7 +22,7 @@ impl LifecycleManager {
         let mut sys = System::new_all();
         sys.refresh_all();
         
-        for (_pid, process) in sys.processes() {
+        for process in sys.processes().values() {
             if process.name().contains("vortex-server") {
                 process.kill();
             }
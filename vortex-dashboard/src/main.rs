@@ -177,7 +177,7 @@ fn main() -> Result<()> {
         
         loop {
             line_buf.clear();
-            if let Err(_) = reader.read_until(b'\n', &mut line_buf) { break; }
+            if reader.read_until(b'\n', &mut line_buf).is_err() { break; }
             if line_buf.is_empty() { break; }
 
             // Use from_utf8 to avoid allocations (Borrowing from buf)
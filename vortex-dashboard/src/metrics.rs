@@ -175,7 +175,7 @@ impl SystemSampler {
         // --- 1. Parse /proc/stat (CPU & Context Switches) ---
         let stat = fs::read_to_string("/proc/stat")?;
         for line in stat.lines() {
-            if line.starts_with("cpu") && line.as_bytes().get(3).map_or(false, |&b| b.is_ascii_digit()) {
+            if line.starts_with("cpu") && line.as_bytes().get(3).is_some_and(|b| b.is_ascii_digit()) {
                 let parts: Vec<&str> = line.split_whitespace().collect();
                 if parts.len() < 8 { continue; }
 
@@ -195,8 +195,8 @@ impl SystemSampler {
                 raw.cpu_user_ticks.push(user + nice);
                 raw.cpu_system_ticks.push(system + irq);
                 raw.cpu_softirq_ticks.push(softirq);
-            } else if line.starts_with("ctxt ") {
-                raw.context_switches = line[5..].trim().parse().unwrap_or(0);
+            } else if let Some(rest) = line.strip_prefix("ctxt ") {
+                raw.context_switches = rest.trim().parse().unwrap_or(0);
             }
         }
         
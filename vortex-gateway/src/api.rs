@@ -0,0 +1,200 @@
+//! JSON bodies of the gateway endpoints. `openapi.json` documents the same shapes.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use vortex_client::{CollectionInfo, Filter, Metadata, MetadataValue, SearchHit, SearchOptions, StoredVector};
+use vortex_rpc::{METRIC_COSINE, METRIC_INNER_PRODUCT, METRIC_L2};
+
+/// Upper bound on records in one `POST /v1/vectors/batch` body.
+pub const MAX_BATCH: usize = 10_000;
+
+/// Selects the collection of a request (`?collection=ID`). Absent means the default collection.
+#[derive(Debug, Default, Deserialize)]
+pub struct Target {
+    pub collection: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpsertBody {
+    pub id: u64,
+    pub vector: Vec<f32>,
+    #[serde(default)]
+    pub metadata: Option<JsonMetadata>,
+}
+
+impl UpsertBody {
+    pub fn metadata(&self) -> Option<Metadata> {
+        self.metadata.as_ref().map(|fields| Metadata {
+            fields: fields.iter().map(|(key, value)| (key.clone(), value.clone().into())).collect(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchBody {
+    pub vectors: Vec<UpsertBody>,
+}
+
+/// Metadata as a JSON object: integers, strings (keywords) and string arrays (tags).
+pub type JsonMetadata = BTreeMap<String, JsonValue>;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum JsonValue {
+    Int(i64),
+    Keyword(String),
+    Tags(Vec<String>),
+}
+
+impl From<JsonValue> for MetadataValue {
+    fn from(value: JsonValue) -> Self {
+        match value {
+            JsonValue::Int(v) => Self::Int(v),
+            JsonValue::Keyword(v) => Self::Keyword(v),
+            JsonValue::Tags(v) => Self::Tags(v),
+        }
+    }
+}
+
+impl From<MetadataValue> for JsonValue {
+    fn from(value: MetadataValue) -> Self {
+        match value {
+            MetadataValue::Int(v) => Self::Int(v),
+            MetadataValue::Keyword(v) => Self::Keyword(v),
+            MetadataValue::Tags(v) => Self::Tags(v),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchBody {
+    pub vector: Vec<f32>,
+    #[serde(default = "default_top_k")]
+    pub top_k: u32,
+    #[serde(default)]
+    pub ef_search: u32,
+    #[serde(default)]
+    pub filter: Option<JsonFilter>,
+}
+
+fn default_top_k() -> u32 {
+    10
+}
+
+impl SearchBody {
+    pub fn options(&self) -> SearchOptions {
+        let options = SearchOptions::new(self.top_k).with_ef_search(self.ef_search);
+        match &self.filter {
+            Some(filter) => options.with_filter(filter.clone().into()),
+            None => options,
+        }
+    }
+}
+
+/// A `Filter` expression, e.g. `{"and": [{"eq": {"key": "region", "value": "eu"}}, {"range": {"key": "stock", "min": 1, "max": 9}}]}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum JsonFilter {
+    Eq { key: String, value: String },
+    Range { key: String, min: i64, max: i64 },
+    And(Vec<JsonFilter>),
+    Or(Vec<JsonFilter>),
+    Not(Box<JsonFilter>),
+}
+
+impl From<JsonFilter> for Filter {
+    fn from(filter: JsonFilter) -> Self {
+        match filter {
+            JsonFilter::Eq { key, value } => Self::Eq { key, value },
+            JsonFilter::Range { key, min, max } => Self::Range { key, min, max },
+            JsonFilter::And(children) => Self::And(children.into_iter().map(Into::into).collect()),
+            JsonFilter::Or(children) => Self::Or(children.into_iter().map(Into::into).collect()),
+            JsonFilter::Not(child) => Self::Not(Box::new((*child).into())),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct IdResponse {
+    pub id: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchResponse {
+    pub upserted: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hit {
+    pub id: u64,
+    pub distance: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub hits: Vec<Hit>,
+}
+
+impl From<Vec<SearchHit>> for SearchResponse {
+    fn from(hits: Vec<SearchHit>) -> Self {
+        Self { hits: hits.into_iter().map(|hit| Hit { id: hit.id, distance: hit.distance }).collect() }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct VectorResponse {
+    pub id: u64,
+    pub vector: Vec<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<JsonMetadata>,
+}
+
+impl VectorResponse {
+    pub fn new(id: u64, stored: StoredVector) -> Self {
+        let metadata = stored.metadata.map(|m| m.fields.into_iter().map(|(key, value)| (key, value.into())).collect());
+        Self { id, vector: stored.vector, metadata }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CollectionStats {
+    pub id: u32,
+    pub name: String,
+    pub dimension: u32,
+    pub max_elements: u32,
+    pub metric: &'static str,
+}
+
+impl From<CollectionInfo> for CollectionStats {
+    fn from(info: CollectionInfo) -> Self {
+        let metric = match info.spec.metric {
+            METRIC_INNER_PRODUCT => "ip",
+            METRIC_L2 => "l2",
+            METRIC_COSINE => "cosine",
+            _ => "unknown",
+        };
+        Self { id: info.id, name: info.name, dimension: info.spec.dimension, max_elements: info.spec.max_elements, metric }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct GatewayStats {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatsResponse {
+    pub collections: Vec<CollectionStats>,
+    pub gateway: GatewayStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    /// Whether the same request may succeed if sent again.
+    pub retryable: bool,
+}
@@ -0,0 +1,46 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use log::info;
+use std::sync::Arc;
+use vortex_client::{Client, ClientConfig};
+
+mod api;
+mod routes;
+
+/// VORTEX HTTP/JSON Gateway: REST endpoints translated to VBP
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Address the HTTP listener binds to
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// VBP address of the VORTEX server
+    #[arg(short, long, default_value = "127.0.0.1:9000")]
+    server: String,
+
+    /// VBP connections shared by all HTTP requests
+    #[arg(short, long, default_value_t = 8)]
+    connections: usize,
+
+    /// Largest accepted request body in MB
+    #[arg(long, default_value_t = 32)]
+    max_body_mb: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let config = ClientConfig::default().with_connections(args.connections);
+    let client = Client::connect_with(args.server.as_str(), config).await
+        .with_context(|| format!("Failed to reach the VORTEX server at {}", args.server))?;
+    let app = routes::router(Arc::new(routes::Gateway::new(client)), args.max_body_mb * 1024 * 1024);
+
+    let listener = tokio::net::TcpListener::bind(&args.listen).await
+        .with_context(|| format!("Failed to bind {}", args.listen))?;
+    info!("Gateway listening on http://{} (VBP upstream {})", args.listen, args.server);
+    axum::serve(listener, app).await.context("HTTP server failed")?;
+    Ok(())
+}
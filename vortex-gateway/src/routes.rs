@@ -0,0 +1,227 @@
+//! REST endpoints, each translated to one VBP exchange (or one pipelined batch).
+
+use crate::api::*;
+use axum::extract::{DefaultBodyLimit, Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use vortex_client::{Client, Error};
+use vortex_rpc::{
+    STATUS_BAD_MAGIC, STATUS_BUSY, STATUS_CAPACITY_FULL, STATUS_DIMENSION_MISMATCH, STATUS_FRAME_TOO_LARGE, STATUS_NOT_FOUND,
+    STATUS_STORAGE, STATUS_UNKNOWN_COLLECTION, STATUS_UNKNOWN_OPCODE, STATUS_UNSUPPORTED_VERSION,
+};
+
+/// The schema served at `GET /openapi.json`.
+const OPENAPI: &str = include_str!("../openapi.json");
+
+pub struct Gateway {
+    client: Client,
+    requests: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Gateway {
+    pub fn new(client: Client) -> Self {
+        Self { client, requests: AtomicU64::new(0), errors: AtomicU64::new(0) }
+    }
+
+    fn client(&self, target: &Target) -> Client {
+        match target.collection {
+            Some(id) => self.client.collection(id),
+            None => self.client.clone(),
+        }
+    }
+}
+
+/// Builds the router. Request bodies above `max_body` bytes are refused with 413.
+pub fn router(gateway: Arc<Gateway>, max_body: usize) -> Router {
+    Router::new()
+        .route("/v1/vectors", post(upsert))
+        .route("/v1/vectors/batch", post(upsert_batch))
+        .route("/v1/vectors/:id", get(fetch).delete(delete))
+        .route("/v1/search", post(search))
+        .route("/v1/stats", get(stats))
+        .route("/openapi.json", get(openapi))
+        .layer(middleware::from_fn_with_state(gateway.clone(), count))
+        .layer(DefaultBodyLimit::max(max_body))
+        .with_state(gateway)
+}
+
+/// A failed request, rendered as `{"error": "...", "retryable": bool}`.
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+    retryable: bool,
+}
+
+impl ApiError {
+    /// A failure the client caused; sending the request again cannot help.
+    fn fatal(status: StatusCode, message: String) -> Self {
+        Self { status, message, retryable: false }
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        let status = match &e {
+            Error::Status { status: STATUS_NOT_FOUND | STATUS_UNKNOWN_COLLECTION, .. } => StatusCode::NOT_FOUND,
+            Error::Status { status: STATUS_DIMENSION_MISMATCH, .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Status { status: STATUS_FRAME_TOO_LARGE, .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::Status { status: STATUS_CAPACITY_FULL, .. } => StatusCode::INSUFFICIENT_STORAGE,
+            Error::Status { status: STATUS_BUSY | STATUS_STORAGE, .. } => StatusCode::SERVICE_UNAVAILABLE,
+            // The gateway framed the request, so these are its own faults
+            Error::Status { status: STATUS_UNKNOWN_OPCODE | STATUS_BAD_MAGIC | STATUS_UNSUPPORTED_VERSION, .. } => StatusCode::BAD_GATEWAY,
+            Error::Status { .. } => StatusCode::BAD_REQUEST,
+            Error::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Error::Io(_) | Error::Protocol(_) | Error::Closed => StatusCode::BAD_GATEWAY,
+        };
+        Self { status, message: e.to_string(), retryable: e.is_retryable() }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ErrorResponse { error: self.message, retryable: self.retryable })).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+async fn upsert(State(gw): State<Arc<Gateway>>, Query(target): Query<Target>, Json(body): Json<UpsertBody>) -> ApiResult<IdResponse> {
+    let client = gw.client(&target);
+    match body.metadata() {
+        Some(metadata) => client.upsert_with_metadata(body.id, &body.vector, &metadata).await?,
+        None => client.upsert(body.id, &body.vector).await?,
+    }
+    Ok(Json(IdResponse { id: body.id }))
+}
+
+async fn upsert_batch(State(gw): State<Arc<Gateway>>, Query(target): Query<Target>, Json(body): Json<BatchBody>) -> ApiResult<BatchResponse> {
+    if body.vectors.len() > MAX_BATCH {
+        return Err(ApiError::fatal(StatusCode::PAYLOAD_TOO_LARGE, format!("Batch exceeds {} vectors", MAX_BATCH)));
+    }
+    let metadata: Vec<Option<_>> = body.vectors.iter().map(UpsertBody::metadata).collect();
+    let records: Vec<_> = body.vectors.iter().zip(&metadata).map(|(record, metadata)| (record.id, record.vector.as_slice(), metadata.as_ref())).collect();
+    gw.client(&target).upsert_batch_with_metadata(records).await?;
+    Ok(Json(BatchResponse { upserted: body.vectors.len() }))
+}
+
+async fn search(State(gw): State<Arc<Gateway>>, Query(target): Query<Target>, Json(body): Json<SearchBody>) -> ApiResult<SearchResponse> {
+    let hits = gw.client(&target).search(&body.vector, &body.options()).await?;
+    Ok(Json(hits.into()))
+}
+
+async fn fetch(State(gw): State<Arc<Gateway>>, Query(target): Query<Target>, Path(id): Path<u64>) -> ApiResult<VectorResponse> {
+    match gw.client(&target).get(id).await? {
+        Some(stored) => Ok(Json(VectorResponse::new(id, stored))),
+        None => Err(ApiError::fatal(StatusCode::NOT_FOUND, format!("Vector {} not found", id))),
+    }
+}
+
+async fn delete(State(gw): State<Arc<Gateway>>, Query(target): Query<Target>, Path(id): Path<u64>) -> ApiResult<IdResponse> {
+    gw.client(&target).delete(id).await?;
+    Ok(Json(IdResponse { id }))
+}
+
+async fn stats(State(gw): State<Arc<Gateway>>) -> ApiResult<StatsResponse> {
+    let collections = gw.client.list_collections().await?;
+    Ok(Json(StatsResponse {
+        collections: collections.into_iter().map(Into::into).collect(),
+        gateway: GatewayStats { requests: gw.requests.load(Ordering::Relaxed), errors: gw.errors.load(Ordering::Relaxed) },
+    }))
+}
+
+async fn openapi() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI)
+}
+
+/// Counts every request, and every one answered with a 4xx or 5xx.
+async fn count(State(gw): State<Arc<Gateway>>, request: Request, next: Next) -> Response {
+    gw.requests.fetch_add(1, Ordering::Relaxed);
+    let response = next.run(request).await;
+    if response.status().is_client_error() || response.status().is_server_error() {
+        gw.errors.fetch_add(1, Ordering::Relaxed);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+    use vortex_client::testing::TestServer;
+
+    async fn call(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let request = axum::http::Request::builder().method(method).uri(uri).header(header::CONTENT_TYPE, "application/json");
+        let body = body.map_or_else(Body::empty, |b| Body::from(b.to_string()));
+        let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_rest_endpoints_translate_to_vbp() {
+        let server = TestServer::start("gateway_rest", 4);
+        let client = Client::connect(("127.0.0.1", server.port)).await.unwrap();
+        let app = router(Arc::new(Gateway::new(client)), 1 << 20);
+
+        let (status, _) = call(&app, "POST", "/v1/vectors", Some(json!({"id": 1, "vector": [1, 0, 0, 0], "metadata": {"region": "eu", "stock": 3}}))).await;
+        assert_eq!(status, StatusCode::OK);
+        let batch: Vec<Value> = (2..6).map(|id| json!({"id": id, "vector": [0, (id % 2) as f32, 1, 0]})).collect();
+        let (status, body) = call(&app, "POST", "/v1/vectors/batch", Some(json!({"vectors": batch}))).await;
+        assert_eq!((status, body), (StatusCode::OK, json!({"upserted": 4})));
+
+        let (status, body) = call(&app, "GET", "/v1/vectors/1", None).await;
+        assert_eq!((status, body), (StatusCode::OK, json!({"id": 1, "vector": [1.0, 0.0, 0.0, 0.0], "metadata": {"region": "eu", "stock": 3}})));
+        let (_, body) = call(&app, "GET", "/v1/vectors/2", None).await;
+        assert_eq!(body, json!({"id": 2, "vector": [0.0, 0.0, 1.0, 0.0]}));
+
+        let filter = json!({"and": [{"eq": {"key": "region", "value": "eu"}}, {"range": {"key": "stock", "min": 1, "max": 5}}]});
+        let (status, body) = call(&app, "POST", "/v1/search", Some(json!({"vector": [1, 0, 0, 0], "top_k": 3, "filter": filter}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["hits"].as_array().unwrap().iter().map(|h| h["id"].as_u64().unwrap()).collect::<Vec<_>>(), vec![1]);
+
+        let (status, _) = call(&app, "DELETE", "/v1/vectors/1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&app, "GET", "/v1/vectors/1", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Engine rejections keep their meaning over HTTP
+        let (status, body) = call(&app, "POST", "/v1/vectors", Some(json!({"id": 9, "vector": [1, 0]}))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body["error"].as_str().unwrap().contains("dimension mismatch"));
+        assert_eq!(body["retryable"], json!(false));
+        let (status, _) = call(&app, "GET", "/v1/vectors/2?collection=77", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(&app, "POST", "/v1/search", Some(json!({"vector": [1, 0, 0, 0], "top_k": 0}))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = call(&app, "GET", "/v1/stats", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["collections"][0], json!({"id": 0, "name": "default", "dimension": 4, "max_elements": 1000, "metric": "ip"}));
+        assert_eq!(body["gateway"], json!({"requests": 11, "errors": 4}));
+    }
+
+    #[test]
+    fn test_transient_engine_failures_invite_a_retry() {
+        for status in [STATUS_BUSY, STATUS_STORAGE] {
+            let error = ApiError::from(Error::Status { opcode: vortex_rpc::OP_UPSERT, status, message: None });
+            assert_eq!((error.status, error.retryable), (StatusCode::SERVICE_UNAVAILABLE, true));
+        }
+    }
+
+    #[test]
+    fn test_openapi_schema_is_valid_json() {
+        let schema: Value = serde_json::from_str(OPENAPI).unwrap();
+        for path in ["/v1/vectors", "/v1/vectors/batch", "/v1/vectors/{id}", "/v1/search", "/v1/stats"] {
+            assert!(schema["paths"][path].is_object(), "{} is not documented", path);
+        }
+    }
+}
@@ -0,0 +1,8 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Vendored protoc: building needs no system protobuf install
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::configure()
+        .btree_map(["."])
+        .compile_protos(&["proto/vortex.proto"], &["proto"])?;
+    Ok(())
+}
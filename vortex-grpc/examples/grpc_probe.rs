@@ -0,0 +1,43 @@
+use vortex_grpc::pb::vortex_client::VortexClient;
+use vortex_grpc::pb::{GetRequest, SearchRequest, UpsertRequest};
+
+/// gRPC Probe: streams upserts through `vortex-grpc`, then reads them back.
+/// Usage: grpc_probe [endpoint] [count] [dimension]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let endpoint = args.get(1).cloned().unwrap_or_else(|| "http://127.0.0.1:50051".to_string());
+    let count: u64 = args.get(2).and_then(|s| s.parse().ok()).unwrap_or(1000);
+    let dimension: usize = args.get(3).and_then(|s| s.parse().ok()).unwrap_or(128);
+
+    println!("Connecting to {}...", endpoint);
+    let mut stub = VortexClient::connect(endpoint).await?;
+
+    let vector_for = |id: u64| -> Vec<f32> { (0..dimension).map(|i| ((id as usize + i) % 7) as f32).collect() };
+    let records: Vec<UpsertRequest> = (0..count).map(|id| UpsertRequest { id, vector: vector_for(id), ..Default::default() }).collect();
+    let start = std::time::Instant::now();
+    let upserted = stub.upsert(tokio_stream_iter(records)).await?.into_inner().upserted;
+    println!("Streamed {} upserts in {:?}", upserted, start.elapsed());
+
+    let got = stub.get(GetRequest { id: count - 1, collection: None }).await?.into_inner();
+    println!("GET {} -> {} values (match: {})", got.id, got.vector.len(), got.vector == vector_for(count - 1));
+
+    let hits = stub.search(SearchRequest { vector: vector_for(0), top_k: 5, ..Default::default() }).await?.into_inner().hits;
+    for hit in hits {
+        println!("  id={} distance={}", hit.id, hit.distance);
+    }
+    Ok(())
+}
+
+/// Feeds `records` to a client-streaming call through a channel.
+fn tokio_stream_iter(records: Vec<UpsertRequest>) -> impl tonic::codegen::tokio_stream::Stream<Item = UpsertRequest> {
+    let (tx, rx) = tokio::sync::mpsc::channel(1024);
+    tokio::spawn(async move {
+        for record in records {
+            if tx.send(record).await.is_err() {
+                break;
+            }
+        }
+    });
+    tonic::codegen::tokio_stream::wrappers::ReceiverStream::new(rx)
+}
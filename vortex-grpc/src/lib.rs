@@ -0,0 +1,258 @@
+//! gRPC front end of the VORTEX engine, defined in `proto/vortex.proto`.
+//!
+//! Each call is translated to VBP through `vortex_client::Client`. A streamed `Upsert`
+//! is written in order on one pipelined connection, so the reactor folds it into
+//! `BatchAccumulator` group commits instead of one WAL write per record.
+
+use tonic::{Request, Response, Status, Streaming};
+use vortex_client::{Client, Error, Metadata, MetadataValue, SearchOptions};
+use vortex_rpc::{
+    STATUS_BAD_MAGIC, STATUS_BUSY, STATUS_CAPACITY_FULL, STATUS_FRAME_TOO_LARGE, STATUS_NOT_FOUND, STATUS_STORAGE,
+    STATUS_UNKNOWN_COLLECTION, STATUS_UNKNOWN_OPCODE, STATUS_UNSUPPORTED_VERSION,
+};
+
+pub mod pb {
+    tonic::include_proto!("vortex.v1");
+}
+
+use pb::vortex_server::Vortex;
+pub use pb::vortex_server::VortexServer;
+
+/// `top_k` used when a `SearchRequest` leaves it at zero.
+const DEFAULT_TOP_K: u32 = 10;
+
+/// Implements the `vortex.v1.Vortex` service on top of a VBP client.
+pub struct VortexService {
+    client: Client,
+}
+
+impl VortexService {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub fn into_server(self) -> VortexServer<Self> {
+        VortexServer::new(self)
+    }
+
+    fn client(&self, collection: Option<u32>) -> Client {
+        match collection {
+            Some(id) => self.client.collection(id),
+            None => self.client.clone(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Vortex for VortexService {
+    async fn upsert(&self, request: Request<Streaming<pb::UpsertRequest>>) -> Result<Response<pb::UpsertResponse>, Status> {
+        let mut records = request.into_inner();
+        let Some(first) = records.message().await? else {
+            return Ok(Response::new(pb::UpsertResponse { upserted: 0 }));
+        };
+        let collection = first.collection;
+        let mut stream = self.client(collection).upsert_stream().await.map_err(status)?;
+
+        let mut next = Some(first);
+        while let Some(record) = next {
+            let checked = if record.collection == collection {
+                metadata(record.metadata)
+            } else {
+                Err("All records of an Upsert stream must address the same collection".to_string())
+            };
+            let metadata = match checked {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    // Records already sent stay applied: settle them before refusing the rest
+                    let durable = stream.finish().await.map_err(status)?;
+                    return Err(Status::invalid_argument(format!("{}; the {} records before it are durable", e, durable)));
+                }
+            };
+            stream.send(record.id, &record.vector, metadata.as_ref()).await.map_err(status)?;
+            next = records.message().await?;
+        }
+        let upserted = stream.finish().await.map_err(status)?;
+        Ok(Response::new(pb::UpsertResponse { upserted: upserted as u64 }))
+    }
+
+    async fn search(&self, request: Request<pb::SearchRequest>) -> Result<Response<pb::SearchResponse>, Status> {
+        let request = request.into_inner();
+        let top_k = if request.top_k == 0 { DEFAULT_TOP_K } else { request.top_k };
+        let mut options = SearchOptions::new(top_k).with_ef_search(request.ef_search);
+        if let Some(filter) = request.filter {
+            options = options.with_filter(filter.try_into().map_err(Status::invalid_argument)?);
+        }
+        let hits = self.client(request.collection).search(&request.vector, &options).await.map_err(status)?;
+        let hits = hits.into_iter().map(|hit| pb::Hit { id: hit.id, distance: hit.distance }).collect();
+        Ok(Response::new(pb::SearchResponse { hits }))
+    }
+
+    async fn get(&self, request: Request<pb::GetRequest>) -> Result<Response<pb::GetResponse>, Status> {
+        let request = request.into_inner();
+        match self.client(request.collection).get(request.id).await.map_err(status)? {
+            Some(stored) => {
+                let metadata = stored.metadata.map_or_else(Default::default, |m| m.fields.into_iter().map(|(key, value)| (key, value.into())).collect());
+                Ok(Response::new(pb::GetResponse { id: request.id, vector: stored.vector, metadata }))
+            }
+            None => Err(Status::not_found(format!("Vector {} not found", request.id))),
+        }
+    }
+
+    async fn delete(&self, request: Request<pb::DeleteRequest>) -> Result<Response<pb::DeleteResponse>, Status> {
+        let request = request.into_inner();
+        self.client(request.collection).delete(request.id).await.map_err(status)?;
+        Ok(Response::new(pb::DeleteResponse {}))
+    }
+}
+
+/// Maps a VBP failure onto the closest gRPC status code. Only `Unavailable` and
+/// `DeadlineExceeded` invite a retry, matching `Error::is_retryable`.
+fn status(e: Error) -> Status {
+    let message = e.to_string();
+    match e {
+        Error::Status { status: STATUS_NOT_FOUND | STATUS_UNKNOWN_COLLECTION, .. } => Status::not_found(message),
+        Error::Status { status: STATUS_CAPACITY_FULL | STATUS_FRAME_TOO_LARGE, .. } => Status::resource_exhausted(message),
+        Error::Status { status: STATUS_BUSY | STATUS_STORAGE, .. } => Status::unavailable(message),
+        Error::Status { status: STATUS_UNKNOWN_OPCODE | STATUS_BAD_MAGIC | STATUS_UNSUPPORTED_VERSION, .. } => Status::internal(message),
+        Error::Status { .. } => Status::invalid_argument(message),
+        Error::Timeout => Status::deadline_exceeded(message),
+        Error::Io(_) | Error::Protocol(_) | Error::Closed => Status::unavailable(message),
+    }
+}
+
+/// Converts the metadata of an `UpsertRequest`. A value without a `kind` is refused, naming its key.
+fn metadata(fields: std::collections::BTreeMap<String, pb::MetadataValue>) -> Result<Option<Metadata>, String> {
+    if fields.is_empty() {
+        return Ok(None);
+    }
+    let fields = fields.into_iter().map(|(key, value)| {
+        let value = match value.kind {
+            Some(pb::metadata_value::Kind::Int(v)) => MetadataValue::Int(v),
+            Some(pb::metadata_value::Kind::Keyword(v)) => MetadataValue::Keyword(v),
+            Some(pb::metadata_value::Kind::Tags(tags)) => MetadataValue::Tags(tags.values),
+            None => return Err(format!("Metadata value of {:?} has no kind", key)),
+        };
+        Ok((key, value))
+    }).collect::<Result<_, _>>()?;
+    Ok(Some(Metadata { fields }))
+}
+
+impl From<MetadataValue> for pb::MetadataValue {
+    fn from(value: MetadataValue) -> Self {
+        let kind = match value {
+            MetadataValue::Int(v) => pb::metadata_value::Kind::Int(v),
+            MetadataValue::Keyword(v) => pb::metadata_value::Kind::Keyword(v),
+            MetadataValue::Tags(values) => pb::metadata_value::Kind::Tags(pb::Tags { values }),
+        };
+        Self { kind: Some(kind) }
+    }
+}
+
+impl TryFrom<pb::Filter> for vortex_client::Filter {
+    type Error = &'static str;
+
+    fn try_from(filter: pb::Filter) -> Result<Self, &'static str> {
+        use pb::filter::Op;
+        let children = |list: pb::FilterList| list.filters.into_iter().map(Self::try_from).collect::<Result<Vec<_>, _>>();
+        Ok(match filter.op.ok_or("Filter has no operator")? {
+            Op::Eq(eq) => Self::Eq { key: eq.key, value: eq.value },
+            Op::Range(range) => Self::Range { key: range.key, min: range.min, max: range.max },
+            Op::And(list) => Self::And(children(list)?),
+            Op::Or(list) => Self::Or(children(list)?),
+            Op::Not(child) => Self::Not(Box::new(Self::try_from(*child)?)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pb::vortex_client::VortexClient;
+    use tonic::Code;
+    use vortex_client::testing::{basis_vector, TestServer};
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    /// Small pseudo-random vector: distinct per ID, so the graph is not degenerate.
+    fn vector_for(id: u64) -> Vec<f32> {
+        (0..8).map(|i| ((id * 31 + i * 17) % 97) as f32 / 970.0).collect()
+    }
+
+    #[tokio::test]
+    async fn test_streamed_upserts_and_unary_calls() {
+        let server = TestServer::with_capacity("grpc_service", 8, 2000);
+        let client = Client::connect(("127.0.0.1", server.port)).await.unwrap();
+        let grpc_port = free_port();
+        tokio::spawn(tonic::transport::Server::builder()
+            .add_service(VortexService::new(client).into_server())
+            .serve(([127, 0, 0, 1], grpc_port).into()));
+
+        let mut stub = None;
+        for _ in 0..50 {
+            match VortexClient::connect(format!("http://127.0.0.1:{}", grpc_port)).await {
+                Ok(connected) => {
+                    stub = Some(connected);
+                    break;
+                }
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(20)).await,
+            }
+        }
+        let mut stub = stub.unwrap();
+
+        // 1000 records in one stream; the last write of ID 7 wins because order is kept
+        let mut records: Vec<pb::UpsertRequest> = (0..1000).map(|id| pb::UpsertRequest {
+            id,
+            vector: vector_for(id),
+            ..Default::default()
+        }).collect();
+        let replacement = basis_vector(8, 7);
+        records.push(pb::UpsertRequest {
+            id: 7,
+            vector: replacement.clone(),
+            metadata: [("region".to_string(), pb::MetadataValue { kind: Some(pb::metadata_value::Kind::Keyword("eu".into())) })].into(),
+            collection: None,
+        });
+        let upserted = stub.upsert(futures::stream::iter(records)).await.unwrap().into_inner();
+        assert_eq!(upserted.upserted, 1001);
+
+        let got = stub.get(pb::GetRequest { id: 999, collection: None }).await.unwrap().into_inner();
+        assert_eq!((got.vector, got.metadata.len()), (vector_for(999), 0));
+        let got = stub.get(pb::GetRequest { id: 7, collection: None }).await.unwrap().into_inner();
+        assert_eq!(got.vector, replacement);
+        assert_eq!(got.metadata["region"].kind, Some(pb::metadata_value::Kind::Keyword("eu".into())));
+
+        let region = pb::Filter { op: Some(pb::filter::Op::Eq(pb::Eq { key: "region".into(), value: "eu".into() })) };
+        let search = pb::SearchRequest { vector: replacement, top_k: 5, filter: Some(region), ..Default::default() };
+        let hits = stub.search(search).await.unwrap().into_inner().hits;
+        assert_eq!(hits.iter().map(|h| h.id).collect::<Vec<_>>(), vec![7]);
+
+        stub.delete(pb::DeleteRequest { id: 7, collection: None }).await.unwrap();
+        let missing = stub.get(pb::GetRequest { id: 7, collection: None }).await.unwrap_err();
+        assert_eq!(missing.code(), Code::NotFound);
+
+        // Engine rejections keep their meaning
+        let short = pb::UpsertRequest { id: 5000, vector: vec![1.0; 3], ..Default::default() };
+        let rejected = stub.upsert(futures::stream::iter([short])).await.unwrap_err();
+        assert_eq!(rejected.code(), Code::InvalidArgument);
+        let untyped = pb::UpsertRequest { id: 5001, vector: basis_vector(8, 0), metadata: [("region".to_string(), pb::MetadataValue { kind: None })].into(), collection: None };
+        let rejected = stub.upsert(futures::stream::iter([untyped])).await.unwrap_err();
+        assert_eq!((rejected.code(), rejected.message()), (Code::InvalidArgument, "Metadata value of \"region\" has no kind; the 0 records before it are durable"));
+        let unknown = stub.get(pb::GetRequest { id: 1, collection: Some(40) }).await.unwrap_err();
+        assert_eq!(unknown.code(), Code::NotFound);
+        let mixed = [
+            pb::UpsertRequest { id: 1, vector: basis_vector(8, 1), ..Default::default() },
+            pb::UpsertRequest { id: 2, vector: basis_vector(8, 2), collection: Some(3), ..Default::default() },
+        ];
+        let rejected = stub.upsert(futures::stream::iter(mixed)).await.unwrap_err();
+        assert_eq!(rejected.code(), Code::InvalidArgument);
+        assert!(rejected.message().ends_with("the 1 records before it are durable"));
+        let got = stub.get(pb::GetRequest { id: 1, collection: None }).await.unwrap().into_inner();
+        assert_eq!(got.vector, basis_vector(8, 1));
+
+        // A failed WAL write persisted nothing, so the caller may retry
+        let failed = Error::Status { opcode: vortex_rpc::OP_UPSERT, status: STATUS_STORAGE, message: None };
+        assert_eq!(status(failed).code(), Code::Unavailable);
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use log::info;
+use std::net::SocketAddr;
+use vortex_client::{Client, ClientConfig};
+use vortex_grpc::VortexService;
+
+/// VORTEX gRPC Front End: the `vortex.v1.Vortex` service translated to VBP
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Address the gRPC listener binds to
+    #[arg(short, long, default_value = "127.0.0.1:50051")]
+    listen: SocketAddr,
+
+    /// VBP address of the VORTEX server
+    #[arg(short, long, default_value = "127.0.0.1:9000")]
+    server: String,
+
+    /// VBP connections shared by all gRPC calls
+    #[arg(short, long, default_value_t = 8)]
+    connections: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+    let args = Args::parse();
+
+    let config = ClientConfig::default().with_connections(args.connections);
+    let client = Client::connect_with(args.server.as_str(), config).await
+        .with_context(|| format!("Failed to reach the VORTEX server at {}", args.server))?;
+
+    info!("gRPC front end listening on {} (VBP upstream {})", args.listen, args.server);
+    tonic::transport::Server::builder()
+        .add_service(VortexService::new(client).into_server())
+        .serve(args.listen)
+        .await
+        .context("gRPC server failed")?;
+    Ok(())
+}
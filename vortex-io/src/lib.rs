@@ -8,10 +8,12 @@ pub mod ring;
 pub mod memory;
 pub mod net;
 pub mod storage;
+pub mod wake;
 
 // Re-exports for easier access by vortex-core
 pub use ring::RingDriver as VortexRing;
 pub use memory::BufferPool;
 pub use net::VortexListener;
+pub use wake::Waker;
 pub use platform::lock_memory_pages as lock_all_memory;
 pub use platform::affinity;
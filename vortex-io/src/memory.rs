@@ -93,7 +93,7 @@ impl BufferPool {
     /// # Panics
     /// Panics if alignment is not a multiple of 4096 (Rule #2).
     pub fn new(page_count: usize, page_size: usize) -> Self {
-        if page_size % PAGE_SIZE != 0 {
+        if !page_size.is_multiple_of(PAGE_SIZE) {
             panic!("CRITICAL: BufferPool alignment violation. {} is not a multiple of {}.", page_size, PAGE_SIZE);
         }
 
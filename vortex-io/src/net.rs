@@ -1,9 +1,15 @@
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
 use io_uring::{opcode, types};
-use socket2::{Socket, Domain, Type, Protocol};
+use socket2::{Socket, Domain, Type, Protocol, TcpKeepalive};
 use log::info;
 
+/// Gap between keepalive probes once a connection has gone quiet.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+/// Unanswered probes before the kernel drops the connection.
+const KEEPALIVE_RETRIES: u32 = 6;
+
 /// VORTEX TCP Ingress Listener.
 /// Uses socket2 for safe hardware-level configuration (REUSEPORT, NODELAY).
 pub struct VortexListener {
@@ -46,6 +52,22 @@ impl VortexListener {
         Ok(Self { socket })
     }
 
+    /// Enables TCP keepalive, probing a connection after `idle` without traffic.
+    /// Accepted sockets inherit the setting, so half-open peers are eventually reset
+    /// and their pending reads fail.
+    ///
+    /// # Errors
+    /// Returns `std::io::Error` if the socket rejects the keepalive options.
+    pub fn set_keepalive(&self, idle: Duration) -> std::io::Result<()> {
+        let keepalive = TcpKeepalive::new()
+            .with_time(idle)
+            .with_interval(KEEPALIVE_INTERVAL)
+            .with_retries(KEEPALIVE_RETRIES);
+        self.socket.set_tcp_keepalive(&keepalive)?;
+        info!("VBP Ingress keepalive: probing after {:?} idle, every {:?}, {} probes.", idle, KEEPALIVE_INTERVAL, KEEPALIVE_RETRIES);
+        Ok(())
+    }
+
     /// Exposes the raw file descriptor for the io_uring submission queue.
     #[inline]
     pub fn as_raw_fd(&self) -> RawFd {
@@ -7,6 +7,12 @@ pub struct SystemTopology {
     available_ram: u64,
 }
 
+impl Default for SystemTopology {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SystemTopology {
     /// Detects the system's physical core and memory configuration.
     pub fn new() -> Self {
@@ -41,6 +41,13 @@ impl DirectFile {
         self._file.set_len(size)
     }
 
+    /// Blocking positioned write. `buf` must be 4KB aligned (O_DIRECT).
+    /// Reserved for rare control writes such as segment headers, never for the data path.
+    pub fn write_all_at(&self, buf: &[u8], offset: u64) -> std::io::Result<()> {
+        use std::os::unix::fs::FileExt;
+        self._file.write_all_at(buf, offset)
+    }
+
     pub fn write_sqe(&self, buf: *const u8, len: u32, offset: u64, user_data: u64) -> io_uring::squeue::Entry {
         opcode::Write::new(types::Fd(self.fd), buf, len)
             .offset(offset)
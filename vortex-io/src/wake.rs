@@ -0,0 +1,44 @@
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::Arc;
+
+/// Cross-thread wake-up for a reactor blocked on its ring.
+///
+/// Wraps an eventfd the reactor keeps a read posted on. `wake` completes that read
+/// from any thread, ending the reactor's `submit_and_wait`. Clones share the eventfd.
+#[derive(Clone)]
+pub struct Waker {
+    fd: Arc<OwnedFd>,
+}
+
+impl Waker {
+    /// Creates the eventfd.
+    ///
+    /// # Errors
+    /// Returns `std::io::Error` if the kernel refuses the eventfd.
+    pub fn new() -> std::io::Result<Self> {
+        // SAFETY: eventfd has no memory-safety preconditions; the result is checked below.
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: fd is a freshly created descriptor owned by nobody else.
+        Ok(Self { fd: Arc::new(unsafe { OwnedFd::from_raw_fd(fd) }) })
+    }
+
+    /// Wakes the reactor. Wakes before the reactor next waits are not lost: the
+    /// eventfd counter stays readable until its read completes.
+    pub fn wake(&self) {
+        let one = 1u64.to_ne_bytes();
+        // SAFETY: Writes 8 bytes from a live buffer to an eventfd this handle keeps open.
+        let written = unsafe { libc::write(self.fd.as_raw_fd(), one.as_ptr() as *const libc::c_void, one.len()) };
+        if written < 0 {
+            log::warn!("Reactor wake-up failed: {}", std::io::Error::last_os_error());
+        }
+    }
+}
+
+impl AsRawFd for Waker {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
@@ -0,0 +1,145 @@
+//! Validated view of inbound request frames.
+//!
+//! Fixed-layout prefixes are checked with `rkyv::check_archived_root` before any field
+//! is read, and every opcode payload is parsed into a `Command` borrowing the frame's
+//! vector bytes. A malformed or hostile frame yields the status to answer with, never
+//! an unchecked read.
+
+use crate::metadata::{self, Filter, Metadata};
+use crate::{
+    CollectionRef, CollectionSpec, CreateCollectionRequest, DeleteRequest, GetRequest, HelloRequest, SearchRequest, UpsertRequest,
+    MAX_SEARCH_EF, MAX_SEARCH_TOP_K, OP_CREATE_COLLECTION, OP_DELETE, OP_DROP_COLLECTION, OP_GET, OP_HELLO, OP_LIST_COLLECTIONS,
+    OP_SEARCH, OP_UPSERT, STATUS_DIMENSION_MISMATCH, STATUS_ERR, STATUS_UNKNOWN_OPCODE,
+};
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Infallible};
+use bytecheck::CheckBytes;
+
+/// Status and reason a frame is refused with.
+pub type Rejection = (u8, &'static str);
+
+/// Largest fixed-layout prefix on the wire (the 16-byte headers).
+const MAX_PREFIX: usize = 16;
+
+/// Staging buffer aligned for every archived prefix.
+#[repr(C, align(16))]
+struct Aligned([u8; MAX_PREFIX]);
+
+/// Validates the archived `T` at the start of `bytes` and copies it out.
+///
+/// Frames start at arbitrary offsets, so the prefix is staged in an aligned buffer
+/// first. Archives are little-endian (`archive_le`), matching the wire layout.
+///
+/// # Errors
+/// Returns an error if `bytes` is shorter than the archived `T` or fails `CheckBytes`.
+pub(crate) fn decode_prefix<T>(bytes: &[u8]) -> Result<T, &'static str>
+where
+    T: Archive,
+    T::Archived: for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, Infallible>,
+{
+    let size = std::mem::size_of::<T::Archived>();
+    if size > MAX_PREFIX || bytes.len() < size {
+        return Err("Bytes too short for the fixed-layout prefix");
+    }
+    let mut aligned = Aligned([0; MAX_PREFIX]);
+    aligned.0[..size].copy_from_slice(&bytes[..size]);
+    let archived = rkyv::check_archived_root::<T>(&aligned.0[..size]).map_err(|_| "Fixed-layout prefix failed validation")?;
+    archived.deserialize(&mut Infallible).map_err(|_| "Fixed-layout prefix failed validation")
+}
+
+/// An opcode payload that passed validation. Vector bytes borrow the frame.
+#[derive(Debug, PartialEq)]
+pub enum Command<'a> {
+    Upsert { request: UpsertRequest, vector: &'a [u8], metadata: Option<Metadata> },
+    Delete(DeleteRequest),
+    Get(GetRequest),
+    Search { request: SearchRequest, query: &'a [u8], filter: Option<Filter> },
+    CreateCollection { spec: CollectionSpec, name: &'a str },
+    ListCollections,
+    DropCollection(CollectionRef),
+    Hello(HelloRequest),
+}
+
+/// Parses the payload of an `opcode` frame. For data opcodes `body` starts after the
+/// `CollectionRef` prefix and `dimension` is the addressed collection's; other opcodes ignore it.
+///
+/// # Errors
+/// - `STATUS_DIMENSION_MISMATCH` if a vector or query is not `dimension` values long.
+/// - `STATUS_UNKNOWN_OPCODE` for opcodes outside the protocol.
+/// - `STATUS_ERR` for any other malformed payload or out-of-range search parameter.
+pub fn parse_command(opcode: u8, body: &[u8], dimension: usize) -> Result<Command<'_>, Rejection> {
+    let malformed = |e| (STATUS_ERR, e);
+    match opcode {
+        OP_UPSERT => {
+            check_vector_length(body, UpsertRequest::SIZE, dimension).map_err(|_| (STATUS_DIMENSION_MISMATCH, "Vector dimension mismatch"))?;
+            let (request, vector, metadata) = UpsertRequest::parse(body, dimension).map_err(malformed)?;
+            Ok(Command::Upsert { request, vector, metadata })
+        }
+        OP_DELETE => DeleteRequest::parse(body).map(Command::Delete).map_err(malformed),
+        OP_GET => GetRequest::parse(body).map(Command::Get).map_err(malformed),
+        OP_SEARCH => {
+            check_vector_length(body, SearchRequest::SIZE, dimension).map_err(|_| (STATUS_DIMENSION_MISMATCH, "Query dimension mismatch"))?;
+            let (request, query, filter) = SearchRequest::parse(body, dimension).map_err(malformed)?;
+            if request.top_k == 0 || request.top_k > MAX_SEARCH_TOP_K {
+                return Err((STATUS_ERR, "top_k out of range"));
+            }
+            if request.ef_search > MAX_SEARCH_EF {
+                return Err((STATUS_ERR, "ef_search out of range"));
+            }
+            Ok(Command::Search { request, query, filter })
+        }
+        OP_CREATE_COLLECTION => CreateCollectionRequest::parse(body).map(|(spec, name)| Command::CreateCollection { spec, name }).map_err(malformed),
+        OP_LIST_COLLECTIONS => Ok(Command::ListCollections),
+        OP_DROP_COLLECTION => CollectionRef::parse(body).map(Command::DropCollection).map_err(malformed),
+        OP_HELLO => HelloRequest::parse(body).map(Command::Hello).map_err(malformed),
+        _ => Err((STATUS_UNKNOWN_OPCODE, "Unknown opcode")),
+    }
+}
+
+/// Checks `body` holds a `prefix`-byte header and exactly `dimension` values, optionally
+/// followed by one trailing block. Anything else is a vector of the wrong dimension.
+fn check_vector_length(body: &[u8], prefix: usize, dimension: usize) -> Result<(), ()> {
+    let end = prefix + dimension * 4;
+    if body.len() < end || (body.len() > end && !metadata::is_block(&body[end..])) {
+        return Err(());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RequestHeader, OP_UPSERT, VBP_MAGIC};
+
+    #[test]
+    fn test_hostile_frames_are_rejected_with_a_status() {
+        // Headers are validated from any offset
+        let mut wire = vec![0xEEu8; 3];
+        wire.extend_from_slice(&RequestHeader::new(1, OP_GET, 8, 99).to_bytes());
+        let header = RequestHeader::parse(&wire[3..]).unwrap();
+        assert_eq!((header.magic, header.opcode, header.payload_len, header.request_id), (VBP_MAGIC, OP_GET, 8, 99));
+        assert!(RequestHeader::parse(&wire[3..18]).is_err());
+        assert!(RequestHeader::parse(&[0xFF; 16]).is_err());
+
+        let upsert = UpsertRequest { id: 7 }.encode(&[1.0, 2.0]);
+        match parse_command(OP_UPSERT, &upsert, 2).unwrap() {
+            Command::Upsert { request, vector, metadata } => assert_eq!((request.id, vector.len(), metadata), (7, 8, None)),
+            other => panic!("expected an upsert, got {:?}", other),
+        }
+        assert_eq!(parse_command(OP_UPSERT, &upsert, 3).unwrap_err().0, STATUS_DIMENSION_MISMATCH);
+        assert_eq!(parse_command(OP_UPSERT, &upsert[..5], 2).unwrap_err().0, STATUS_DIMENSION_MISMATCH);
+        assert_eq!(parse_command(OP_UPSERT, &upsert, 1).unwrap_err().0, STATUS_DIMENSION_MISMATCH);
+
+        // A trailer framed as a block but holding garbage is malformed, not a dimension error
+        let mut bad_metadata = upsert.clone();
+        bad_metadata.extend_from_slice(&[1, 0, 0, 0, 9]);
+        assert_eq!(parse_command(OP_UPSERT, &bad_metadata, 2).unwrap_err().0, STATUS_ERR);
+
+        let search = SearchRequest { top_k: 0, ef_search: 0 }.encode(&[1.0, 2.0]);
+        assert_eq!(parse_command(OP_SEARCH, &search, 2).unwrap_err(), (STATUS_ERR, "top_k out of range"));
+        assert_eq!(parse_command(OP_DELETE, &[1, 2, 3], 2).unwrap_err().0, STATUS_ERR);
+        assert_eq!(parse_command(OP_GET, &7u64.to_le_bytes(), 2).unwrap(), Command::Get(GetRequest { id: 7 }));
+        assert_eq!(parse_command(OP_CREATE_COLLECTION, &[0; 4], 0).unwrap_err().0, STATUS_ERR);
+        assert_eq!(parse_command(99, &[], 0).unwrap_err().0, STATUS_UNKNOWN_OPCODE);
+    }
+}
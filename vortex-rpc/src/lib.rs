@@ -1,21 +1,73 @@
 use rkyv::{Archive, Deserialize, Serialize};
 use bytecheck::CheckBytes;
 
+pub mod frame;
+pub mod metadata;
+pub use frame::{parse_command, Command, Rejection};
+pub use metadata::{Filter, Metadata, MetadataValue};
+use frame::decode_prefix;
+
 /// 'VX' in ASCII hex. Used to identify VORTEX Binary Protocol packets.
 pub const VBP_MAGIC: u16 = 0x5658;
 
+/// Protocol version 1: data payloads address the default collection.
+pub const VBP_VERSION_1: u8 = 1;
+
+/// Protocol version 2: data payloads start with a `CollectionRef`.
+pub const VBP_VERSION_2: u8 = 2;
+
+/// Oldest protocol version this build speaks.
+pub const VBP_VERSION_MIN: u8 = VBP_VERSION_1;
+
+/// Newest protocol version this build speaks.
+pub const VBP_VERSION_MAX: u8 = VBP_VERSION_2;
+
+/// ID of the implicit collection configured on the server command line.
+pub const DEFAULT_COLLECTION: u32 = 0;
+
 /// Opcode for inserting or updating a vector.
 pub const OP_UPSERT: u8 = 1;
 
+/// Opcode for deleting (tombstoning) a vector by ID.
+pub const OP_DELETE: u8 = 2;
+
+/// Opcode for fetching the stored vector for an ID.
+pub const OP_GET: u8 = 3;
+
 /// Opcode for searching nearest neighbors.
 pub const OP_SEARCH: u8 = 5;
 
+/// Admin opcode: create a named collection (`CollectionSpec` followed by the name).
+pub const OP_CREATE_COLLECTION: u8 = 16;
+
+/// Admin opcode: list collections (empty payload, responds with `CollectionInfo` entries).
+pub const OP_LIST_COLLECTIONS: u8 = 17;
+
+/// Admin opcode: drop a collection (`CollectionRef` payload).
+pub const OP_DROP_COLLECTION: u8 = 18;
+
+/// Handshake opcode: negotiate the protocol version (`HelloRequest` payload, `HelloResponse` answer).
+/// Accepted under any header version, since it runs before a version is agreed.
+pub const OP_HELLO: u8 = 32;
+
+/// Distance metric: negative inner product (smaller is closer).
+pub const METRIC_INNER_PRODUCT: u8 = 0;
+
+/// Distance metric: squared Euclidean distance.
+pub const METRIC_L2: u8 = 1;
+
+/// Distance metric: cosine distance (`1 - cos`).
+pub const METRIC_COSINE: u8 = 2;
+
+/// Maximum length of a collection name in bytes.
+pub const MAX_COLLECTION_NAME: usize = 64;
+
 /// The strict layout of the VORTEX Binary Protocol Header.
 /// 
 /// # Layout (C-Compatible)
 /// - `magic` (2 bytes): Must be `0x5658`.
-/// - `version` (1 byte): Protocol version (currently 1).
-/// - `opcode` (1 byte): Command type (1=Upsert, 5=Search).
+/// - `version` (1 byte): Protocol version (1, or 2 to address a collection).
+/// - `opcode` (1 byte): Command type (1=Upsert, 2=Delete, 3=Get, 5=Search, 16-18=Collection admin, 32=Hello).
 /// - `payload_len` (4 bytes): Length of the following payload body.
 /// - `request_id` (8 bytes): Client-generated correlation ID.
 /// 
@@ -34,33 +86,679 @@ pub struct RequestHeader {
     pub request_id: u64,
 }
 
-/// Safely casts a byte slice to a RequestHeader and validates the magic number.
+impl RequestHeader {
+    /// Size of the header on the wire.
+    pub const SIZE: usize = 16;
+
+    /// Builds the header of a request frame carrying `payload_len` bytes.
+    pub fn new(version: u8, opcode: u8, payload_len: u32, request_id: u64) -> Self {
+        Self { magic: VBP_MAGIC, version, opcode, payload_len, request_id }
+    }
+
+    /// Encodes the header in its little-endian wire layout, without pointer casts.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut out = [0u8; Self::SIZE];
+        out[0..2].copy_from_slice(&self.magic.to_le_bytes());
+        out[2] = self.version;
+        out[3] = self.opcode;
+        out[4..8].copy_from_slice(&self.payload_len.to_le_bytes());
+        out[8..16].copy_from_slice(&self.request_id.to_le_bytes());
+        out
+    }
+
+    /// Decodes and validates the header at the start of `bytes`, at any alignment.
+    /// The version is left to the caller, which may have negotiated it per connection.
+    ///
+    /// # Errors
+    /// Returns an error if the slice is too short or the magic number is invalid.
+    pub fn parse(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < Self::SIZE {
+            return Err("Packet too short for VBP Header");
+        }
+        let header: Self = decode_prefix(bytes)?;
+        if header.magic != VBP_MAGIC {
+            return Err("Invalid Magic Number");
+        }
+        Ok(header)
+    }
+}
+
+/// Decodes a RequestHeader and validates the magic number and version.
 ///
 /// # Errors
-/// Returns an error if the slice is too short or the magic number is invalid.
+/// Returns an error if the slice is too short, the magic number is invalid, or the
+/// version is outside `VBP_VERSION_MIN..=VBP_VERSION_MAX` (except for `OP_HELLO`).
+pub fn verify_header(bytes: &[u8]) -> Result<RequestHeader, &'static str> {
+    let header = RequestHeader::parse(bytes)?;
+    if header.opcode != OP_HELLO && !(VBP_VERSION_MIN..=VBP_VERSION_MAX).contains(&header.version) {
+        return Err("Unsupported protocol version");
+    }
+
+    Ok(header)
+}
+
+/// Payload of an `OP_HELLO` request: the range of protocol versions the client speaks.
 ///
-/// # Safety
-/// This function handles the unsafe pointer cast internally and verifies bounds.
-pub fn verify_header(bytes: &[u8]) -> Result<&RequestHeader, &'static str> {
-    if bytes.len() < std::mem::size_of::<RequestHeader>() {
-        return Err("Packet too short for VBP Header");
+/// # Layout
+/// - `min_version` (1 byte)
+/// - `max_version` (1 byte)
+/// - `reserved` (2 bytes): Must be zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HelloRequest {
+    pub min_version: u8,
+    pub max_version: u8,
+}
+
+impl HelloRequest {
+    /// Size of the payload in bytes.
+    pub const SIZE: usize = 4;
+
+    /// The range this build speaks.
+    pub fn current() -> Self {
+        Self { min_version: VBP_VERSION_MIN, max_version: VBP_VERSION_MAX }
     }
 
-    // SAFETY: We checked the length above. The struct is POD (Archive+Copy+C-Repr).
-    // The pointer cast is valid for reading raw bytes as the struct.
-    let header = unsafe { &*(bytes.as_ptr() as *const RequestHeader) };
+    /// Parses an `OP_HELLO` payload.
+    pub fn parse(payload: &[u8]) -> Result<Self, &'static str> {
+        if payload.len() < Self::SIZE {
+            return Err("Payload too short for HELLO");
+        }
+        Ok(Self { min_version: payload[0], max_version: payload[1] })
+    }
 
-    if header.magic != VBP_MAGIC {
-        return Err("Invalid Magic Number");
+    /// Encodes the `OP_HELLO` payload.
+    pub fn encode(&self) -> Vec<u8> {
+        vec![self.min_version, self.max_version, 0, 0]
     }
 
-    Ok(header)
+    /// Highest version both this request and `VBP_VERSION_MIN..=VBP_VERSION_MAX` cover, if any.
+    pub fn negotiate(&self) -> Option<u8> {
+        let version = self.max_version.min(VBP_VERSION_MAX);
+        (version >= self.min_version.max(VBP_VERSION_MIN)).then_some(version)
+    }
+}
+
+/// Answer to an `OP_HELLO`: the negotiated version and the server's limits.
+///
+/// # Layout (Little-Endian)
+/// - `version` (1 byte): Newest version the connection may use from now on. Older
+///   versions down to `VBP_VERSION_MIN` stay valid.
+/// - `opcode_count` (1 byte)
+/// - `reserved` (2 bytes): Must be zero.
+/// - `max_frame_size` (4 bytes): Largest request frame, header included, the server buffers.
+/// - `dimension` (4 bytes): Dimension of the default collection.
+/// - `opcodes` (`opcode_count` bytes): Every opcode the server serves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HelloResponse {
+    pub version: u8,
+    pub max_frame_size: u32,
+    pub dimension: u32,
+    pub opcodes: Vec<u8>,
+}
+
+impl HelloResponse {
+    /// Size of the fixed part preceding the opcode list.
+    pub const PREFIX_SIZE: usize = 12;
+
+    /// Encoded size of this response.
+    pub fn encoded_len(&self) -> usize {
+        Self::PREFIX_SIZE + self.opcodes.len()
+    }
+
+    /// Writes the response into the first `encoded_len()` bytes of `out`.
+    pub fn write_to(&self, out: &mut [u8]) {
+        out[0] = self.version;
+        out[1] = self.opcodes.len() as u8;
+        out[2..4].copy_from_slice(&[0, 0]);
+        out[4..8].copy_from_slice(&self.max_frame_size.to_le_bytes());
+        out[8..12].copy_from_slice(&self.dimension.to_le_bytes());
+        out[Self::PREFIX_SIZE..self.encoded_len()].copy_from_slice(&self.opcodes);
+    }
+
+    /// Decodes the payload of a `STATUS_OK` answer to `OP_HELLO`.
+    pub fn parse(payload: &[u8]) -> Result<Self, &'static str> {
+        if payload.len() < Self::PREFIX_SIZE {
+            return Err("Payload too short for HELLO response");
+        }
+        let end = Self::PREFIX_SIZE + payload[1] as usize;
+        if payload.len() < end {
+            return Err("Truncated HELLO opcode list");
+        }
+        Ok(Self {
+            version: payload[0],
+            max_frame_size: u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]),
+            dimension: u32::from_le_bytes([payload[8], payload[9], payload[10], payload[11]]),
+            opcodes: payload[Self::PREFIX_SIZE..end].to_vec(),
+        })
+    }
+
+    /// Whether the server serves `opcode`.
+    pub fn supports(&self, opcode: u8) -> bool {
+        self.opcodes.contains(&opcode)
+    }
+}
+
+/// Collection address carried at the start of every data-opcode payload in protocol version 2.
+///
+/// # Layout (Little-Endian)
+/// - `collection_id` (4 bytes): Target collection.
+/// - `reserved` (4 bytes): Must be zero.
+#[repr(C)]
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[archive_attr(derive(CheckBytes, Debug))]
+pub struct CollectionRef {
+    pub collection_id: u32,
+    pub reserved: u32,
+}
+
+impl CollectionRef {
+    /// Size of the prefix in bytes.
+    pub const SIZE: usize = 8;
+
+    /// Reads the collection address of a data-opcode payload sent with `version`.
+    /// Returns the collection ID and the number of prefix bytes to skip.
+    ///
+    /// # Errors
+    /// Returns an error for unknown versions or a payload shorter than the prefix.
+    pub fn split(version: u8, payload: &[u8]) -> Result<(u32, usize), &'static str> {
+        match version {
+            VBP_VERSION_1 => Ok((DEFAULT_COLLECTION, 0)),
+            VBP_VERSION_2 => Ok((Self::parse(payload)?.collection_id, Self::SIZE)),
+            _ => Err("Unsupported protocol version"),
+        }
+    }
+
+    /// Decodes the prefix from the first `CollectionRef::SIZE` bytes of `payload`.
+    pub fn parse(payload: &[u8]) -> Result<Self, &'static str> {
+        if payload.len() < Self::SIZE {
+            return Err("Payload too short for collection prefix");
+        }
+        decode_prefix(payload)
+    }
+
+    /// Encodes the prefix followed by `body`.
+    pub fn encode(&self, body: &[u8]) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(Self::SIZE + body.len());
+        payload.extend_from_slice(&self.collection_id.to_le_bytes());
+        payload.extend_from_slice(&self.reserved.to_le_bytes());
+        payload.extend_from_slice(body);
+        payload
+    }
+}
+
+/// Parameters of a collection, as sent in `OP_CREATE_COLLECTION` and returned by listings.
+/// Zero in `max_elements`, `m` or `ef_construction` selects the server default.
+///
+/// # Layout (Little-Endian)
+/// - `dimension` (4 bytes)
+/// - `max_elements` (4 bytes): Per-shard capacity.
+/// - `m` (2 bytes): HNSW links per node on upper layers (layer 0 uses `2 * m`).
+/// - `ef_construction` (2 bytes): HNSW build beam width.
+/// - `metric` (1 byte): One of the `METRIC_*` constants.
+/// - `reserved` (3 bytes): Must be zero.
+#[repr(C)]
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[archive_attr(derive(CheckBytes, Debug))]
+pub struct CollectionSpec {
+    pub dimension: u32,
+    pub max_elements: u32,
+    pub m: u16,
+    pub ef_construction: u16,
+    pub metric: u8,
+    pub reserved: [u8; 3],
+}
+
+impl CollectionSpec {
+    /// Size of the encoded spec in bytes.
+    pub const SIZE: usize = 16;
+
+    /// Decodes a spec from the first `CollectionSpec::SIZE` bytes of `bytes`.
+    pub fn parse(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < Self::SIZE {
+            return Err("Payload too short for collection spec");
+        }
+        decode_prefix(bytes)
+    }
+
+    /// Writes the spec into the first `CollectionSpec::SIZE` bytes of `out`.
+    pub fn write_to(&self, out: &mut [u8]) {
+        out[0..4].copy_from_slice(&self.dimension.to_le_bytes());
+        out[4..8].copy_from_slice(&self.max_elements.to_le_bytes());
+        out[8..10].copy_from_slice(&self.m.to_le_bytes());
+        out[10..12].copy_from_slice(&self.ef_construction.to_le_bytes());
+        out[12] = self.metric;
+        out[13..16].copy_from_slice(&self.reserved);
+    }
+}
+
+/// Checks that `name` is 1..=`MAX_COLLECTION_NAME` bytes of `[A-Za-z0-9_-]`.
+pub fn validate_collection_name(name: &[u8]) -> Result<&str, &'static str> {
+    if name.is_empty() || name.len() > MAX_COLLECTION_NAME {
+        return Err("Collection name length out of range");
+    }
+    if !name.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'_' || *b == b'-') {
+        return Err("Collection name must be [A-Za-z0-9_-]");
+    }
+    // All bytes are ASCII, so this never fails
+    std::str::from_utf8(name).map_err(|_| "Collection name is not UTF-8")
+}
+
+/// Payload of an `OP_CREATE_COLLECTION` request: a `CollectionSpec` followed by the name.
+pub struct CreateCollectionRequest;
+
+impl CreateCollectionRequest {
+    /// Parses the spec and validated name.
+    pub fn parse(payload: &[u8]) -> Result<(CollectionSpec, &str), &'static str> {
+        let spec = CollectionSpec::parse(payload)?;
+        let name = validate_collection_name(&payload[CollectionSpec::SIZE..])?;
+        Ok((spec, name))
+    }
+
+    /// Encodes a create request.
+    pub fn encode(spec: &CollectionSpec, name: &str) -> Vec<u8> {
+        let mut payload = vec![0u8; CollectionSpec::SIZE];
+        spec.write_to(&mut payload);
+        payload.extend_from_slice(name.as_bytes());
+        payload
+    }
+}
+
+/// One collection in an `OP_CREATE_COLLECTION` or `OP_LIST_COLLECTIONS` response.
+///
+/// # Layout (Little-Endian)
+/// - `id` (4 bytes)
+/// - `name_len` (4 bytes)
+/// - `spec` (`CollectionSpec::SIZE` bytes)
+/// - `name` (`name_len` bytes)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionInfo {
+    pub id: u32,
+    pub spec: CollectionSpec,
+    pub name: String,
+}
+
+impl CollectionInfo {
+    /// Size of the fixed part preceding the name.
+    pub const PREFIX_SIZE: usize = 8 + CollectionSpec::SIZE;
+
+    /// Encoded size of this entry.
+    pub fn encoded_len(&self) -> usize {
+        Self::PREFIX_SIZE + self.name.len()
+    }
+
+    /// Writes the entry into the first `encoded_len()` bytes of `out`.
+    pub fn write_to(&self, out: &mut [u8]) {
+        out[0..4].copy_from_slice(&self.id.to_le_bytes());
+        out[4..8].copy_from_slice(&(self.name.len() as u32).to_le_bytes());
+        self.spec.write_to(&mut out[8..Self::PREFIX_SIZE]);
+        out[Self::PREFIX_SIZE..self.encoded_len()].copy_from_slice(self.name.as_bytes());
+    }
+
+    /// Decodes a response payload made of back-to-back entries.
+    pub fn decode_all(mut payload: &[u8]) -> Result<Vec<Self>, &'static str> {
+        let mut entries = Vec::new();
+        while !payload.is_empty() {
+            if payload.len() < Self::PREFIX_SIZE {
+                return Err("Truncated collection entry");
+            }
+            let id = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+            let name_len = u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]) as usize;
+            let spec = CollectionSpec::parse(&payload[8..])?;
+            let end = Self::PREFIX_SIZE + name_len;
+            if payload.len() < end {
+                return Err("Truncated collection name");
+            }
+            let name = validate_collection_name(&payload[Self::PREFIX_SIZE..end])?.to_string();
+            entries.push(Self { id, spec, name });
+            payload = &payload[end..];
+        }
+        Ok(entries)
+    }
+}
+
+/// Fixed prefix of an `OP_UPSERT` payload.
+///
+/// # Layout (Little-Endian)
+/// - `id` (8 bytes): External vector ID.
+/// - `vector` (`dim * 4` bytes): The vector as `f32` values, immediately after the prefix.
+/// - `metadata` (optional): A `Metadata` block after the vector. `dim` is the collection dimension.
+#[repr(C)]
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[archive_attr(derive(CheckBytes, Debug))]
+pub struct UpsertRequest {
+    pub id: u64,
+}
+
+impl UpsertRequest {
+    /// Size of the fixed prefix in bytes.
+    pub const SIZE: usize = 8;
+
+    /// Splits an `OP_UPSERT` payload for a `dimension`-sized collection into its prefix,
+    /// the raw vector bytes and the optional metadata.
+    ///
+    /// # Errors
+    /// Returns an error if the payload is shorter than the prefix plus `dimension` values,
+    /// or the bytes after the vector are not a well-formed metadata block.
+    pub fn parse(payload: &[u8], dimension: usize) -> Result<(Self, &[u8], Option<Metadata>), &'static str> {
+        if payload.len() < Self::SIZE {
+            return Err("Upsert payload too short for vector ID");
+        }
+        if dimension == 0 || payload.len() < Self::SIZE + dimension * 4 {
+            return Err("Upsert vector shorter than the collection dimension");
+        }
+        let (vector, trailer) = payload[Self::SIZE..].split_at(dimension * 4);
+        let metadata = Metadata::parse_block(trailer)?;
+        Ok((decode_prefix(payload)?, vector, metadata))
+    }
+
+    /// Encodes a full `OP_UPSERT` payload (ID followed by the vector).
+    pub fn encode(&self, vector: &[f32]) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(Self::SIZE + vector.len() * 4);
+        payload.extend_from_slice(&self.id.to_le_bytes());
+        for value in vector {
+            payload.extend_from_slice(&value.to_le_bytes());
+        }
+        payload
+    }
+
+    /// Encodes a full `OP_UPSERT` payload with `metadata` attached to the vector.
+    pub fn encode_with_metadata(&self, vector: &[f32], metadata: &Metadata) -> Vec<u8> {
+        let mut payload = self.encode(vector);
+        metadata.encode_block(&mut payload);
+        payload
+    }
+}
+
+/// Payload of an `OP_DELETE` request.
+///
+/// # Layout (Little-Endian)
+/// - `id` (8 bytes): External vector ID to erase.
+#[repr(C)]
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[archive_attr(derive(CheckBytes, Debug))]
+pub struct DeleteRequest {
+    pub id: u64,
+}
+
+impl DeleteRequest {
+    /// Size of the payload in bytes.
+    pub const SIZE: usize = 8;
+
+    /// Parses an `OP_DELETE` payload.
+    ///
+    /// # Errors
+    /// Returns an error if the payload is not exactly `DeleteRequest::SIZE` bytes.
+    pub fn parse(payload: &[u8]) -> Result<Self, &'static str> {
+        if payload.len() != Self::SIZE {
+            return Err("Delete payload must be exactly one vector ID");
+        }
+        decode_prefix(payload)
+    }
+
+    /// Encodes the `OP_DELETE` payload.
+    pub fn encode(&self) -> Vec<u8> {
+        self.id.to_le_bytes().to_vec()
+    }
+}
+
+/// Payload of an `OP_GET` request.
+///
+/// The response payload is a `StoredVector`, or empty with `STATUS_NOT_FOUND` if the ID
+/// is not live.
+///
+/// # Layout (Little-Endian)
+/// - `id` (8 bytes): External vector ID to fetch.
+#[repr(C)]
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[archive_attr(derive(CheckBytes, Debug))]
+pub struct GetRequest {
+    pub id: u64,
+}
+
+impl GetRequest {
+    /// Size of the payload in bytes.
+    pub const SIZE: usize = 8;
+
+    /// Parses an `OP_GET` payload.
+    ///
+    /// # Errors
+    /// Returns an error if the payload is not exactly `GetRequest::SIZE` bytes.
+    pub fn parse(payload: &[u8]) -> Result<Self, &'static str> {
+        if payload.len() != Self::SIZE {
+            return Err("Get payload must be exactly one vector ID");
+        }
+        decode_prefix(payload)
+    }
+
+    /// Encodes the `OP_GET` payload.
+    pub fn encode(&self) -> Vec<u8> {
+        self.id.to_le_bytes().to_vec()
+    }
+}
+
+/// Payload of a `STATUS_OK` answer to `OP_GET`: the stored full-precision vector and its metadata.
+///
+/// # Layout (Little-Endian)
+/// - Protocol v1: the vector as `f32` values. Metadata is not returned.
+/// - Protocol v2: `dimension` (4 bytes, u32), the vector as `dimension` `f32` values,
+///   then the metadata block if the vector has one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredVector {
+    pub vector: Vec<f32>,
+    pub metadata: Option<Metadata>,
+}
+
+impl StoredVector {
+    /// Encoded size of the v2 prefix in front of the vector.
+    pub const DIMENSION_SIZE: usize = 4;
+
+    /// Decodes the answer to an `OP_GET` sent with protocol `version`.
+    ///
+    /// # Errors
+    /// Returns an error if the vector is cut short or the metadata block is malformed.
+    pub fn parse(payload: &[u8], version: u8) -> Result<Self, &'static str> {
+        let (vector, trailer) = if version >= VBP_VERSION_2 {
+            if payload.len() < Self::DIMENSION_SIZE {
+                return Err("Payload too short for a stored vector");
+            }
+            let (prefix, rest) = payload.split_at(Self::DIMENSION_SIZE);
+            let bytes = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize * 4;
+            if rest.len() < bytes {
+                return Err("Stored vector is shorter than its dimension");
+            }
+            rest.split_at(bytes)
+        } else {
+            if !payload.len().is_multiple_of(4) {
+                return Err("Vector payload is not a whole number of floats");
+            }
+            (payload, &[][..])
+        };
+        let mut values = vec![0.0f32; vector.len() / 4];
+        decode_f32s(vector, &mut values);
+        Ok(Self { vector: values, metadata: Metadata::parse_block(trailer)? })
+    }
+}
+
+/// Encodes `values` as little-endian bytes into `out`.
+/// Returns the number of values written (bounded by both lengths).
+pub fn encode_f32s(values: &[f32], out: &mut [u8]) -> usize {
+    let mut count = 0;
+    for (src, dst) in values.iter().zip(out.chunks_exact_mut(4)) {
+        dst.copy_from_slice(&src.to_le_bytes());
+        count += 1;
+    }
+    count
+}
+
+/// Decodes little-endian `f32` values from `bytes` into `out`.
+/// Returns the number of values written (bounded by both lengths).
+pub fn decode_f32s(bytes: &[u8], out: &mut [f32]) -> usize {
+    let mut count = 0;
+    for (dst, src) in out.iter_mut().zip(bytes.chunks_exact(4)) {
+        *dst = f32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        count += 1;
+    }
+    count
+}
+
+/// Upper bound on the vector dimension a server can be configured with.
+/// Keeps an upsert frame and an `OP_GET` response (16 + 4096 * 4 bytes) inside one 64KB page.
+pub const MAX_DIMENSION: u32 = 4096;
+
+/// Upper bound on `SearchRequest::top_k` accepted by the server.
+/// Keeps a full result set (16 + 1024 * 16 bytes) well inside one 64KB response page.
+pub const MAX_SEARCH_TOP_K: u32 = 1024;
+
+/// Upper bound on `SearchRequest::ef_search` accepted by the server.
+/// Bounds the candidate beam (and the latency of a single query) per shard.
+pub const MAX_SEARCH_EF: u32 = 4096;
+
+/// Fixed prefix of an `OP_SEARCH` payload.
+///
+/// # Layout (Little-Endian)
+/// - `top_k` (4 bytes): Number of nearest neighbors requested.
+/// - `ef_search` (4 bytes): Beam width of the search. 0 selects the collection default;
+///   values below `top_k` are raised to `top_k`. Keeps the query vector 8-byte aligned.
+/// - `query` (`dim * 4` bytes): The query vector as `f32` values, immediately after the prefix.
+/// - `filter` (optional): A `Filter` block after the query. Only matching vectors are returned.
+#[repr(C)]
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[archive_attr(derive(CheckBytes, Debug))]
+pub struct SearchRequest {
+    pub top_k: u32,
+    pub ef_search: u32,
+}
+
+impl SearchRequest {
+    /// Size of the fixed prefix in bytes.
+    pub const SIZE: usize = 8;
+
+    /// Splits an `OP_SEARCH` payload for a `dimension`-sized collection into its prefix,
+    /// the raw query bytes and the optional filter.
+    ///
+    /// # Errors
+    /// Returns an error if the payload is shorter than the prefix plus `dimension` values,
+    /// or the bytes after the query are not a well-formed filter block.
+    pub fn parse(payload: &[u8], dimension: usize) -> Result<(Self, &[u8], Option<Filter>), &'static str> {
+        if payload.len() < Self::SIZE {
+            return Err("Search payload too short for prefix");
+        }
+        if dimension == 0 || payload.len() < Self::SIZE + dimension * 4 {
+            return Err("Search query shorter than the collection dimension");
+        }
+        let (query, trailer) = payload[Self::SIZE..].split_at(dimension * 4);
+        let filter = Filter::parse_block(trailer)?;
+        Ok((decode_prefix(payload)?, query, filter))
+    }
+
+    /// Encodes a full `OP_SEARCH` payload (prefix followed by the query vector).
+    pub fn encode(&self, query: &[f32]) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(Self::SIZE + query.len() * 4);
+        payload.extend_from_slice(&self.top_k.to_le_bytes());
+        payload.extend_from_slice(&self.ef_search.to_le_bytes());
+        for value in query {
+            payload.extend_from_slice(&value.to_le_bytes());
+        }
+        payload
+    }
+
+    /// Encodes a full `OP_SEARCH` payload restricted to vectors matching `filter`.
+    pub fn encode_with_filter(&self, query: &[f32], filter: &Filter) -> Vec<u8> {
+        let mut payload = self.encode(query);
+        filter.encode_block(&mut payload);
+        payload
+    }
+}
+
+/// One entry of an `OP_SEARCH` response payload.
+///
+/// The response payload is a packed array of `payload_len / SearchHit::SIZE` hits,
+/// ordered from nearest to farthest.
+///
+/// # Layout (Little-Endian)
+/// - `id` (8 bytes): External vector ID.
+/// - `distance` (4 bytes): Distance under the index metric (lower is closer).
+/// - `reserved` (4 bytes): Zero. Pads each hit to 16 bytes.
+#[repr(C)]
+#[derive(Archive, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[archive_attr(derive(CheckBytes, Debug))]
+pub struct SearchHit {
+    pub id: u64,
+    pub distance: f32,
+    pub reserved: u32,
+}
+
+impl SearchHit {
+    /// Size of one encoded hit in bytes.
+    pub const SIZE: usize = 16;
+
+    /// Writes this hit into the first `SearchHit::SIZE` bytes of `out`.
+    ///
+    /// # Panics
+    /// Panics if `out` is shorter than `SearchHit::SIZE`.
+    pub fn write_to(&self, out: &mut [u8]) {
+        out[0..8].copy_from_slice(&self.id.to_le_bytes());
+        out[8..12].copy_from_slice(&self.distance.to_le_bytes());
+        out[12..16].copy_from_slice(&self.reserved.to_le_bytes());
+    }
+
+    /// Decodes every hit from an `OP_SEARCH` response payload.
+    ///
+    /// # Errors
+    /// Returns an error if the payload is not a whole number of hits.
+    pub fn decode_all(payload: &[u8]) -> Result<Vec<Self>, &'static str> {
+        if !payload.len().is_multiple_of(Self::SIZE) {
+            return Err("Search response is not a whole number of hits");
+        }
+        payload.chunks_exact(Self::SIZE).map(decode_prefix).collect()
+    }
 }
 
 /// Response Status: Success
 pub const STATUS_OK: u8 = 0;
-/// Response Status: General Error
+/// Response Status: Malformed request, or a failure no other status describes
 pub const STATUS_ERR: u8 = 1;
+/// Response Status: The requested ID is not present
+pub const STATUS_NOT_FOUND: u8 = 2;
+/// Response Status: Vector length does not match the server's configured dimension
+pub const STATUS_DIMENSION_MISMATCH: u8 = 3;
+/// Response Status: The addressed collection does not exist
+pub const STATUS_UNKNOWN_COLLECTION: u8 = 4;
+/// Response Status: The collection holds `max_elements` live vectors; new IDs are refused
+pub const STATUS_CAPACITY_FULL: u8 = 5;
+/// Response Status: The frame is larger than the server can buffer
+pub const STATUS_FRAME_TOO_LARGE: u8 = 6;
+/// Response Status: The opcode is not part of the protocol
+pub const STATUS_UNKNOWN_OPCODE: u8 = 7;
+/// Response Status: The stream lost framing. The server closes the connection after this response.
+pub const STATUS_BAD_MAGIC: u8 = 8;
+/// Response Status: The server is temporarily overloaded; the same request may succeed later
+pub const STATUS_BUSY: u8 = 9;
+/// Response Status: The header version is one the server (or this connection, after `OP_HELLO`) does not speak
+pub const STATUS_UNSUPPORTED_VERSION: u8 = 10;
+/// Response Status: The WAL write failed and nothing was persisted; the same request may succeed later
+pub const STATUS_STORAGE: u8 = 11;
+
+/// Upper bound on the UTF-8 message an error response may carry as its payload.
+/// Responses to `OP_GET` and `OP_SEARCH` only carry data when the status is `STATUS_OK`.
+pub const MAX_STATUS_MESSAGE: usize = 256;
+
+/// Whether a request answered with `status` may succeed if sent again unchanged.
+/// Every other non-OK status is fatal for that request.
+pub fn is_retryable(status: u8) -> bool {
+    matches!(status, STATUS_BUSY | STATUS_STORAGE)
+}
+
+/// Decodes the message carried by an error response, if it has one.
+///
+/// # Errors
+/// Returns an error if the payload is not valid UTF-8.
+pub fn status_message(payload: &[u8]) -> Result<Option<&str>, &'static str> {
+    if payload.is_empty() {
+        return Ok(None);
+    }
+    std::str::from_utf8(payload).map(Some).map_err(|_| "Status message is not UTF-8")
+}
 
 /// The strict layout of the VORTEX Binary Protocol Response Header.
 /// Matches RequestHeader size (16 bytes) for symmetry.
@@ -75,3 +773,177 @@ pub struct ResponseHeader {
     pub payload_len: u32,
     pub request_id: u64,
 }
+
+impl ResponseHeader {
+    /// Size of the header on the wire.
+    pub const SIZE: usize = 16;
+
+    /// Encodes the header in its little-endian wire layout, without pointer casts.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut out = [0u8; Self::SIZE];
+        out[0..2].copy_from_slice(&self.magic.to_le_bytes());
+        out[2] = self.status;
+        out[3] = self.opcode;
+        out[4..8].copy_from_slice(&self.payload_len.to_le_bytes());
+        out[8..16].copy_from_slice(&self.request_id.to_le_bytes());
+        out
+    }
+
+    /// Decodes a response header from the first `ResponseHeader::SIZE` bytes of `bytes`.
+    ///
+    /// # Errors
+    /// Returns an error if the slice is too short or the magic number is invalid.
+    pub fn parse(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < Self::SIZE {
+            return Err("Packet too short for VBP Header");
+        }
+        let header: Self = decode_prefix(bytes)?;
+        if header.magic != VBP_MAGIC {
+            return Err("Invalid Magic Number");
+        }
+        Ok(header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_payload_round_trip() {
+        let request = SearchRequest { top_k: 7, ef_search: 200 };
+        let payload = request.encode(&[0.5, -1.0, 2.0]);
+        let (parsed, query, filter) = SearchRequest::parse(&payload, 3).unwrap();
+        assert_eq!(parsed, request);
+        assert_eq!(query.len(), 12);
+        assert_eq!(f32::from_le_bytes(query[4..8].try_into().unwrap()), -1.0);
+        assert_eq!(filter, None);
+
+        assert!(SearchRequest::parse(&payload[..4], 3).is_err());
+        assert!(SearchRequest::parse(&payload[..10], 3).is_err());
+        assert!(SearchRequest::parse(&payload, 2).is_err());
+
+        let region = Filter::eq("region", "eu");
+        let filtered = request.encode_with_filter(&[0.5, -1.0, 2.0], &region);
+        assert_eq!(SearchRequest::parse(&filtered, 3).unwrap().2, Some(region));
+
+        let hits = [
+            SearchHit { id: 42, distance: -3.5, reserved: 0 },
+            SearchHit { id: u64::MAX, distance: 0.25, reserved: 0 },
+        ];
+        let mut encoded = vec![0u8; hits.len() * SearchHit::SIZE];
+        for (hit, out) in hits.iter().zip(encoded.chunks_exact_mut(SearchHit::SIZE)) {
+            hit.write_to(out);
+        }
+        assert_eq!(SearchHit::decode_all(&encoded).unwrap(), hits);
+        assert!(SearchHit::decode_all(&encoded[..20]).is_err());
+    }
+
+    #[test]
+    fn test_hello_negotiates_the_highest_common_version() {
+        assert_eq!(HelloRequest::current().negotiate(), Some(VBP_VERSION_MAX));
+        assert_eq!(HelloRequest { min_version: 1, max_version: 9 }.negotiate(), Some(VBP_VERSION_MAX));
+        assert_eq!(HelloRequest { min_version: 0, max_version: 1 }.negotiate(), Some(VBP_VERSION_1));
+        assert_eq!(HelloRequest { min_version: VBP_VERSION_MAX + 1, max_version: 9 }.negotiate(), None);
+        assert_eq!(HelloRequest::parse(&HelloRequest::current().encode()).unwrap(), HelloRequest::current());
+
+        let hello = HelloResponse { version: 2, max_frame_size: 65536, dimension: 384, opcodes: vec![OP_UPSERT, OP_SEARCH, OP_HELLO] };
+        let mut encoded = vec![0u8; hello.encoded_len()];
+        hello.write_to(&mut encoded);
+        let parsed = HelloResponse::parse(&encoded).unwrap();
+        assert_eq!(parsed, hello);
+        assert!(parsed.supports(OP_SEARCH) && !parsed.supports(OP_GET));
+        assert!(HelloResponse::parse(&encoded[..13]).is_err());
+    }
+
+    #[test]
+    fn test_only_transient_statuses_are_retryable() {
+        assert!(is_retryable(STATUS_BUSY) && is_retryable(STATUS_STORAGE));
+        assert!(!is_retryable(STATUS_ERR) && !is_retryable(STATUS_CAPACITY_FULL));
+    }
+
+    #[test]
+    fn test_headers_encode_in_wire_layout() {
+        let request = RequestHeader::new(VBP_VERSION_2, OP_SEARCH, 0x0102_0304, 0x0A0B_0C0D_0E0F_1011);
+        let bytes = request.to_bytes();
+        assert_eq!(&bytes[..4], &[0x58, 0x56, VBP_VERSION_2, OP_SEARCH]);
+        let parsed = verify_header(&bytes).unwrap();
+        assert_eq!((parsed.payload_len, parsed.request_id), (0x0102_0304, 0x0A0B_0C0D_0E0F_1011));
+        assert!(verify_header(&RequestHeader::new(VBP_VERSION_MAX + 1, OP_GET, 0, 1).to_bytes()).is_err());
+        assert!(verify_header(&RequestHeader::new(0, OP_HELLO, 4, 1).to_bytes()).is_ok());
+
+        let response = ResponseHeader { magic: VBP_MAGIC, status: STATUS_NOT_FOUND, opcode: OP_GET, payload_len: 12, request_id: 77 };
+        let parsed = ResponseHeader::parse(&response.to_bytes()).unwrap();
+        assert_eq!((parsed.status, parsed.opcode, parsed.payload_len, parsed.request_id), (STATUS_NOT_FOUND, OP_GET, 12, 77));
+        assert!(ResponseHeader::parse(&response.to_bytes()[..15]).is_err());
+        assert!(ResponseHeader::parse(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_upsert_payload_round_trip() {
+        let payload = UpsertRequest { id: 9 }.encode(&[1.5, 2.5]);
+        let (parsed, vector, metadata) = UpsertRequest::parse(&payload, 2).unwrap();
+        assert_eq!(parsed.id, 9);
+        assert_eq!(metadata, None);
+
+        let mut out = [0.0f32; 4];
+        assert_eq!(decode_f32s(vector, &mut out), 2);
+        assert_eq!(&out[..2], &[1.5, 2.5]);
+
+        assert!(UpsertRequest::parse(&payload[..8], 2).is_err());
+        assert!(UpsertRequest::parse(&payload[..11], 2).is_err());
+        // A longer vector is not a metadata block
+        assert!(UpsertRequest::parse(&payload, 1).is_err());
+
+        let tags = Metadata::new().keyword("region", "eu").int("stock", 3);
+        let payload = UpsertRequest { id: 9 }.encode_with_metadata(&[1.5, 2.5], &tags);
+        assert_eq!(UpsertRequest::parse(&payload, 2).unwrap().2, Some(tags));
+    }
+
+    #[test]
+    fn test_stored_vector_layouts() {
+        let mut vector = [0u8; 8];
+        encode_f32s(&[1.5, 2.5], &mut vector);
+        let v1 = StoredVector::parse(&vector, VBP_VERSION_1).unwrap();
+        assert_eq!((v1.vector, v1.metadata), (vec![1.5, 2.5], None));
+        assert!(StoredVector::parse(&vector[..7], VBP_VERSION_1).is_err());
+
+        let tags = Metadata::new().keyword("region", "eu");
+        let mut payload = 2u32.to_le_bytes().to_vec();
+        payload.extend_from_slice(&vector);
+        assert_eq!(StoredVector::parse(&payload, VBP_VERSION_2).unwrap().metadata, None);
+        tags.encode_block(&mut payload);
+        let v2 = StoredVector::parse(&payload, VBP_VERSION_2).unwrap();
+        assert_eq!((v2.vector, v2.metadata), (vec![1.5, 2.5], Some(tags)));
+        assert!(StoredVector::parse(&payload[..10], VBP_VERSION_2).is_err());
+        assert!(StoredVector::parse(&payload[..payload.len() - 1], VBP_VERSION_2).is_err());
+    }
+
+    #[test]
+    fn test_collection_payload_round_trip() {
+        let spec = CollectionSpec { dimension: 384, max_elements: 5000, m: 8, ef_construction: 64, metric: METRIC_INNER_PRODUCT, reserved: [0; 3] };
+        let payload = CreateCollectionRequest::encode(&spec, "text-v2");
+        assert_eq!(CreateCollectionRequest::parse(&payload).unwrap(), (spec, "text-v2"));
+        assert!(CreateCollectionRequest::parse(&payload[..CollectionSpec::SIZE]).is_err());
+        assert!(CreateCollectionRequest::parse(&CreateCollectionRequest::encode(&spec, "bad name")).is_err());
+
+        let entries = [
+            CollectionInfo { id: 0, spec, name: "default".to_string() },
+            CollectionInfo { id: 3, spec, name: "img".to_string() },
+        ];
+        let mut encoded = vec![0u8; entries.iter().map(|e| e.encoded_len()).sum()];
+        let mut offset = 0;
+        for entry in &entries {
+            entry.write_to(&mut encoded[offset..]);
+            offset += entry.encoded_len();
+        }
+        assert_eq!(CollectionInfo::decode_all(&encoded).unwrap(), entries);
+        assert!(CollectionInfo::decode_all(&encoded[..encoded.len() - 1]).is_err());
+
+        let prefixed = CollectionRef { collection_id: 3, reserved: 0 }.encode(&[9, 9]);
+        assert_eq!(CollectionRef::split(VBP_VERSION_2, &prefixed).unwrap(), (3, CollectionRef::SIZE));
+        assert_eq!(CollectionRef::split(VBP_VERSION_1, &prefixed).unwrap(), (DEFAULT_COLLECTION, 0));
+        assert!(CollectionRef::split(VBP_VERSION_2, &prefixed[..4]).is_err());
+        assert!(CollectionRef::split(9, &prefixed).is_err());
+    }
+}
@@ -0,0 +1,397 @@
+//! Typed per-vector metadata and the filter expressions evaluated against it.
+//!
+//! Both travel as optional trailing blocks: metadata after the vector of an `OP_UPSERT`,
+//! a filter after the query of an `OP_SEARCH`. A block is `[len u32][body]`, so the
+//! server can tell a well-formed trailer from a vector of the wrong dimension.
+
+/// Upper bound on the encoded metadata block of one vector (including its length prefix).
+pub const MAX_METADATA_SIZE: usize = 1024;
+
+/// Upper bound on the encoded filter block of one search (including its length prefix).
+pub const MAX_FILTER_SIZE: usize = 1024;
+
+/// Maximum nesting of `And` / `Or` / `Not` in a filter. Bounds recursion on the reactor thread.
+pub const MAX_FILTER_DEPTH: usize = 8;
+
+/// Maximum length of a metadata key in bytes.
+pub const MAX_METADATA_KEY: usize = 64;
+
+const KIND_INT: u8 = 1;
+const KIND_KEYWORD: u8 = 2;
+const KIND_TAGS: u8 = 3;
+
+const FILTER_EQ: u8 = 1;
+const FILTER_RANGE: u8 = 2;
+const FILTER_AND: u8 = 3;
+const FILTER_OR: u8 = 4;
+const FILTER_NOT: u8 = 5;
+
+/// One typed metadata value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataValue {
+    /// Signed integer, matched by `Filter::Range`.
+    Int(i64),
+    /// Single string, matched by `Filter::Eq`.
+    Keyword(String),
+    /// Set of strings. `Filter::Eq` matches if any tag equals the value.
+    Tags(Vec<String>),
+}
+
+/// Key/value payload attached to a vector.
+///
+/// # Layout (Little-Endian, per field)
+/// - `key_len` (1 byte), `key` (`key_len` bytes, UTF-8)
+/// - `kind` (1 byte): 1=Int, 2=Keyword, 3=Tags
+/// - Int: `value` (8 bytes, i64)
+/// - Keyword: `len` (2 bytes), `value` (`len` bytes)
+/// - Tags: `count` (2 bytes), then `count` times `len` (2 bytes) + `tag` (`len` bytes)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub fields: Vec<(String, MetadataValue)>,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an integer field.
+    pub fn int(mut self, key: &str, value: i64) -> Self {
+        self.fields.push((key.to_string(), MetadataValue::Int(value)));
+        self
+    }
+
+    /// Adds a keyword field.
+    pub fn keyword(mut self, key: &str, value: &str) -> Self {
+        self.fields.push((key.to_string(), MetadataValue::Keyword(value.to_string())));
+        self
+    }
+
+    /// Adds a tag-set field.
+    pub fn tags(mut self, key: &str, tags: &[&str]) -> Self {
+        self.fields.push((key.to_string(), MetadataValue::Tags(tags.iter().map(|t| t.to_string()).collect())));
+        self
+    }
+
+    /// Returns the first value stored under `key`.
+    pub fn get(&self, key: &str) -> Option<&MetadataValue> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Appends this metadata as a length-prefixed block.
+    pub fn encode_block(&self, out: &mut Vec<u8>) {
+        let start = begin_block(out);
+        for (key, value) in &self.fields {
+            write_key(out, key);
+            match value {
+                MetadataValue::Int(v) => {
+                    out.push(KIND_INT);
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+                MetadataValue::Keyword(s) => {
+                    out.push(KIND_KEYWORD);
+                    write_str16(out, s);
+                }
+                MetadataValue::Tags(tags) => {
+                    out.push(KIND_TAGS);
+                    out.extend_from_slice(&(tags.len() as u16).to_le_bytes());
+                    for tag in tags {
+                        write_str16(out, tag);
+                    }
+                }
+            }
+        }
+        end_block(out, start);
+    }
+
+    /// Decodes the trailing metadata block of an upsert. An empty trailer means no metadata.
+    ///
+    /// # Errors
+    /// Returns an error if the block is oversized, its length prefix does not cover the
+    /// trailer exactly, or a field is malformed.
+    pub fn parse_block(trailer: &[u8]) -> Result<Option<Self>, &'static str> {
+        if trailer.is_empty() {
+            return Ok(None);
+        }
+        if trailer.len() > MAX_METADATA_SIZE {
+            return Err("Metadata block too large");
+        }
+        let mut reader = Reader::new(block_body(trailer)?);
+        let mut fields = Vec::new();
+        while !reader.is_empty() {
+            let key = reader.key()?;
+            let value = match reader.u8()? {
+                KIND_INT => MetadataValue::Int(reader.i64()?),
+                KIND_KEYWORD => MetadataValue::Keyword(reader.str16()?),
+                KIND_TAGS => {
+                    let count = reader.u16()?;
+                    let mut tags = Vec::with_capacity(count as usize);
+                    for _ in 0..count {
+                        tags.push(reader.str16()?);
+                    }
+                    MetadataValue::Tags(tags)
+                }
+                _ => return Err("Unknown metadata value kind"),
+            };
+            fields.push((key, value));
+        }
+        Ok(Some(Self { fields }))
+    }
+}
+
+/// Boolean expression over `Metadata`, applied while the index is traversed.
+///
+/// # Layout (Little-Endian, prefix notation)
+/// - `op` (1 byte): 1=Eq, 2=Range, 3=And, 4=Or, 5=Not
+/// - Eq: `key_len` (1 byte), `key`, `len` (2 bytes), `value`
+/// - Range: `key_len` (1 byte), `key`, `min` (8 bytes, i64), `max` (8 bytes, i64), inclusive
+/// - And / Or: `count` (1 byte), then `count` child expressions
+/// - Not: one child expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Keyword equals `value`, or tag set contains it.
+    Eq { key: String, value: String },
+    /// Integer within `min..=max`.
+    Range { key: String, min: i64, max: i64 },
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn eq(key: &str, value: &str) -> Self {
+        Self::Eq { key: key.to_string(), value: value.to_string() }
+    }
+
+    pub fn range(key: &str, min: i64, max: i64) -> Self {
+        Self::Range { key: key.to_string(), min, max }
+    }
+
+    /// Evaluates the expression. Vectors without metadata behave as if every key were absent.
+    pub fn matches(&self, metadata: Option<&Metadata>) -> bool {
+        match self {
+            Self::Eq { key, value } => match metadata.and_then(|m| m.get(key)) {
+                Some(MetadataValue::Keyword(s)) => s == value,
+                Some(MetadataValue::Tags(tags)) => tags.iter().any(|t| t == value),
+                _ => false,
+            },
+            Self::Range { key, min, max } => match metadata.and_then(|m| m.get(key)) {
+                Some(MetadataValue::Int(v)) => (*min..=*max).contains(v),
+                _ => false,
+            },
+            Self::And(children) => children.iter().all(|c| c.matches(metadata)),
+            Self::Or(children) => children.iter().any(|c| c.matches(metadata)),
+            Self::Not(child) => !child.matches(metadata),
+        }
+    }
+
+    /// Appends this filter as a length-prefixed block.
+    pub fn encode_block(&self, out: &mut Vec<u8>) {
+        let start = begin_block(out);
+        self.encode_expr(out);
+        end_block(out, start);
+    }
+
+    fn encode_expr(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Eq { key, value } => {
+                out.push(FILTER_EQ);
+                write_key(out, key);
+                write_str16(out, value);
+            }
+            Self::Range { key, min, max } => {
+                out.push(FILTER_RANGE);
+                write_key(out, key);
+                out.extend_from_slice(&min.to_le_bytes());
+                out.extend_from_slice(&max.to_le_bytes());
+            }
+            Self::And(children) | Self::Or(children) => {
+                out.push(if matches!(self, Self::And(_)) { FILTER_AND } else { FILTER_OR });
+                out.push(children.len() as u8);
+                for child in children {
+                    child.encode_expr(out);
+                }
+            }
+            Self::Not(child) => {
+                out.push(FILTER_NOT);
+                child.encode_expr(out);
+            }
+        }
+    }
+
+    /// Decodes the trailing filter block of a search. An empty trailer means no filter.
+    ///
+    /// # Errors
+    /// Returns an error if the block is oversized, nested deeper than `MAX_FILTER_DEPTH`,
+    /// or does not hold exactly one well-formed expression.
+    pub fn parse_block(trailer: &[u8]) -> Result<Option<Self>, &'static str> {
+        if trailer.is_empty() {
+            return Ok(None);
+        }
+        if trailer.len() > MAX_FILTER_SIZE {
+            return Err("Filter block too large");
+        }
+        let mut reader = Reader::new(block_body(trailer)?);
+        let filter = Self::parse_expr(&mut reader, 0)?;
+        if !reader.is_empty() {
+            return Err("Trailing bytes after filter expression");
+        }
+        Ok(Some(filter))
+    }
+
+    fn parse_expr(reader: &mut Reader, depth: usize) -> Result<Self, &'static str> {
+        if depth >= MAX_FILTER_DEPTH {
+            return Err("Filter nested too deeply");
+        }
+        match reader.u8()? {
+            FILTER_EQ => Ok(Self::Eq { key: reader.key()?, value: reader.str16()? }),
+            FILTER_RANGE => Ok(Self::Range { key: reader.key()?, min: reader.i64()?, max: reader.i64()? }),
+            op @ (FILTER_AND | FILTER_OR) => {
+                let count = reader.u8()?;
+                let mut children = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    children.push(Self::parse_expr(reader, depth + 1)?);
+                }
+                Ok(if op == FILTER_AND { Self::And(children) } else { Self::Or(children) })
+            }
+            FILTER_NOT => Ok(Self::Not(Box::new(Self::parse_expr(reader, depth + 1)?))),
+            _ => Err("Unknown filter operator"),
+        }
+    }
+}
+
+/// Returns true if `trailer` is framed as exactly one `[len u32][body]` block.
+/// A vector or query longer than the collection dimension almost never is.
+pub fn is_block(trailer: &[u8]) -> bool {
+    block_body(trailer).is_ok()
+}
+
+/// Reserves the length prefix of a block and returns where it starts.
+fn begin_block(out: &mut Vec<u8>) -> usize {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    start
+}
+
+/// Back-fills the length prefix reserved by `begin_block`.
+fn end_block(out: &mut [u8], start: usize) {
+    let len = (out.len() - start - 4) as u32;
+    out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+}
+
+/// Returns the body of a `[len u32][body]` block that must span all of `trailer`.
+fn block_body(trailer: &[u8]) -> Result<&[u8], &'static str> {
+    if trailer.len() < 4 {
+        return Err("Block too short for length prefix");
+    }
+    let len = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) as usize;
+    if len != trailer.len() - 4 {
+        return Err("Block length does not match payload");
+    }
+    Ok(&trailer[4..])
+}
+
+fn write_key(out: &mut Vec<u8>, key: &str) {
+    out.push(key.len() as u8);
+    out.extend_from_slice(key.as_bytes());
+}
+
+fn write_str16(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u16).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// Bounds-checked cursor over a block body.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], &'static str> {
+        if self.bytes.len() < n {
+            return Err("Truncated block");
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, &'static str> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn i64(&mut self) -> Result<i64, &'static str> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(i64::from_le_bytes(b))
+    }
+
+    fn utf8(&mut self, len: usize) -> Result<String, &'static str> {
+        std::str::from_utf8(self.take(len)?).map(str::to_string).map_err(|_| "Block string is not UTF-8")
+    }
+
+    fn key(&mut self) -> Result<String, &'static str> {
+        let len = self.u8()? as usize;
+        if len == 0 || len > MAX_METADATA_KEY {
+            return Err("Metadata key length out of range");
+        }
+        self.utf8(len)
+    }
+
+    fn str16(&mut self) -> Result<String, &'static str> {
+        let len = self.u16()? as usize;
+        self.utf8(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_and_filter_round_trip() {
+        let item = Metadata::new().int("stock", 12).keyword("region", "eu-west").tags("labels", &["sale", "new"]);
+        let mut block = Vec::new();
+        item.encode_block(&mut block);
+        assert_eq!(Metadata::parse_block(&block).unwrap(), Some(item.clone()));
+        assert_eq!(Metadata::parse_block(&[]).unwrap(), None);
+        assert!(Metadata::parse_block(&block[..block.len() - 1]).is_err());
+
+        let in_stock_eu = Filter::And(vec![
+            Filter::range("stock", 1, i64::MAX),
+            Filter::eq("region", "eu-west"),
+            Filter::Not(Box::new(Filter::eq("labels", "discontinued"))),
+        ]);
+        let mut block = Vec::new();
+        in_stock_eu.encode_block(&mut block);
+        assert_eq!(Filter::parse_block(&block).unwrap(), Some(in_stock_eu.clone()));
+
+        assert!(in_stock_eu.matches(Some(&item)));
+        assert!(!in_stock_eu.matches(Some(&Metadata::new().int("stock", 0).keyword("region", "eu-west"))));
+        assert!(!in_stock_eu.matches(None));
+        assert!(Filter::eq("labels", "new").matches(Some(&item)));
+        assert!(Filter::Not(Box::new(Filter::eq("region", "us"))).matches(None));
+
+        // Nesting is bounded so a hostile filter cannot exhaust the reactor stack
+        let mut deep = Filter::eq("a", "b");
+        for _ in 0..MAX_FILTER_DEPTH {
+            deep = Filter::Not(Box::new(deep));
+        }
+        let mut block = Vec::new();
+        deep.encode_block(&mut block);
+        assert_eq!(Filter::parse_block(&block).unwrap_err(), "Filter nested too deeply");
+    }
+}
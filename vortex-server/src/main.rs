@@ -30,6 +30,43 @@ struct Args {
     /// Max vectors per shard (overrides adaptive scaling)
     #[arg(short, long)]
     capacity: Option<usize>,
+
+    /// Vector dimension. Fixed for the lifetime of the data directory.
+    #[arg(long, default_value_t = 128, value_parser = clap::value_parser!(u32).range(1..=vortex_rpc::MAX_DIMENSION as i64))]
+    dimension: u32,
+
+    /// Distance metric of the default collection (ip, l2, cosine). Fixed for the lifetime of the data directory.
+    #[arg(long, default_value_t = vortex_core::index::distance::Metric::InnerProduct)]
+    metric: vortex_core::index::distance::Metric,
+
+    /// WAL growth in MB (per shard and collection) between index snapshots
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u64).range(1..))]
+    snapshot_interval_mb: u64,
+
+    /// Size in MB of each WAL segment file. Segments covered by a snapshot are deleted.
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u64).range(1..))]
+    wal_segment_mb: u64,
+
+    /// Concurrent client connections per shard. Each pins 128 KB of I/O buffers; further clients get STATUS_BUSY.
+    #[arg(long, default_value_t = vortex_core::reactor::DEFAULT_MAX_CONNECTIONS as u32, value_parser = clap::value_parser!(u32).range(1..=65536))]
+    max_connections: u32,
+
+    /// Seconds a connection may stay silent between requests before it is closed (0 disables)
+    #[arg(long, default_value_t = vortex_core::reactor::DEFAULT_IDLE_TIMEOUT.as_secs())]
+    idle_timeout_secs: u64,
+
+    /// Seconds a client has to finish sending a frame it started (0 disables)
+    #[arg(long, default_value_t = vortex_core::reactor::DEFAULT_READ_DEADLINE.as_secs())]
+    read_deadline_secs: u64,
+
+    /// Seconds of silence before TCP keepalive probes a connection (0 disables)
+    #[arg(long, default_value_t = vortex_core::reactor::DEFAULT_KEEPALIVE.as_secs())]
+    keepalive_secs: u64,
+}
+
+/// Maps a seconds option to a duration, 0 meaning "off".
+fn optional_secs(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
 }
 
 fn main() -> Result<()> {
@@ -38,7 +75,12 @@ fn main() -> Result<()> {
     let args = Args::parse();
     
     info!("Starting VORTEX Server v{}", env!("CARGO_PKG_VERSION"));
-    info!("Configuration: Port={}, StorageDir={}", args.port, args.dir);
+    info!("Configuration: Port={}, StorageDir={}, Dimension={}, Metric={}", args.port, args.dir, args.dimension, args.metric);
+
+    // Refuse to start before touching the hardware if the data was written with another dimension or metric
+    let dimension = args.dimension as usize;
+    vortex_core::storage::manifest::Manifest::open(&args.dir, dimension, args.metric)
+        .context("Storage directory does not match the configured dimension or metric")?;
 
     // 1. Lock Memory (Standard Rule 4) - MUST BE FIRST
     // Rule I: Unwrap allowed at startup
@@ -65,7 +107,15 @@ fn main() -> Result<()> {
 
     // 5. Initialize Milestone 6 Shard Proxy (The Brain)
     info!("Phase 4: initializing Shard Proxy (Capacity: {}/shard)...", max_elements);
-    let proxy = Arc::new(vortex_core::proxy::ShardProxy::new(num_shards, max_elements, args.dir));
+    let catalog = vortex_core::catalog::Catalog::open(&args.dir, dimension, args.metric, max_elements)
+        .context("Failed to load collection catalog")?;
+    let proxy = Arc::new(vortex_core::proxy::ShardProxy::new(num_shards, Arc::new(catalog))
+        .with_snapshot_interval(args.snapshot_interval_mb * 1024 * 1024)
+        .with_wal_segment_size(args.wal_segment_mb * 1024 * 1024)
+        .with_max_connections(args.max_connections as usize)
+        .with_idle_timeout(optional_secs(args.idle_timeout_secs))
+        .with_read_deadline(optional_secs(args.read_deadline_secs))
+        .with_keepalive(optional_secs(args.keepalive_secs)));
     
     // 5. Setup Graceful Shutdown (Signal Handler)
     info!("Phase 5: registering signal handlers...");